@@ -172,14 +172,10 @@ impl PlayerView {
                     self.judge.commit(t, tj, event.line_id, event.note_id, 0.);
                     match tj {
                         TJ::Perfect => {
-                            res.with_model(line_tr * note.object.now(res), |res| {
-                                res.emit_at_origin(note.rotation(line), res.res_pack.info.fx_perfect())
-                            });
+                            res.with_model(line_tr * note.object.now(res), |res| res.emit_at_origin(note.rotation(line), res.fx_perfect()));
                         }
                         TJ::Good => {
-                            res.with_model(line_tr * note.object.now(res), |res| {
-                                res.emit_at_origin(note.rotation(line), res.res_pack.info.fx_good())
-                            });
+                            res.with_model(line_tr * note.object.now(res), |res| res.emit_at_origin(note.rotation(line), res.fx_good()));
                         }
                         TJ::Bad => {
                             self.bad_notes.push(BadNote {
@@ -1,2 +1,19 @@
 
 label = CALIBRATION
+
+auto = Auto Calibrate
+auto-title = Auto Calibration
+auto-not-enough = Not enough data yet. Play at least { $plays } charts with this audio output first.
+auto-content = Suggested offset: { $offset }ms (currently { $current }ms)
+  Estimated from { $hits } hits in your last { $plays } plays, give or take { $error }ms.
+  Confidence: { $confidence }
+auto-confidence-low = Low, play a few more charts for a better estimate
+auto-confidence-medium = Medium
+auto-confidence-high = High
+auto-cancel = Cancel
+auto-apply = Apply
+auto-apply-device = Apply to This Device Only
+
+device-shared = Output: { $device }
+device-own = Output: { $device } (own offset)
+use-shared = Use Shared Offset
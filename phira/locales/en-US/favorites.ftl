@@ -35,6 +35,15 @@ sync-from-cloud = Sync from cloud
 sync-confirm = Syncing may overwrite data. Continue?
 sync-outdated = Cloud data is updated by other devices since last sync, sync anyway? (Overwrite cloud data)
 synced = Sync complete
+sync-pending-done = Offline changes to favorites synced
+sync-conflict = Sync conflict
+sync-conflict-content = "{ $name }" was changed both here and on another device:
+  { $conflicts }
+sync-conflict-name = Renamed to "{ $local }" here, but "{ $remote }" in cloud
+sync-conflict-order = Reordered both here and in cloud
+sync-conflict-later = Later
+sync-conflict-keep-server = Keep cloud
+sync-conflict-keep-mine = Keep mine
 upload-to-cloud = Upload to cloud
 uploaded = Uploaded
 delete-from-cloud = Delete from cloud
@@ -1,6 +1,12 @@
 
 info-fail = Failed to load info.
 invalid-chart = Invalid chart.
+pack-empty = The chart pack contains no charts.
+
+manifest-verify-failed = The chart archive is incomplete or has been modified.
+manifest-invalid = The export manifest is corrupted.
+manifest-missing-files = Missing files: { $files }
+manifest-modified-files = Modified files: { $files }
 
 importing = Importing...
 import-success = Imported successfully.
@@ -16,6 +22,20 @@ batch-import-downloaded-skipped = Charts skipped due to already downloaded: { $c
 batch-import-failed = Batch import failed
 batch-import-failed-chart = Batch import failed: { $chart }
 
+bulk-import-empty = No chart archives found.
+bulk-import-scan-failed = Failed to scan for charts
+bulk-import-duplicates = Already in library:
+bulk-import-failures = Failed:
+
 warning = Warning
 warning-new-speed-event = This chart uses the speed event easing introduced in RPE 1.7.0. For compatibility reasons, Phira does not enable support for this event by default. If you want to enable it, please check "New Speed Tween" in the chart information.
 warning-attach-ui = This chart uses UI attachment. Recent versions of Phira introduced a fix for UI attachment that may cause issues with charts that rely on the old behavior. If you encounter any problems, please uncheck "Attach UI Fix" in the chart information.
+warning-rpe-unsupported = This chart uses RPE { $version } features that Phira does not render yet, so it may look different than in RPE:
+warning-rpe-unknown = This chart has fields Phira does not recognize (RPE { $version }):
+rpe-scope-chart = chart
+rpe-scope-meta = metadata
+rpe-scope-line = judge line #{ $id }
+rpe-scope-events = { $count } events of judge line #{ $id }, first #{ $first }
+rpe-scope-notes = { $count } notes of judge line #{ $id }, first #{ $first }
+rpe-fields-more = …and { $count } more
+warning-shader = The shaders of this chart's effects have problems that may stop them from loading on some devices:
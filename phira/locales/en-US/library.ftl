@@ -12,6 +12,8 @@ not-opened = Not available yet.
 failed-to-load-online = Failed to load online charts.
 
 import = Import
+import-chart = Import Chart
+import-folder = Import Folder
 
 offline-mode = All online functionality is disabled due to Offline Mode (settings).
 
@@ -45,3 +47,5 @@ multi-select-all = Select All
 multi-select-invert = Invert Selection
 
 fav-created = Favorites created
+
+search-local-only = Only words, tags and #id are searched online; other filters apply to local charts only
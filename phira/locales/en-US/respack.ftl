@@ -12,3 +12,26 @@ info-content =
 
 cant-delete-builtin = The built-in respack can't be deleted.
 deleted = Successfully deleted.
+
+validate-failed = Failed to check the respack.
+problems = { $count ->
+    [one] Found a problem in the respack
+   *[other] Found { $count } problems in the respack
+}
+problem-missing = Missing { $file }
+problem-info = Invalid info.yml: { $error }
+problem-image = Cannot read { $file }: { $error }
+problem-atlas = { $file } is { $height } pixels high, leaving no room for the body after a tail of { $tail } and a head of { $head }
+problem-hit-fx = { $file } ({ $width }×{ $height }) cannot be split into { $columns }×{ $rows } frames
+problem-frames = { $file } is { $width } pixels wide, which cannot be split into { $frames } frames
+problem-font = Cannot read { $file }: { $error }
+problem-particles = Invalid particle preset: { $error }
+problem-audio = Cannot play { $file }: { $error }
+problem-audio-ignored = { $file } is not in a supported format (ogg, wav or mp3), so the default sound is used
+no-problems = No problems found.
+
+preview-failed = Failed to load the preview.
+
+dev-mode-on = Dev mode on: the respack reloads when its folder changes.
+dev-mode-off = Dev mode off.
+dev-mode-builtin = Dev mode needs an imported respack.
@@ -19,6 +19,14 @@ item-mp-sub = Enable multiplayer functionality.
 item-mp-addr = Multiplayer Server
 item-mp-addr-sub = Connect to a custom multiplayer server.
 item-mp-addr-invalid = Invalid server address.
+item-quality = Render Quality
+item-quality-sub = Sets MSAA, particles and shader effects together.
+quality-low = Low
+quality-medium = Medium
+quality-high = High
+quality-custom = Custom
+item-auto-quality = Lower Quality When Lagging
+item-auto-quality-sub = Turn off MSAA, particles and then effects during play when frames stay slow.
 item-lowq = Low Resolution Mode
 item-lowq-sub = Lower the quality of the UI, increasing peformance.
 item-clear-cache = Clear Cache
@@ -40,6 +48,12 @@ item-music = Music Volume
 item-sfx = SFX Volume
 item-bgm = BGM Volume
 item-cali = Adjust Offset
+item-visual-offset = Visual Offset
+item-visual-offset-sub = Delay the notes on screen without changing the judgement.
+item-hitsound-scheduling = Schedule Hitsounds
+item-hitsound-scheduling-sub = Time the hitsounds of notes hit early to make up for audio latency.
+item-hitsound-latency = Device Latency
+item-hitsound-latency-sub = Extra latency of the audio device, e.g. Bluetooth headphones.
 item-preferred-sample-rate = Preferred Sample Rate
 preferred-sample-rate-default = System Default
 item-audio-buffer-size = Audio Buffer Size
@@ -56,15 +70,44 @@ item-opt = Chart Optimization
 item-opt-sub = Significantly increase peformance while playing. (If unintended behavior arises, disable this.)
 item-use-keyboard = Use Keyboard
 item-use-keyboard-sub = Enable keyboard input for gameplay. Scores cannot be uploaded when enabled.
+item-input-profile = Input Profile
+item-input-profile-sub = Keys and gamepad buttons used for playing. Tap a binding below to change it.
+binding-tap = Tap
+binding-hold = Hold
+binding-flick = Flick { $direction ->
+    [up] up
+    [down] down
+    [left] left
+    *[right] right
+  }
+binding-zone = { $action } (zone { $zone })
+binding-press = Press…
+item-reset-bindings = Reset Bindings
+reset-bindings = Reset
 item-prefer-reduced-motion = Prefer Reduced Motion
 item-prefer-reduced-motion-sub = Reduce animations and visual effects
 item-speed = Speed
+item-speed-pitch = Pitch at Other Speeds
+item-speed-pitch-sub = Whether the music keeps its pitch when played faster or slower.
+speed-pitch-keep = Keep Pitch
+speed-pitch-follow = Nightcore / Daycore
 item-note-size = Note Size
+item-note-outline = Note Outline
+item-note-outline-sub = Draw a dark outline around notes.
+item-note-shapes = Note Shapes
+item-note-shapes-sub = Mark each kind of note with its own shape.
+item-fx-palette = Hit Effect Colors
+item-fx-palette-sub = Colors of hit effects and the AP/FC indicator.
+palette-pack = Resource Pack
+palette-colorblind = Colorblind Safe
+palette-high-contrast = High Contrast
 
 item-chart-debug = Show Line ID
 item-chart-debug-sub = Display the IDs and orientation of lines.
 item-touch-debug = Show Touch Points
 item-touch-debug-sub = Display user touch points.
+item-profiler = Frame Profiler
+item-profiler-sub = Show where frame time goes and save a trace after playing.
 
 load-cali-failed = Failed to load calibration audio.
 
@@ -42,6 +42,7 @@ cancel-not-saved = Chart hasn't been saved yet, would you like to exit now?
 upload-not-saved = Chart hasn't been saved yet, would you like to save it now?
 upload-login-first = Please login first.
 upload-builtin = Built-in charts cannot be uploaded.
+upload-pack = Charts in a chart pack cannot be uploaded.
 upload-rules = Chart Upload Rules
 upload-rules-content =
     Before uploading, you need to confirm that:
@@ -133,3 +134,11 @@ fav-added = Added to favorites (long press to manage)
 still-loading = Still loading...
 export = Export
 exporting = Exporting...
+
+personal = Personal Settings
+personal-sub = Only apply to this chart on this device, and are kept when the chart is downloaded again.
+personal-offset = Offset (ms)
+personal-speed = Speed
+personal-note-scale = Note Size
+personal-dim = Background Dim
+personal-reset = Reset
@@ -1,2 +1,19 @@
 
 label = 延迟校准
+
+auto = 自动校准
+auto-title = 自动校准
+auto-not-enough = 数据不足，请先使用当前音频输出游玩至少 { $plays } 张谱面
+auto-content = 建议延迟：{ $offset }ms（当前 { $current }ms）
+  根据最近 { $plays } 次游玩中的 { $hits } 次判定估计，误差约 { $error }ms
+  可信度：{ $confidence }
+auto-confidence-low = 低，多玩几张谱面可以得到更准确的结果
+auto-confidence-medium = 中
+auto-confidence-high = 高
+auto-cancel = 取消
+auto-apply = 应用
+auto-apply-device = 仅应用于此设备
+
+device-shared = 输出设备：{ $device }
+device-own = 输出设备：{ $device }（独立延迟）
+use-shared = 使用通用延迟
@@ -35,6 +35,15 @@ sync-from-cloud = 从云端同步
 sync-confirm = 同步可能会覆盖数据，是否继续？
 sync-outdated = 云端数据自上次同步后已被其他设备更新，是否仍要同步（覆盖云端数据）？
 synced = 同步完成
+sync-pending-done = 收藏夹的离线修改已同步
+sync-conflict = 同步冲突
+sync-conflict-content = 「{ $name }」在本设备和其他设备上都被修改了：
+  { $conflicts }
+sync-conflict-name = 本地重命名为「{ $local }」，云端为「{ $remote }」
+sync-conflict-order = 本地和云端都调整了顺序
+sync-conflict-later = 稍后
+sync-conflict-keep-server = 保留云端
+sync-conflict-keep-mine = 保留本地
 upload-to-cloud = 上传到云端
 uploaded = 已上传
 delete-from-cloud = 从云端删除
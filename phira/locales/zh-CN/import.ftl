@@ -1,6 +1,12 @@
 
 info-fail = 加载谱面信息失败
 invalid-chart = 无效的谱面
+pack-empty = 谱面包中没有谱面
+
+manifest-verify-failed = 谱面压缩包不完整或已被修改
+manifest-invalid = 导出清单已损坏
+manifest-missing-files = 缺少文件：{ $files }
+manifest-modified-files = 文件已被修改：{ $files }
 
 importing = 导入中
 import-success = 导入成功
@@ -16,6 +22,20 @@ batch-import-downloaded-skipped = 已经下载过而跳过的谱面：{ $charts
 batch-import-failed = 批量导入失败
 batch-import-failed-chart = 批量导入失败: { $chart }
 
+bulk-import-empty = 未找到谱面压缩包
+bulk-import-scan-failed = 扫描谱面失败
+bulk-import-duplicates = 已在谱面库中：
+bulk-import-failures = 导入失败：
+
 warning = 警告
 warning-new-speed-event = 该谱面使用了 RPE 1.7.0 引入的速度事件缓动。为兼容性考虑，Phira 默认不启用对该事件的支持。如果需要启用，请在谱面信息中勾选“新速度缓动”。
 warning-attach-ui = 该谱面使用了 UI 绑定。Phira 最近的版本引入了 UI 绑定的修复，但可能会导致依赖旧行为的谱面出现问题。如有必要，请在谱面信息中取消勾选“UI 绑定修复”。
+warning-rpe-unsupported = 该谱面使用了 Phira 尚不支持渲染的 RPE { $version } 特性，显示效果可能与 RPE 中不同：
+warning-rpe-unknown = 该谱面包含 Phira 无法识别的字段 (RPE { $version })：
+rpe-scope-chart = 谱面
+rpe-scope-meta = 元数据
+rpe-scope-line = #{ $id } 判定线
+rpe-scope-events = #{ $id } 判定线的 { $count } 个事件，首个为 #{ $first }
+rpe-scope-notes = #{ $id } 判定线的 { $count } 个音符，首个为 #{ $first }
+rpe-fields-more = …以及另外 { $count } 项
+warning-shader = 该谱面特效的着色器存在问题，在部分设备上可能无法加载：
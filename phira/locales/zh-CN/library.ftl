@@ -12,6 +12,8 @@ not-opened = 功能尚未开启
 failed-to-load-online = 加载在线谱面失败
 
 import = 导入
+import-chart = 导入谱面
+import-folder = 导入文件夹
 
 offline-mode = 离线模式下无法加载在线谱面
 
@@ -45,3 +47,5 @@ multi-select-all = 全选
 multi-select-invert = 反选
 
 fav-created = 收藏夹已创建
+
+search-local-only = 在线搜索仅支持关键词、标签和 #id，其余筛选条件只对本地谱面生效
@@ -12,3 +12,23 @@ info-content =
 
 cant-delete-builtin = 不能删除内置资源包
 deleted = 已删除
+
+validate-failed = 检查资源包失败
+problems = 资源包中发现 { $count } 个问题
+problem-missing = 缺少 { $file }
+problem-info = info.yml 无效：{ $error }
+problem-image = 无法读取 { $file }：{ $error }
+problem-atlas = { $file } 高 { $height } 像素，去掉 { $tail } 像素的尾部和 { $head } 像素的头部后没有留给中部的空间
+problem-hit-fx = { $file }（{ $width }×{ $height }）无法分割为 { $columns }×{ $rows } 帧
+problem-frames = { $file } 宽 { $width } 像素，无法分割为 { $frames } 帧
+problem-font = 无法读取 { $file }：{ $error }
+problem-particles = 粒子预设无效：{ $error }
+problem-audio = 无法播放 { $file }：{ $error }
+problem-audio-ignored = { $file } 的格式不受支持（仅支持 ogg、wav 和 mp3），将使用默认音效
+no-problems = 未发现问题
+
+preview-failed = 预览加载失败
+
+dev-mode-on = 开发模式已开启：资源包文件夹变化时会自动重新加载
+dev-mode-off = 开发模式已关闭
+dev-mode-builtin = 开发模式仅适用于导入的资源包
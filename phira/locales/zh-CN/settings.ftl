@@ -19,6 +19,14 @@ item-mp-sub = 启用多人游戏
 item-mp-addr = 多人游戏服务器
 item-mp-addr-sub = 服务器地址，'主机:端口'
 item-mp-addr-invalid = 无效的服务器地址
+item-quality = 画质
+item-quality-sub = 同时设置抗锯齿、粒子和着色器特效。
+quality-low = 低
+quality-medium = 中
+quality-high = 高
+quality-custom = 自定义
+item-auto-quality = 卡顿时降低画质
+item-auto-quality-sub = 游玩中持续掉帧时，依次关闭抗锯齿、粒子和特效。
 item-lowq = 低画质模式
 item-lowq-sub = 建议在画面卡顿时启用
 item-clear-cache = 清除缓存
@@ -40,6 +48,12 @@ item-music = 音乐音量
 item-sfx = 音效音量
 item-bgm = BGM 音量
 item-cali = 调整延迟
+item-visual-offset = 画面延迟
+item-visual-offset-sub = 推迟音符的显示，不影响判定
+item-hitsound-scheduling = 提前播放打击音
+item-hitsound-scheduling-sub = 调整提前击打的音符的打击音时机以抵消音频延迟
+item-hitsound-latency = 设备延迟
+item-hitsound-latency-sub = 音频设备额外的延迟，如蓝牙耳机
 item-preferred-sample-rate = 首选采样率
 preferred-sample-rate-default = 系统默认
 item-audio-buffer-size = 音频缓冲区大小
@@ -56,15 +70,44 @@ item-opt = 激进优化
 item-opt-sub = 采用激进的优化策略，提升性能但可能导致部分谱面显示出错
 item-use-keyboard = 使用键盘游玩
 item-use-keyboard-sub = 开启后可以使用键盘进行游戏，但成绩无法上传
+item-input-profile = 按键方案
+item-input-profile-sub = 游玩时使用的按键和手柄按钮，点击下方的绑定可以修改
+binding-tap = 点击
+binding-hold = 按住
+binding-flick = 滑动（{ $direction ->
+    [up] 上
+    [down] 下
+    [left] 左
+    *[right] 右
+  }）
+binding-zone = { $action }（区域 { $zone }）
+binding-press = 请按键…
+item-reset-bindings = 重置按键绑定
+reset-bindings = 重置
 item-prefer-reduced-motion = 减少动画效果
 item-prefer-reduced-motion-sub = 减少动画和视觉特效
 item-speed = 速度
+item-speed-pitch = 变速音调
+item-speed-pitch-sub = 变速播放时音乐是否保持原有音调
+speed-pitch-keep = 保持音调
+speed-pitch-follow = Nightcore / Daycore
 item-note-size = 音符大小
+item-note-outline = 音符描边
+item-note-outline-sub = 在音符周围绘制深色描边
+item-note-shapes = 音符形状标记
+item-note-shapes-sub = 为每种音符绘制不同的形状
+item-fx-palette = 打击特效配色
+item-fx-palette-sub = 打击特效与 AP/FC 指示器的颜色
+palette-pack = 资源包
+palette-colorblind = 色盲友好
+palette-high-contrast = 高对比度
 
 item-chart-debug = 谱面调试
 item-chart-debug-sub = 显示判定线编号和朝向
 item-touch-debug = 触摸调试
 item-touch-debug-sub = 游玩过程中显示触摸点
+item-profiler = 帧耗时分析
+item-profiler-sub = 显示每帧各部分的耗时，并在游玩结束后保存性能追踪。
 
 load-cali-failed = 加载音频失败
 
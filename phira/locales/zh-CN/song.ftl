@@ -51,6 +51,7 @@ cancel-not-saved = 你还没有保存信息，确定要退出吗？
 upload-not-saved = 你还没有保存谱面，确定要继续上传吗？
 upload-login-first = 请先登录
 upload-builtin = 不能上传内置谱面
+upload-pack = 不能上传谱面包中的谱面
 upload-rules = 上传须知
 upload-rules-content =
   在上传前，你需要确认：
@@ -154,3 +155,11 @@ still-loading = 仍在加载中…
 
 export = 导出
 exporting = 导出中…
+
+personal = 个人设置
+personal-sub = 仅对本设备上的这张谱面生效，重新下载谱面后仍会保留
+personal-offset = 延迟 (ms)
+personal-speed = 速度
+personal-note-scale = 按键大小
+personal-dim = 背景昏暗度
+personal-reset = 重置
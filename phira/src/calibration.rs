@@ -0,0 +1,133 @@
+//! Automatic offset calibration from the timing of real plays.
+//!
+//! After each play we keep a summary of the player's timing errors together with the offset in
+//! use, which makes an estimate of the audio and input latency that stays valid after the offset
+//! is changed. The latest plays on the same audio output device are combined into a suggested
+//! offset.
+
+use crate::{get_data, get_data_mut};
+use chrono::{DateTime, Utc};
+use prpr::judge::{PlayResult, TimingStats};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Plays with fewer timed hits are too noisy to be useful.
+const MIN_HITS: usize = 30;
+/// How many plays are kept at most.
+const MAX_SAMPLES: usize = 50;
+/// How many of the latest plays an estimate is made of.
+const ESTIMATE_PLAYS: usize = 10;
+/// How many plays an estimate needs at least.
+pub const MIN_PLAYS: usize = 3;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OffsetSample {
+    pub time: DateTime<Utc>,
+    pub device: Option<String>,
+    /// Number of hits left after rejecting outliers
+    pub hits: u32,
+    /// The offset that would have centered the hits, in seconds
+    pub latency: f32,
+    /// Standard deviation of the hits, in seconds
+    pub spread: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+pub struct Estimate {
+    pub offset: f32,
+    pub confidence: Confidence,
+    pub plays: usize,
+    pub hits: u32,
+    /// Standard error of `offset`, in seconds
+    pub error: f32,
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    values.sort_by(f32::total_cmp);
+    let n = values.len();
+    if n % 2 == 1 {
+        values[n / 2]
+    } else {
+        (values[n / 2 - 1] + values[n / 2]) / 2.
+    }
+}
+
+/// Returns the range within three median absolute deviations of the median of `values`.
+fn inlier_range(values: &[f32]) -> RangeInclusive<f32> {
+    let median = median(&mut values.to_vec());
+    let mut deviations: Vec<_> = values.iter().map(|it| (it - median).abs()).collect();
+    // 1.4826 scales the MAD to the standard deviation of a normal distribution. The lower bound
+    // keeps very consistent players from having most of their hits rejected.
+    let limit = (self::median(&mut deviations) * 1.4826 * 3.).max(0.01);
+    (median - limit)..=(median + limit)
+}
+
+/// Records the timing of a finished play made with `offset` on `device`.
+///
+/// Returns whether the play was recorded. Data needs to be saved afterwards.
+pub fn record_play(result: &PlayResult, offset: f32, device: Option<String>) -> bool {
+    // keyboards and gamepads have a latency of their own
+    if result.input_profile.is_some() || result.timings.len() < MIN_HITS {
+        return false;
+    }
+    let diffs: Vec<_> = result.timings.iter().map(|it| it.diff).collect();
+    let range = inlier_range(&diffs);
+    let stats = TimingStats::new(diffs.into_iter().filter(|it| range.contains(it)));
+    let samples = &mut get_data_mut().offset_samples;
+    samples.push(OffsetSample {
+        time: Utc::now(),
+        device,
+        hits: stats.count,
+        latency: offset + stats.mean,
+        spread: stats.std,
+    });
+    if samples.len() > MAX_SAMPLES {
+        samples.drain(..samples.len() - MAX_SAMPLES);
+    }
+    true
+}
+
+/// Suggests an offset for `device` from the latest plays on it, or `None` if there are too few.
+pub fn estimate(device: Option<&str>) -> Option<Estimate> {
+    let samples: Vec<_> = get_data()
+        .offset_samples
+        .iter()
+        .rev()
+        .filter(|it| it.device.as_deref() == device)
+        .take(ESTIMATE_PLAYS)
+        .collect();
+    if samples.len() < MIN_PLAYS {
+        return None;
+    }
+    let range = inlier_range(&samples.iter().map(|it| it.latency).collect::<Vec<_>>());
+    let samples: Vec<_> = samples.into_iter().filter(|it| range.contains(&it.latency)).collect();
+
+    let plays = samples.len();
+    let hits: u32 = samples.iter().map(|it| it.hits).sum();
+    let weight = |it: &OffsetSample| it.hits as f32 / hits as f32;
+    let offset: f32 = samples.iter().map(|it| it.latency * weight(it)).sum();
+    let between: f32 = samples.iter().map(|it| (it.latency - offset).powi(2) * weight(it)).sum();
+    let within: f32 = samples.iter().map(|it| it.spread.powi(2) * weight(it)).sum();
+    let error = (between / plays as f32 + within / hits as f32).sqrt();
+    let confidence = if error <= 0.004 && plays >= 5 {
+        Confidence::High
+    } else if error <= 0.01 {
+        Confidence::Medium
+    } else {
+        Confidence::Low
+    };
+    Some(Estimate {
+        offset,
+        confidence,
+        plays,
+        hits,
+        error,
+    })
+}
@@ -0,0 +1,449 @@
+//! Query language for searching the chart library.
+//!
+//! A query is a whitespace-separated list of terms. Plain words match the chart
+//! name, while `key:value` and `key<op>value` terms filter on metadata:
+//!
+//! ```text
+//! charter:foo diff>=14 tag:"AT" played:no acc<98 format:rpe -composer:bar #1234
+//! ```
+//!
+//! Terms that cannot be understood are treated as plain words, so typing a
+//! name that happens to contain a colon keeps working as before.
+
+use crate::{data::BriefChartInfo, get_data};
+use prpr::{info::ChartFormat, scene::SimpleRecord};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Bumped whenever [`crate::data::Data`] is saved, so that [`ChartIndex`] knows it's stale.
+pub static CHARTS_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Ge,
+    Gt,
+}
+
+impl Cmp {
+    fn test<T: PartialOrd>(self, lhs: T, rhs: T) -> bool {
+        match self {
+            Self::Lt => lhs < rhs,
+            Self::Le => lhs <= rhs,
+            Self::Eq => lhs == rhs,
+            Self::Ne => lhs != rhs,
+            Self::Ge => lhs >= rhs,
+            Self::Gt => lhs > rhs,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TextField {
+    Name,
+    Charter,
+    Composer,
+    Illustrator,
+    Level,
+    Intro,
+}
+
+#[derive(Clone, Debug)]
+enum Filter {
+    Text(String),
+    Field(TextField, String),
+    Tag(String),
+    Id(i32),
+    Difficulty(Cmp, f32),
+    Accuracy(Cmp, f32),
+    Score(Cmp, i32),
+    Played(bool),
+    FullCombo(bool),
+    Format(ChartFormat),
+    Not(Box<Filter>),
+}
+
+fn parse_bool(s: &str) -> Option<bool> {
+    match s {
+        "yes" | "y" | "true" | "1" => Some(true),
+        "no" | "n" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_format(s: &str) -> Option<ChartFormat> {
+    Some(match s {
+        "rpe" => ChartFormat::Rpe,
+        "pec" => ChartFormat::Pec,
+        "pgr" | "phigros" => ChartFormat::Pgr,
+        "pbc" => ChartFormat::Pbc,
+        _ => return None,
+    })
+}
+
+/// Splits `s` into terms, keeping double-quoted parts (which may contain spaces) together.
+fn tokenize(s: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut cur = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !cur.is_empty() {
+                    tokens.push(std::mem::take(&mut cur));
+                }
+            }
+            c => cur.push(c),
+        }
+    }
+    if !cur.is_empty() {
+        tokens.push(cur);
+    }
+    tokens
+}
+
+fn strip_cmp(s: &str) -> Option<(Cmp, &str)> {
+    [
+        (">=", Cmp::Ge),
+        ("<=", Cmp::Le),
+        ("!=", Cmp::Ne),
+        (">", Cmp::Gt),
+        ("<", Cmp::Lt),
+        ("=", Cmp::Eq),
+    ]
+    .into_iter()
+    .find_map(|(op, cmp)| s.strip_prefix(op).map(|value| (cmp, value)))
+}
+
+/// Splits a term like `diff>=14` into `("diff", Cmp::Ge, "14")`. `:` alone means equality, and can be
+/// followed by another operator (`diff:>=14`).
+fn split_term(term: &str) -> Option<(&str, Cmp, &str)> {
+    let pos = term.find([':', '<', '>', '=', '!'])?;
+    if pos == 0 {
+        return None;
+    }
+    let (key, rest) = term.split_at(pos);
+    let (cmp, value) = match rest.strip_prefix(':') {
+        Some(value) => strip_cmp(value).unwrap_or((Cmp::Eq, value)),
+        None => strip_cmp(rest)?,
+    };
+    Some((key, cmp, value))
+}
+
+fn parse_filter(term: &str) -> Filter {
+    if let Some(term) = term.strip_prefix('-').filter(|it| !it.is_empty()) {
+        let filter = parse_filter(term);
+        if !matches!(filter, Filter::Text(_)) {
+            return Filter::Not(Box::new(filter));
+        }
+    }
+    if let Some(id) = term.strip_prefix('#').and_then(|it| it.parse().ok()) {
+        return Filter::Id(id);
+    }
+    let text = || Filter::Text(term.to_lowercase());
+    let Some((key, cmp, value)) = split_term(term) else {
+        return text();
+    };
+    let value_lower = value.to_lowercase();
+    let text_field = |field| (cmp == Cmp::Eq).then(|| Filter::Field(field, value_lower.clone()));
+    let filter = match key.to_lowercase().as_str() {
+        "name" | "title" => text_field(TextField::Name),
+        "charter" | "chart" => text_field(TextField::Charter),
+        "composer" | "artist" => text_field(TextField::Composer),
+        "illustrator" | "illu" => text_field(TextField::Illustrator),
+        "level" | "lv" => text_field(TextField::Level),
+        "intro" | "desc" => text_field(TextField::Intro),
+        "tag" => (cmp == Cmp::Eq).then(|| Filter::Tag(value_lower.clone())),
+        "id" => value.parse().ok().filter(|_| cmp == Cmp::Eq).map(Filter::Id),
+        "diff" | "difficulty" => value.parse().ok().map(|it| Filter::Difficulty(cmp, it)),
+        "acc" | "accuracy" => value.trim_end_matches('%').parse().ok().map(|it| Filter::Accuracy(cmp, it)),
+        "score" => value.parse().ok().map(|it| Filter::Score(cmp, it)),
+        "played" => parse_bool(&value_lower).filter(|_| cmp == Cmp::Eq).map(Filter::Played),
+        "fc" => parse_bool(&value_lower).filter(|_| cmp == Cmp::Eq).map(Filter::FullCombo),
+        "format" => parse_format(&value_lower).filter(|_| cmp == Cmp::Eq).map(Filter::Format),
+        _ => None,
+    };
+    filter.unwrap_or_else(text)
+}
+
+/// A parsed library search query.
+#[derive(Clone, Debug, Default)]
+pub struct ChartQuery {
+    filters: Vec<Filter>,
+}
+
+impl ChartQuery {
+    pub fn parse(s: &str) -> Self {
+        Self {
+            filters: tokenize(s).iter().map(|it| parse_filter(it)).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// The plain-text part of the query, used for server-side search.
+    pub fn text(&self) -> String {
+        self.filters
+            .iter()
+            .filter_map(|it| match it {
+                Filter::Text(text) => Some(text.clone()),
+                Filter::Id(id) => Some(format!("#{id}")),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Tags required (or, prefixed with `-`, excluded) by the query, used for server-side search.
+    pub fn tags(&self) -> impl Iterator<Item = String> + '_ {
+        self.filters.iter().filter_map(|it| match it {
+            Filter::Tag(tag) => Some(tag.clone()),
+            Filter::Not(filter) => match filter.as_ref() {
+                Filter::Tag(tag) => Some(format!("-{tag}")),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    /// Whether the query has filters other than words and tags, which the server can't search by.
+    pub fn has_local_filters(&self) -> bool {
+        self.filters.iter().any(|it| match it {
+            Filter::Text(_) | Filter::Id(_) | Filter::Tag(_) => false,
+            Filter::Not(filter) => !matches!(filter.as_ref(), Filter::Tag(_)),
+            _ => true,
+        })
+    }
+
+    pub fn matches(&self, entry: &IndexEntry) -> bool {
+        self.filters.iter().all(|it| entry.matches(it))
+    }
+}
+
+/// Searchable metadata of a single chart, with text fields pre-lowercased.
+pub struct IndexEntry {
+    id: Option<i32>,
+    name: String,
+    charter: String,
+    composer: String,
+    illustrator: String,
+    level: String,
+    intro: String,
+    tags: Vec<String>,
+    difficulty: f32,
+    format: Option<ChartFormat>,
+    record: Option<SimpleRecord>,
+}
+
+impl IndexEntry {
+    pub fn new(info: &BriefChartInfo, record: Option<SimpleRecord>) -> Self {
+        Self {
+            id: info.id,
+            name: info.name.to_lowercase(),
+            charter: info.charter.to_lowercase(),
+            composer: info.composer.to_lowercase(),
+            illustrator: info.illustrator.to_lowercase(),
+            level: info.level.to_lowercase(),
+            intro: info.intro.to_lowercase(),
+            tags: info.tags.iter().map(|it| it.to_lowercase()).collect(),
+            difficulty: info.difficulty,
+            format: info.format.clone(),
+            record,
+        }
+    }
+
+    fn field(&self, field: TextField) -> &str {
+        match field {
+            TextField::Name => &self.name,
+            TextField::Charter => &self.charter,
+            TextField::Composer => &self.composer,
+            TextField::Illustrator => &self.illustrator,
+            TextField::Level => &self.level,
+            TextField::Intro => &self.intro,
+        }
+    }
+
+    fn matches(&self, filter: &Filter) -> bool {
+        match filter {
+            Filter::Text(text) => self.name.contains(text.as_str()),
+            Filter::Field(field, text) => self.field(*field).contains(text.as_str()),
+            Filter::Tag(tag) => self.tags.iter().any(|it| it == tag),
+            Filter::Id(id) => self.id == Some(*id),
+            Filter::Difficulty(cmp, value) => cmp.test(self.difficulty, *value),
+            Filter::Accuracy(cmp, value) => self.record.as_ref().is_some_and(|it| cmp.test(it.accuracy * 100., *value)),
+            Filter::Score(cmp, value) => self.record.as_ref().is_some_and(|it| cmp.test(it.score, *value)),
+            Filter::Played(played) => self.record.is_some() == *played,
+            Filter::FullCombo(fc) => self.record.as_ref().is_some_and(|it| it.full_combo) == *fc,
+            Filter::Format(format) => self.format.as_ref() == Some(format),
+            Filter::Not(filter) => !self.matches(filter),
+        }
+    }
+}
+
+/// In-memory index over the local charts in [`crate::data::Data`], keyed by local path.
+///
+/// The index is rebuilt lazily the next time it's accessed after the data is saved.
+#[derive(Default)]
+pub struct ChartIndex {
+    generation: Option<u64>,
+    entries: HashMap<String, IndexEntry>,
+}
+
+impl ChartIndex {
+    fn rebuild(&mut self) {
+        let data = get_data();
+        self.entries.clear();
+        self.entries.reserve(data.charts.len());
+        for chart in &data.charts {
+            let record = chart
+                .record
+                .clone()
+                .or_else(|| data.local_records.get(&chart.local_path).cloned().flatten());
+            self.entries.insert(chart.local_path.clone(), IndexEntry::new(&chart.info, record));
+        }
+    }
+
+    fn sync(&mut self) {
+        let generation = CHARTS_GENERATION.load(Ordering::Relaxed);
+        if self.generation != Some(generation) {
+            self.rebuild();
+            self.generation = Some(generation);
+        }
+    }
+
+    pub fn get(&self, local_path: &str) -> Option<&IndexEntry> {
+        self.entries.get(local_path)
+    }
+
+    /// Checks whether the chart matches `query`, preferring indexed metadata for local charts.
+    pub fn matches(&self, query: &ChartQuery, local_path: Option<&str>, info: &BriefChartInfo) -> bool {
+        if query.is_empty() {
+            return true;
+        }
+        match local_path.and_then(|it| self.get(it)) {
+            Some(entry) => query.matches(entry),
+            None => {
+                let record = local_path.and_then(|it| get_data().local_records.get(it).cloned().flatten());
+                query.matches(&IndexEntry::new(info, record))
+            }
+        }
+    }
+}
+
+thread_local! {
+    static INDEX: RefCell<ChartIndex> = RefCell::default();
+}
+
+/// Runs `f` with the up-to-date chart index.
+pub fn with_chart_index<R>(f: impl FnOnce(&ChartIndex) -> R) -> R {
+    INDEX.with(|it| {
+        let mut index = it.borrow_mut();
+        index.sync();
+        f(&index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, charter: &str, difficulty: f32, tags: &[&str]) -> BriefChartInfo {
+        BriefChartInfo {
+            id: Some(1234),
+            uploader: None,
+            name: name.to_owned(),
+            level: format!("IN Lv.{difficulty:.0}"),
+            difficulty,
+            intro: String::new(),
+            charter: charter.to_owned(),
+            composer: "Someone".to_owned(),
+            illustrator: String::new(),
+            created: None,
+            updated: None,
+            chart_updated: None,
+            has_unlock: false,
+            tags: tags.iter().map(|it| it.to_string()).collect(),
+            format: Some(ChartFormat::Rpe),
+            rating: None,
+        }
+    }
+
+    fn record(accuracy: f32, full_combo: bool) -> SimpleRecord {
+        SimpleRecord {
+            score: (accuracy * 1e6) as i32,
+            accuracy,
+            full_combo,
+        }
+    }
+
+    #[test]
+    fn tokenize_quotes() {
+        assert_eq!(tokenize("  a  tag:\"AT  x\" b"), ["a", "tag:AT  x", "b"]);
+        assert_eq!(tokenize("\"unclosed quote"), ["unclosed quote"]);
+        assert!(tokenize("   ").is_empty());
+    }
+
+    #[test]
+    fn split_terms() {
+        assert_eq!(split_term("diff>=14"), Some(("diff", Cmp::Ge, "14")));
+        assert_eq!(split_term("diff:>=14"), Some(("diff", Cmp::Ge, "14")));
+        assert_eq!(split_term("acc<98"), Some(("acc", Cmp::Lt, "98")));
+        assert_eq!(split_term("score!=0"), Some(("score", Cmp::Ne, "0")));
+        assert_eq!(split_term("charter:foo"), Some(("charter", Cmp::Eq, "foo")));
+        assert_eq!(split_term(":foo"), None);
+        assert_eq!(split_term("plain"), None);
+    }
+
+    #[test]
+    fn parse_filters() {
+        assert!(matches!(parse_filter("Name"), Filter::Text(it) if it == "name"));
+        assert!(matches!(parse_filter("charter:Foo"), Filter::Field(TextField::Charter, it) if it == "foo"));
+        assert!(matches!(parse_filter("diff>=14.5"), Filter::Difficulty(Cmp::Ge, it) if it == 14.5));
+        assert!(matches!(parse_filter("acc<98%"), Filter::Accuracy(Cmp::Lt, it) if it == 98.));
+        assert!(matches!(parse_filter("played:no"), Filter::Played(false)));
+        assert!(matches!(parse_filter("format:phigros"), Filter::Format(ChartFormat::Pgr)));
+        assert!(matches!(parse_filter("#1234"), Filter::Id(1234)));
+        assert!(matches!(parse_filter("-composer:bar"), Filter::Not(it) if matches!(*it, Filter::Field(TextField::Composer, _))));
+        // terms that make no sense are searched for as they are
+        assert!(matches!(parse_filter("re:zero"), Filter::Text(it) if it == "re:zero"));
+        assert!(matches!(parse_filter("diff>=hard"), Filter::Text(_)));
+        assert!(matches!(parse_filter("charter>foo"), Filter::Text(_)));
+        assert!(matches!(parse_filter("-word"), Filter::Text(it) if it == "-word"));
+    }
+
+    #[test]
+    fn server_parts() {
+        let query = ChartQuery::parse("snow #12 tag:AT -tag:Joke");
+        assert_eq!(query.text(), "snow #12");
+        assert_eq!(query.tags().collect::<Vec<_>>(), ["at", "-joke"]);
+        assert!(!query.has_local_filters());
+        assert!(ChartQuery::parse("snow charter:foo").has_local_filters());
+        assert!(ChartQuery::parse("-diff>=14").has_local_filters());
+    }
+
+    #[test]
+    fn matching() {
+        let played = IndexEntry::new(&info("Snow Drop", "Foo Bar", 14.2, &["AT"]), Some(record(0.97, true)));
+        let unplayed = IndexEntry::new(&info("Jumping", "Baz", 12., &[]), None);
+        let check = |query: &str| (ChartQuery::parse(query).matches(&played), ChartQuery::parse(query).matches(&unplayed));
+
+        assert_eq!(check(""), (true, true));
+        assert_eq!(check("snow"), (true, false));
+        assert_eq!(check("charter:foo diff>=14 tag:\"at\""), (true, false));
+        assert_eq!(check("played:no"), (false, true));
+        assert_eq!(check("acc<98"), (true, false));
+        assert_eq!(check("acc>=98"), (false, false));
+        assert_eq!(check("fc:yes format:rpe"), (true, false));
+        assert_eq!(check("-charter:foo"), (false, true));
+        assert_eq!(check("#1234 level:lv.12"), (false, true));
+    }
+}
@@ -7,7 +7,7 @@ use crate::{
     page::{ChartItem, Fader, CHOOSE_COVER, CHOSEN_COVER},
     popup::Popup,
     save_data,
-    scene::{render_release_to_refresh, SongScene, MP_PANEL},
+    scene::{pack_root, render_release_to_refresh, SongScene, MP_PANEL},
 };
 use anyhow::Result;
 use core::f32;
@@ -420,10 +420,16 @@ impl ChartsView {
                         } else {
                             format!("download/{}", item.chart.as_ref().unwrap().info.id.unwrap())
                         };
-                        std::fs::remove_dir_all(format!("{}/{path}", dir::charts()?))?;
+                        if let Some(root) = pack_root(&path) {
+                            // the whole pack is shown as one song, so it's deleted as a whole
+                            std::fs::remove_dir_all(format!("{}/{root}", dir::charts()?))?;
+                            data.charts.retain(|it| pack_root(&it.local_path) != Some(root));
+                        } else {
+                            std::fs::remove_dir_all(format!("{}/{path}", dir::charts()?))?;
 
-                        if let Some(chart) = data.find_chart_by_path(path.as_str()) {
-                            data.charts.remove(chart);
+                            if let Some(chart) = data.find_chart_by_path(path.as_str()) {
+                                data.charts.remove(chart);
+                            }
                         }
 
                         save_data()?;
@@ -57,6 +57,9 @@ impl Chart {
             updated: Some(self.updated),
             chart_updated: Some(self.chart_updated),
             has_unlock: false,
+            tags: self.tags.clone(),
+            format: None,
+            rating: self.rating,
         }
     }
 }
@@ -6,7 +6,8 @@ use std::{
 };
 
 use crate::{
-    client::{recv_raw, Client, File},
+    client::File,
+    collection_sync::{self, CollectionOp},
     data::BriefChartInfo,
     dir, get_data,
     page::{local_illustration, Illustration},
@@ -15,9 +16,9 @@ use crate::{
 use super::{Chart, Object, Ptr, User};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use prpr::{ext::BLACK_TEXTURE, info::ChartInfo, task::Task, ui::Dialog};
-use reqwest::Method;
+use prpr::{ext::BLACK_TEXTURE, info::ChartInfo, ui::Dialog};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 use uuid::Uuid;
 
 #[derive(Clone, Deserialize)]
@@ -171,10 +172,7 @@ pub enum CollectionCover {
 
 pub enum CollectionUpdate {
     Unchanged,
-    Updated {
-        sync_task: Option<Task<Result<(Collection, bool)>>>,
-        add: bool,
-    },
+    Updated { add: bool },
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -267,56 +265,48 @@ impl LocalCollection {
             return CollectionUpdate::Unchanged;
         }
 
-        let should_upload = self.id.is_some() && !get_data().config.offline_mode;
-        let mut updated = false;
+        let before = self.clone();
+        let mut changed = Vec::new();
         if add {
             let local_paths: HashSet<String> = self.charts.iter().map(|it| it.path.clone()).collect();
             for chart in charts {
                 if !local_paths.contains(&chart.path) {
                     self.charts.push(chart.clone());
-                    updated = true;
+                    changed.extend(chart.id());
                 }
             }
         } else {
             let to_remove: HashSet<ChartRef> = charts.iter().cloned().collect();
             self.charts.retain(|it| {
                 if to_remove.contains(it) {
-                    updated = true;
+                    changed.extend(it.id());
                     false
                 } else {
                     true
                 }
             });
         }
-        if !updated {
+        if self.charts.len() == before.charts.len() {
             return CollectionUpdate::Unchanged;
         }
 
-        let id = self.id;
-        let col_ids = self.charts.iter().filter_map(|it| it.id()).collect::<Vec<_>>();
         data.set_collection_info(&uuid, self).unwrap();
-        if !should_upload {
-            return CollectionUpdate::Updated { sync_task: None, add };
-        }
-
-        CollectionUpdate::Updated {
-            sync_task: Some(Task::new(async move {
-                let resp: Collection =
-                    recv_raw(Client::request(Method::PATCH, format!("/collection/{}", id.unwrap())).json(&CollectionPatch::Set(col_ids)))
-                        .await?
-                        .json()
-                        .await?;
-                Ok((resp, add))
-            })),
-            add,
+        // online collections are synced in the background, see `collection_sync`
+        let op = if add {
+            CollectionOp::Add { charts: changed }
+        } else {
+            CollectionOp::Remove { charts: changed }
+        };
+        if let Err(err) = collection_sync::record_edit(uuid, &before, op) {
+            warn!(?err, "failed to record collection edit");
         }
+        CollectionUpdate::Updated { add }
     }
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum CollectionPatch {
-    Set(Vec<i32>),
     Public(bool),
     Cover(i32),
 }
@@ -328,3 +318,11 @@ pub struct CollectionContent {
     pub charts: Vec<i32>,
     pub public: bool,
 }
+
+/// Body of `PUT /collection/{id}`. The server rejects it with 412 if `updated` doesn't match.
+#[derive(Serialize)]
+pub struct PutCollection {
+    #[serde(flatten)]
+    pub content: CollectionContent,
+    pub updated: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,390 @@
+//! Offline queue for edits to online collections.
+//!
+//! Edits are applied to the local collection right away and recorded in
+//! [`crate::data::Data::collection_edits`], together with the remote state they were made
+//! against. The queue is replayed in the background whenever we're online; if the remote
+//! collection changed in the meantime, the edits are merged three-way into it, and the player is
+//! asked to pick a side only when both renamed or both reordered the collection.
+
+use crate::{
+    client::{recv_raw, Client, Collection, CollectionContent, LocalCollection, PutCollection},
+    get_data, get_data_mut,
+    page::{
+        favorites::{tl as ftl, L10N_LOCAL},
+        FAV_UPDATED,
+    },
+    save_data,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use prpr::{scene::show_message, task::Task, ui::Dialog};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    sync::{atomic::Ordering, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+use uuid::Uuid;
+
+/// How long to wait before retrying after a failed sync.
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CollectionOp {
+    Add {
+        charts: Vec<i32>,
+    },
+    Remove {
+        charts: Vec<i32>,
+    },
+    /// The whole order of the collection after the edit.
+    Reorder {
+        charts: Vec<i32>,
+    },
+    Rename {
+        name: String,
+    },
+}
+
+impl CollectionOp {
+    fn apply(&self, name: &mut String, charts: &mut Vec<i32>) {
+        match self {
+            Self::Add { charts: added } => {
+                for id in added {
+                    if !charts.contains(id) {
+                        charts.push(*id);
+                    }
+                }
+            }
+            Self::Remove { charts: removed } => charts.retain(|it| !removed.contains(it)),
+            Self::Reorder { charts: order } => {
+                // charts added elsewhere since keep their relative order, after the reordered ones
+                let mut res: Vec<_> = order.iter().copied().filter(|it| charts.contains(it)).collect();
+                res.extend(charts.iter().copied().filter(|it| !order.contains(it)));
+                *charts = res;
+            }
+            Self::Rename { name: new_name } => new_name.clone_into(name),
+        }
+    }
+}
+
+/// A concurrent change that can't be merged automatically.
+enum Conflict {
+    Name { local: String, remote: String },
+    Order,
+}
+
+impl Conflict {
+    fn describe(&self) -> String {
+        match self {
+            Self::Name { local, remote } => ftl!("sync-conflict-name", "local" => local.as_str(), "remote" => remote.as_str()),
+            Self::Order => ftl!("sync-conflict-order").into_owned(),
+        }
+    }
+}
+
+/// Edits to a collection that haven't reached the server yet.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingCollectionEdits {
+    pub base_name: String,
+    pub base_charts: Vec<i32>,
+    pub base_updated: Option<DateTime<Utc>>,
+    pub ops: Vec<CollectionOp>,
+}
+
+fn chart_ids(col: &LocalCollection) -> Vec<i32> {
+    col.charts.iter().filter_map(|it| it.id()).collect()
+}
+
+/// Items of `list` that are also in `other`, in the order of `list`.
+fn common_order(list: &[i32], other: &[i32]) -> Vec<i32> {
+    list.iter().copied().filter(|it| other.contains(it)).collect()
+}
+
+impl PendingCollectionEdits {
+    /// Applies the edits on top of the remote state, returning the merged name and charts along
+    /// with the conflicts that couldn't be resolved automatically.
+    fn merge(&self, remote_name: &str, remote_charts: &[i32]) -> (String, Vec<i32>, Vec<Conflict>) {
+        let mut name = remote_name.to_owned();
+        let mut charts = remote_charts.to_vec();
+        for op in &self.ops {
+            op.apply(&mut name, &mut charts);
+        }
+        let mut conflicts = Vec::new();
+        let renamed = self.ops.iter().any(|it| matches!(it, CollectionOp::Rename { .. }));
+        if renamed && remote_name != self.base_name && name != remote_name {
+            conflicts.push(Conflict::Name {
+                local: name.clone(),
+                remote: remote_name.to_owned(),
+            });
+        }
+        let reordered = self.ops.iter().any(|it| matches!(it, CollectionOp::Reorder { .. }));
+        if reordered
+            && common_order(&self.base_charts, remote_charts) != common_order(remote_charts, &self.base_charts)
+            && common_order(&charts, remote_charts) != common_order(remote_charts, &charts)
+        {
+            conflicts.push(Conflict::Order);
+        }
+        (name, charts, conflicts)
+    }
+}
+
+/// Records an edit to the collection `uuid`, whose state right before the edit is `before`.
+///
+/// Only online collections are tracked.
+pub fn record_edit(uuid: Uuid, before: &LocalCollection, op: CollectionOp) -> Result<()> {
+    if before.id.is_none() {
+        return Ok(());
+    }
+    get_data_mut()
+        .collection_edits
+        .entry(uuid)
+        .or_insert_with(|| PendingCollectionEdits {
+            base_name: before.name.clone(),
+            base_charts: chart_ids(before),
+            base_updated: before.remote_updated,
+            ops: Vec::new(),
+        })
+        .ops
+        .push(op);
+    SYNC.with(|it| it.borrow_mut().retry_at = None);
+    save_data()
+}
+
+fn has_pending_edits(uuid: &Uuid) -> bool {
+    get_data().collection_edits.contains_key(uuid)
+}
+
+/// Drops the pending edits of a collection, e.g. after the player explicitly overwrote one side.
+pub fn discard_edits(uuid: &Uuid) {
+    get_data_mut().collection_edits.remove(uuid);
+    SYNC.with(|it| it.borrow_mut().conflicts.remove(uuid));
+}
+
+/// Replaces the local collection with `col` fetched from the server. If there are pending edits,
+/// they're synced (and merged) instead, so that they're not lost.
+pub fn merge_remote(uuid: &Uuid, col: &Collection) -> Result<()> {
+    if has_pending_edits(uuid) {
+        SYNC.with(|it| {
+            let mut sync = it.borrow_mut();
+            sync.retry_at = None;
+            sync.conflicts.remove(uuid);
+        });
+        return Ok(());
+    }
+    let data = get_data();
+    let local = data.collection_info(uuid);
+    data.set_collection_info(uuid, local.merge(col))
+}
+
+enum SyncResult {
+    Synced(Collection),
+    Conflict(Collection, Vec<Conflict>),
+}
+
+enum Resolution {
+    KeepMine,
+    KeepServer,
+    Later,
+}
+
+#[derive(Default)]
+struct SyncState {
+    /// The collection being synced, how many of its edits are being pushed, and the task.
+    task: Option<(Uuid, usize, Task<Result<SyncResult>>)>,
+    retry_at: Option<Instant>,
+    /// Collections waiting for the player to resolve a conflict, with the remote state.
+    conflicts: HashMap<Uuid, Collection>,
+    /// Collections the player chose to resolve later; they're retried on next launch.
+    postponed: HashSet<Uuid>,
+}
+
+thread_local! {
+    static SYNC: RefCell<SyncState> = RefCell::default();
+}
+
+static RESOLUTION: Mutex<Option<(Uuid, Resolution)>> = Mutex::new(None);
+
+fn start_sync(uuid: Uuid, force: bool) -> Option<(Uuid, usize, Task<Result<SyncResult>>)> {
+    let data = get_data();
+    let edits = data.collection_edits.get(&uuid)?.clone();
+    let id = data.collection_info(&uuid).id?;
+    let count = edits.ops.len();
+    Some((
+        uuid,
+        count,
+        Task::new(async move {
+            let remote: Collection = recv_raw(Client::get(format!("/collection/{id}"))).await?.json().await?;
+            let remote_charts: Vec<i32> = remote.charts.iter().map(|it| it.id).collect();
+            let (name, charts, conflicts) = edits.merge(&remote.name, &remote_charts);
+            if !conflicts.is_empty() && !force {
+                return Ok(SyncResult::Conflict(remote, conflicts));
+            }
+            if name == remote.name && charts == remote_charts {
+                return Ok(SyncResult::Synced(remote));
+            }
+            let body = PutCollection {
+                content: CollectionContent {
+                    name,
+                    description: remote.description.clone(),
+                    charts,
+                    public: remote.public,
+                },
+                updated: Some(remote.updated),
+            };
+            let resp: Collection = recv_raw(Client::request(Method::PUT, format!("/collection/{id}")).json(&body))
+                .await?
+                .json()
+                .await?;
+            Ok(SyncResult::Synced(resp))
+        }),
+    ))
+}
+
+/// Marks the first `count` edits of `uuid` as pushed, `col` being the resulting remote state.
+fn finish_sync(uuid: Uuid, count: usize, col: &Collection) -> Result<()> {
+    let data = get_data_mut();
+    let local = data.collection_info(&uuid);
+    let Some(edits) = data.collection_edits.get_mut(&uuid) else {
+        return Ok(());
+    };
+    edits.ops.drain(..count.min(edits.ops.len()));
+    if edits.ops.is_empty() {
+        data.collection_edits.remove(&uuid);
+        data.set_collection_info(&uuid, local.merge(col))?;
+    } else {
+        // more edits were made while syncing; they're pushed on top of the new remote state
+        col.name.clone_into(&mut edits.base_name);
+        edits.base_charts = col.charts.iter().map(|it| it.id).collect();
+        edits.base_updated = Some(col.updated);
+        data.set_collection_info(
+            &uuid,
+            LocalCollection {
+                remote_updated: Some(col.updated),
+                ..local.as_ref().clone()
+            },
+        )?;
+    }
+    save_data()?;
+    FAV_UPDATED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+fn show_conflict(uuid: Uuid, conflicts: &[Conflict]) {
+    let name = get_data().collection_info(&uuid).name.clone();
+    let conflicts = conflicts.iter().map(Conflict::describe).collect::<Vec<_>>().join("\n");
+    Dialog::plain(ftl!("sync-conflict"), ftl!("sync-conflict-content", "name" => name, "conflicts" => conflicts))
+        .buttons(vec![
+            ftl!("sync-conflict-later").into_owned(),
+            ftl!("sync-conflict-keep-server").into_owned(),
+            ftl!("sync-conflict-keep-mine").into_owned(),
+        ])
+        .listener(move |_dialog, pos| {
+            let resolution = match pos {
+                1 => Resolution::KeepServer,
+                2 => Resolution::KeepMine,
+                _ => Resolution::Later,
+            };
+            *RESOLUTION.lock().unwrap() = Some((uuid, resolution));
+            false
+        })
+        .show();
+}
+
+/// Drives the queue. Called every frame.
+pub fn update() -> Result<()> {
+    SYNC.with(|it| {
+        let mut sync = it.borrow_mut();
+        let sync = &mut *sync;
+
+        let resolution = RESOLUTION.lock().unwrap().take();
+        if let Some((uuid, resolution)) = resolution {
+            if let Some(remote) = sync.conflicts.remove(&uuid) {
+                match resolution {
+                    Resolution::KeepMine => {
+                        if sync.task.is_none() {
+                            sync.task = start_sync(uuid, true);
+                        } else {
+                            // wait for the running sync to finish
+                            sync.conflicts.insert(uuid, remote);
+                            *RESOLUTION.lock().unwrap() = Some((uuid, resolution));
+                        }
+                    }
+                    Resolution::KeepServer => {
+                        let data = get_data_mut();
+                        data.collection_edits.remove(&uuid);
+                        let local = data.collection_info(&uuid);
+                        data.set_collection_info(&uuid, local.merge(&remote))?;
+                        save_data()?;
+                        FAV_UPDATED.store(true, Ordering::SeqCst);
+                    }
+                    Resolution::Later => {
+                        sync.postponed.insert(uuid);
+                    }
+                }
+            }
+        }
+
+        if let Some((uuid, count, task)) = &mut sync.task {
+            if let Some(res) = task.take() {
+                let (uuid, count) = (*uuid, *count);
+                sync.task = None;
+                match res {
+                    Ok(SyncResult::Synced(col)) => {
+                        finish_sync(uuid, count, &col)?;
+                        show_message(ftl!("sync-pending-done")).ok();
+                    }
+                    Ok(SyncResult::Conflict(remote, conflicts)) => {
+                        sync.conflicts.insert(uuid, remote);
+                        show_conflict(uuid, &conflicts);
+                    }
+                    Err(err) => {
+                        warn!(?err, "failed to sync collection edits, retrying later");
+                        sync.retry_at = Some(Instant::now() + RETRY_INTERVAL);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        let data = get_data();
+        if data.collection_edits.is_empty() || data.config.offline_mode || data.me.is_none() {
+            return Ok(());
+        }
+        if sync.retry_at.is_some_and(|it| Instant::now() < it) {
+            return Ok(());
+        }
+        let removed: Vec<_> = data
+            .collection_edits
+            .keys()
+            .filter(|it| !data.collection_uuids().contains(*it))
+            .copied()
+            .collect();
+        if !removed.is_empty() {
+            let data = get_data_mut();
+            for uuid in removed {
+                data.collection_edits.remove(&uuid);
+            }
+            save_data()?;
+        }
+        let next = data
+            .collection_edits
+            .keys()
+            .find(|it| !sync.conflicts.contains_key(*it) && !sync.postponed.contains(*it))
+            .copied();
+        if let Some(uuid) = next {
+            sync.task = start_sync(uuid, false);
+            if sync.task.is_none() {
+                // the collection is no longer online
+                get_data_mut().collection_edits.remove(&uuid);
+                save_data()?;
+            }
+        }
+        Ok(())
+    })
+}
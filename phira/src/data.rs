@@ -1,5 +1,7 @@
 use crate::{
+    calibration::OffsetSample,
     client::{Character, Chart, LocalCollection, Ptr, User},
+    collection_sync::PendingCollectionEdits,
     dir,
 };
 use anyhow::{Context, Result};
@@ -7,7 +9,7 @@ use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use prpr::{
     config::{Config, Mods},
-    info::ChartInfo,
+    info::{ChartFormat, ChartInfo, ChartPack},
     scene::SimpleRecord,
     ui::PREFER_REDUCED_MOTION,
 };
@@ -41,6 +43,13 @@ pub struct BriefChartInfo {
     pub chart_updated: Option<DateTime<Utc>>,
     #[serde(default)]
     pub has_unlock: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub format: Option<ChartFormat>,
+    /// Rating of the chart online, as of when it was last fetched
+    #[serde(default)]
+    pub rating: Option<f32>,
 }
 
 impl BriefChartInfo {
@@ -59,6 +68,9 @@ impl BriefChartInfo {
             updated: Some(chart.updated),
             chart_updated: Some(chart.chart_updated),
             has_unlock: false,
+            tags: chart.tags.clone(),
+            format: None,
+            rating: chart.rating,
         }
     }
 }
@@ -79,6 +91,9 @@ impl From<ChartInfo> for BriefChartInfo {
             updated: info.updated,
             chart_updated: info.chart_updated,
             has_unlock: info.unlock_video.is_some(),
+            tags: info.tags,
+            format: info.format,
+            rating: None,
         }
     }
 }
@@ -95,6 +110,67 @@ pub struct LocalChart {
     pub played_unlock: bool,
 }
 
+/// Lists the indices of the charts present in the unpacked pack at `root`, in ascending order.
+///
+/// Charts may have been deleted individually, so the indices are not necessarily contiguous.
+pub fn pack_member_indices(root: &Path) -> std::io::Result<Vec<usize>> {
+    let mut indices = Vec::new();
+    for entry in std::fs::read_dir(root)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Some(index) = entry.file_name().to_str().and_then(|it| it.parse().ok()) {
+            indices.push(index);
+        }
+    }
+    indices.sort_unstable();
+    Ok(indices)
+}
+
+/// Personal settings for a chart, applied on top of the chart's own info and the global config.
+///
+/// These are kept apart from [`LocalChart`] and never written to the chart's `info.yml`, so that
+/// they survive re-downloading the chart.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ChartOverrides {
+    /// Added to the global offset, in seconds
+    pub offset: f32,
+    pub speed: Option<f32>,
+    pub note_scale: Option<f32>,
+    pub background_dim: Option<f32>,
+}
+
+impl ChartOverrides {
+    /// The key of a chart in [`Data::chart_overrides`]: its online id where it has one, so that the
+    /// overrides still apply after the chart is deleted and downloaded again, and its local path
+    /// otherwise.
+    pub fn key(id: Option<i32>, local_path: &str) -> String {
+        match id {
+            Some(id) => format!("#{id}"),
+            None => local_path.to_owned(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, config: &mut Config, info: &mut ChartInfo) {
+        config.offset += self.offset;
+        if let Some(speed) = self.speed {
+            config.speed = speed;
+        }
+        if let Some(note_scale) = self.note_scale {
+            config.note_scale = note_scale;
+        }
+        if let Some(dim) = self.background_dim {
+            info.background_dim = dim;
+        }
+    }
+}
+
 fn default_anys_gateway() -> String {
     "https://anys.mivik.moe".to_string()
 }
@@ -105,6 +181,9 @@ pub struct Data {
     pub me: Option<User>,
     pub charts: Vec<LocalChart>,
     pub local_records: HashMap<String, Option<SimpleRecord>>,
+    /// Personal settings of charts, by [`ChartOverrides::key`]
+    #[serde(default)]
+    pub chart_overrides: HashMap<String, ChartOverrides>,
     pub config: Config,
     pub message_check_time: Option<DateTime<Utc>>,
     pub language: Option<String>,
@@ -135,6 +214,18 @@ pub struct Data {
     #[serde(default)]
     pub import_scan_retry: HashMap<String, u8>,
 
+    /// Content hashes of local charts by local path, used to detect duplicates when importing
+    #[serde(default)]
+    pub chart_hashes: HashMap<String, String>,
+
+    /// Edits to online collections that are yet to be synced, by collection
+    #[serde(default)]
+    pub collection_edits: HashMap<Uuid, PendingCollectionEdits>,
+
+    /// Timing summaries of the latest plays, used for automatic offset calibration
+    #[serde(default)]
+    pub offset_samples: Vec<OffsetSample>,
+
     #[serde(skip)]
     collection_cache: DashMap<Uuid, Arc<LocalCollection>>,
 }
@@ -198,6 +289,31 @@ impl Data {
             let filename = filename.to_str().unwrap();
             let filename = format!("custom/{filename}");
             let path = entry.path();
+            if path.join(ChartPack::MANIFEST).is_file() {
+                // Charts of a pack live in numbered subfolders, written by the import itself.
+                for index in pack_member_indices(&path)? {
+                    let member = path.join(index.to_string());
+                    let local_path = format!("{filename}/{index}");
+                    if occurred.contains(&local_path) {
+                        continue;
+                    }
+                    let info = match prpr::fs::fs_from_file(&member) {
+                        Ok(mut fs) => prpr::fs::load_info(fs.deref_mut()).await,
+                        Err(err) => Err(err),
+                    };
+                    match info {
+                        Ok(info) => self.charts.push(LocalChart {
+                            info: BriefChartInfo { id: None, ..info.into() },
+                            local_path,
+                            record: None,
+                            mods: Mods::default(),
+                            played_unlock: false,
+                        }),
+                        Err(err) => warn!(?err, "failed to load chart in pack: {local_path}"),
+                    }
+                }
+                continue;
+            }
             if occurred.contains(&filename) {
                 self.import_scan_retry.remove(&filename);
                 continue;
@@ -308,6 +424,8 @@ impl Data {
         let charts = dir::charts()?;
         self.local_records
             .retain(|local_path, _| Path::new(&format!("{charts}/{local_path}")).exists());
+        let local_paths: HashSet<_> = self.charts.iter().map(|it| it.local_path.as_str()).collect();
+        self.chart_hashes.retain(|local_path, _| local_paths.contains(local_path.as_str()));
 
         self.config.init();
         PREFER_REDUCED_MOTION.store(self.prefer_reduced_motion, Ordering::Relaxed);
@@ -318,6 +436,17 @@ impl Data {
         self.charts.iter().position(|local| local.local_path == local_path)
     }
 
+    /// Drops the overrides of charts that have left the library for good.
+    ///
+    /// Overrides keyed by online id are kept, as the chart can be downloaded again. Those keyed by
+    /// the local path of a custom chart are dropped once it's gone, since importing it again gives
+    /// it a new path.
+    pub fn prune_chart_overrides(&mut self) {
+        let local_paths: HashSet<_> = self.charts.iter().map(|it| it.local_path.as_str()).collect();
+        self.chart_overrides
+            .retain(|key, _| !key.starts_with("custom/") || local_paths.contains(key.as_str()));
+    }
+
     pub fn collection_uuids(&self) -> &[Uuid] {
         &self.collection_uuids
     }
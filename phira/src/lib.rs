@@ -5,9 +5,12 @@ prpr_l10n::tl_file!("common" ttl crate::);
 mod inner;
 
 mod anim;
+mod calibration;
 mod censor;
+mod chart_query;
 mod charts_view;
 mod client;
+mod collection_sync;
 mod data;
 mod icons;
 mod images;
@@ -88,6 +91,7 @@ pub fn sync_data() {
         get_data_mut().language = Some(default_lang);
     }
     set_prefered_locale(get_data().language.as_ref().and_then(|it| it.parse().ok()));
+    get_data_mut().prune_chart_overrides();
     let _ = client::set_access_token_sync(get_data().tokens.as_ref().map(|it| &*it.0));
 }
 
@@ -109,6 +113,7 @@ pub fn get_data_mut() -> &'static mut Data {
 
 pub fn save_data() -> Result<()> {
     std::fs::write(format!("{}/data.json", dir::root()?), serde_json::to_string(get_data())?)?;
+    chart_query::CHARTS_GENERATION.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
     Ok(())
 }
 
@@ -165,6 +170,14 @@ mod dir {
     pub fn respacks() -> Result<String> {
         ensure("data/respack")
     }
+
+    pub fn effects() -> Result<String> {
+        ensure("data/effect")
+    }
+
+    pub fn traces() -> Result<String> {
+        ensure("data/trace")
+    }
 }
 
 async fn the_main() -> Result<()> {
@@ -196,6 +209,7 @@ async fn the_main() -> Result<()> {
     }
 
     let dir = dir::root()?;
+    let fresh_install = !std::path::Path::new(&format!("{dir}/data.json")).exists();
     let mut data: Data = std::fs::read_to_string(format!("{dir}/data.json"))
         .map_err(anyhow::Error::new)
         .and_then(|s| Ok(serde_json::from_str(&s)?))
@@ -204,6 +218,16 @@ async fn the_main() -> Result<()> {
     set_data(data);
     sync_data();
     save_data()?;
+    prpr::profile::set_trace_dir(dir::traces()?);
+
+    match prpr::core::load_effect_packages(std::path::Path::new(&dir::effects()?)) {
+        Ok(failed) => {
+            for (path, err) in failed {
+                error!("failed to load effect package {}: {err:?}", path.display());
+            }
+        }
+        Err(err) => error!("failed to load effect packages: {err:?}"),
+    }
 
     // Warm up the offline banned-word automaton so local edits can check
     // synchronously. No-op without the `aa` feature.
@@ -226,6 +250,19 @@ async fn the_main() -> Result<()> {
     let font = FontArc::try_from_vec(load_file("font.ttf").await?)?;
     let mut painter = TextPainter::new(font.clone(), None);
 
+    if !get_data().config.quality_benchmarked {
+        if fresh_install {
+            let quality = prpr::quality::benchmark(&mut painter, &ttl!("benchmarking")).await;
+            quality.apply(&mut get_data_mut().config);
+        } else {
+            // options set by hand are kept, the tier only follows them
+            let config = &mut get_data_mut().config;
+            config.quality = prpr::config::Quality::detect(config);
+        }
+        get_data_mut().config.quality_benchmarked = true;
+        save_data()?;
+    }
+
     let mut main = Main::new(Box::new(MainScene::new(font).await?), TimeManager::default(), None).await?;
 
     let tm = TimeManager::default();
@@ -267,6 +304,11 @@ async fn the_main() -> Result<()> {
                 main.update()?;
                 main.render(&mut painter)?;
             }
+            // the in-game HUD editor can't reach our data, so it hands the layout over instead
+            if let Some(layout) = prpr::hud::take_edited_layout() {
+                get_data_mut().config.hud = layout;
+                save_data()?;
+            }
             prpr::ext::flush_pending_texture_deletions();
             Ok(())
         }();
@@ -1,7 +1,7 @@
 prpr_l10n::tl_file!("collection");
 
 use super::{Illustration, NextPage, Page, SharedState};
-use crate::{icons::Icons, load_res_tex, resource::rtl, scene::ChapterScene};
+use crate::{chart_query::ChartQuery, icons::Icons, load_res_tex, resource::rtl, scene::ChapterScene};
 use anyhow::Result;
 use macroquad::prelude::*;
 use prpr::{
@@ -29,6 +29,7 @@ struct Transit {
 
 pub struct CollectionPage {
     icons: Arc<Icons>,
+    query: ChartQuery,
 
     colls: Vec<CollectionItem>,
     scroll: Scroll,
@@ -49,9 +50,10 @@ impl CollectionPage {
     const HEIGHT: f32 = 0.63;
     const PAD: f32 = 0.06;
 
-    pub async fn new(icons: Arc<Icons>) -> Result<Self> {
+    pub async fn new(icons: Arc<Icons>, query: ChartQuery) -> Result<Self> {
         Ok(Self {
             icons,
+            query,
 
             colls: {
                 let mut res = {
@@ -116,8 +118,9 @@ impl Page for CollectionPage {
                         let icons = Arc::clone(&self.icons);
                         let rank_icons = s.icons.clone();
                         let illu = coll.illu.texture.1.clone();
+                        let query = self.query.clone();
                         self.scene_task = Some(Box::pin(async move {
-                            let scene = ChapterScene::new(id, icons, rank_icons, illu).await?;
+                            let scene = ChapterScene::new(id, icons, rank_icons, illu, query).await?;
                             Ok(NextScene::Overlay(Box::new(scene)))
                         }));
                     }
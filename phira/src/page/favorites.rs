@@ -2,7 +2,12 @@ prpr_l10n::tl_file!("favorites");
 
 use super::{Illustration, NextPage, Page, SharedState};
 use crate::{
-    client::{recv_raw, Chart, Client, Collection, CollectionContent, CollectionCover, CollectionPatch, File, LocalCollection, Ptr, UserManager},
+    chart_query::{with_chart_index, ChartQuery},
+    client::{
+        recv_raw, Chart, Client, Collection, CollectionContent, CollectionCover, CollectionPatch, File, LocalCollection, Ptr, PutCollection,
+        UserManager,
+    },
+    collection_sync::{self, CollectionOp},
     get_data, get_data_mut,
     icons::Icons,
     page::{SFader, CHOOSE_COVER},
@@ -46,13 +51,6 @@ const CARD_PAD: f32 = 0.033;
 const INFO_TRANSIT: f32 = 0.32;
 const INFO_WIDTH: f32 = 0.75;
 
-#[derive(Serialize)]
-struct PutCollection {
-    #[serde(flatten)]
-    content: CollectionContent,
-    updated: Option<DateTime<Utc>>,
-}
-
 // 收藏夹文件夹项 || Folder item
 struct FolderItem {
     index: Option<usize>,
@@ -105,6 +103,7 @@ pub struct FavoritesPage {
     next_page: Option<NextPage>,
 
     chosen_cover: Option<Result<i32, String>>,
+    query: ChartQuery,
 
     upload_task: Option<Task<Result<Collection>>>,
     delete_from_cloud_task: Option<Task<Result<()>>>,
@@ -122,7 +121,13 @@ pub struct FavoritesPage {
 }
 
 impl FavoritesPage {
-    pub fn new(icons: Arc<Icons>, rank_icons: [SafeTexture; 8], active_folder: Option<usize>, chosen_cover: Option<Result<i32, String>>) -> Self {
+    pub fn new(
+        icons: Arc<Icons>,
+        rank_icons: [SafeTexture; 8],
+        active_folder: Option<usize>,
+        chosen_cover: Option<Result<i32, String>>,
+        query: ChartQuery,
+    ) -> Self {
         let mut page = Self {
             icons,
             rank_icons,
@@ -166,6 +171,7 @@ impl FavoritesPage {
             next_page: None,
 
             chosen_cover,
+            query,
 
             upload_task: None,
             delete_from_cloud_task: None,
@@ -198,7 +204,28 @@ impl FavoritesPage {
             btn: RectButton::new(),
         });
 
+        // 只显示含有符合搜索条件谱面的收藏夹 || Only show collections with charts matching the library search
+        let matched = |col: &LocalCollection| {
+            self.query.is_empty()
+                || with_chart_index(|index| {
+                    col.charts.iter().any(|it| match index.get(&it.path) {
+                        Some(entry) => self.query.matches(entry),
+                        None => match &it.info {
+                            Some(chart) => index.matches(&self.query, None, &chart.info),
+                            None => it
+                                .find_local_path()
+                                .ok()
+                                .flatten()
+                                .and_then(|path| index.get(&path))
+                                .is_some_and(|entry| self.query.matches(entry)),
+                        },
+                    })
+                })
+        };
         for (index, col) in data.collections().enumerate() {
+            if !matched(&col) {
+                continue;
+            }
             folders.push(FolderItem {
                 index: Some(index),
                 name: col.name.clone(),
@@ -664,16 +691,14 @@ impl Page for FavoritesPage {
                         let uuid = data.collection_uuids()[index];
                         let col = data.collection_info(&uuid);
                         let new_col = LocalCollection {
-                            name: new_name,
+                            name: new_name.clone(),
                             ..col.as_ref().clone()
                         };
                         data.set_collection_info(&uuid, new_col)?;
+                        collection_sync::record_edit(uuid, &col, CollectionOp::Rename { name: new_name })?;
                         let _ = save_data();
                         show_message(tl!("updated")).ok();
                         self.rebuild_folders();
-                        if col.id.is_some() && !data.config.offline_mode {
-                            self.sync_to_cloud(false);
-                        }
                     }
                 }
                 "fav_description" => {
@@ -794,7 +819,8 @@ impl Page for FavoritesPage {
                 }
             }
             if self.operations_delete.swap(false, Ordering::SeqCst) {
-                data.remove_collection(index)?;
+                let uuid = data.remove_collection(index)?;
+                collection_sync::discard_edits(&uuid);
                 let _ = save_data();
                 show_message(tl!("deleted")).ok();
                 self.active_folder = if data.collection_uuids().is_empty() {
@@ -869,6 +895,8 @@ impl Page for FavoritesPage {
                     let uuid = data.collection_uuids()[self.active_folder.unwrap()];
                     let local = data.collection_info(&uuid);
                     data.set_collection_info(&uuid, local.merge(&col))?;
+                    collection_sync::discard_edits(&uuid);
+                    let _ = save_data();
                     show_message(tl!("synced")).ok();
                     self.rebuild_folders();
                 }
@@ -910,6 +938,8 @@ impl Page for FavoritesPage {
                         let mut local = data.collection_info(&uuid).as_ref().clone();
                         local.id = None;
                         data.set_collection_info(&uuid, local)?;
+                        collection_sync::discard_edits(&uuid);
+                        let _ = save_data();
                         show_message(tl!("deleted")).ok();
                     }
                     Err(err) => {
@@ -945,6 +975,9 @@ impl Page for FavoritesPage {
                         let uuid = data.collection_uuids()[self.active_folder.unwrap()];
                         let local = data.collection_info(&uuid);
                         data.set_collection_info(&uuid, local.merge(&col))?;
+                        // the whole local state was pushed, pending edits included
+                        collection_sync::discard_edits(&uuid);
+                        let _ = save_data();
                         show_message(tl!("synced")).ok();
                         self.rebuild_folders();
                     }
@@ -1012,14 +1045,13 @@ impl Page for FavoritesPage {
                     Ok(charts) => {
                         let data = get_data();
                         let uuid = data.collection_uuids()[self.active_folder.unwrap()];
-                        let mut col = data.collection_info(&uuid).as_ref().clone();
-                        let online = col.id.is_some();
+                        let before = data.collection_info(&uuid);
+                        let mut col = before.as_ref().clone();
+                        let ids = charts.iter().map(|it| it.id).collect();
                         col.charts.extend(charts.into_iter().map(Into::into));
                         data.set_collection_info(&uuid, col)?;
+                        collection_sync::record_edit(uuid, &before, CollectionOp::Add { charts: ids })?;
                         show_message(tl!("imported")).ok();
-                        if online && !data.config.offline_mode {
-                            self.sync_to_cloud(false);
-                        }
                     }
                     Err(err) => {
                         show_error(err);
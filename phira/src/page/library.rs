@@ -2,15 +2,17 @@ prpr_l10n::tl_file!("library");
 
 use super::{CollectionPage, FavoritesPage, NextPage, Page, SharedState};
 use crate::{
+    chart_query::{with_chart_index, ChartQuery},
     charts_view::{ChartDisplayItem, ChartsView, NEED_UPDATE},
     client::{recv_raw, Chart, ChartRef, ChartRefChartInfo, Client, Collection, CollectionUpdate, LocalCollection},
+    collection_sync::{self, CollectionOp},
     dir, get_data, get_data_mut,
     icons::Icons,
     page::{favorites::FAV_PAGE_RESULT, ChartItem, ChartType, Illustration},
     popup::Popup,
     rate::RateDialog,
     save_data,
-    scene::{check_read_tos_and_policy, compress_folder, confirm_dialog, ChartOrder, JUST_LOADED_TOS},
+    scene::{check_read_tos_and_policy, confirm_dialog, export_chart, export_pack, pack_members, pack_root, ChartOrder, JUST_LOADED_TOS},
     tabs::{Tabs, TitleFn},
     tags::TagsDialog,
 };
@@ -22,7 +24,7 @@ use jni::{jni_sig, jni_str, objects::JObject, refs::Global, vm::JavaVM, EnvUnown
 use macroquad::prelude::*;
 use prpr::{
     ext::{poll_future, semi_black, JoinToString, LocalTask, RectExt, SafeTexture, ScaleType},
-    scene::{request_file, request_input, return_input, show_error, show_message, take_input, NextScene},
+    scene::{request_file, request_folder, request_input, return_input, show_error, show_message, take_input, NextScene},
     task::Task,
     ui::{button_hit, DRectButton, Dialog, RectButton, Ui},
 };
@@ -107,6 +109,8 @@ pub struct LibraryPage {
     rank_icons: [SafeTexture; 8],
 
     import_btn: DRectButton,
+    import_menu: Popup,
+    need_show_import_menu: bool,
 
     search_btn: DRectButton,
     search_str: String,
@@ -133,8 +137,7 @@ pub struct LibraryPage {
     fav_btn: DRectButton,
     // None = 显示全部 || show all,      Some(folder_name) = 过滤指定收藏夹 || filter by folder
     current_fav_index: Option<usize>,
-    sync_fav_task: Option<Task<Result<Option<Collection>>>>,
-    force_sync_to_cloud: Arc<AtomicBool>,
+    sync_fav_task: Option<Task<Result<Collection>>>,
 
     multi_operation_btn: DRectButton,
     multi_operation_menu: Popup,
@@ -144,7 +147,6 @@ pub struct LibraryPage {
     manage_fav_menu: Popup,
     manage_fav_menu_options: Vec<(Uuid, bool)>,
     need_show_manage_fav_menu: bool,
-    manage_fav_task: Option<Task<Result<(Collection, bool)>>>,
     manage_fav_pre_task: Option<Task<Result<ManageFavorite>>>,
     #[allow(clippy::type_complexity)]
     refresh_local_fav_task: Option<Task<Result<(Uuid, Vec<ChartRef>)>>>,
@@ -192,6 +194,10 @@ impl LibraryPage {
             rank_icons,
 
             import_btn: DRectButton::new(),
+            import_menu: Popup::new()
+                .with_size(0.5)
+                .with_options(vec![tl!("import-chart").into_owned(), tl!("import-folder").into_owned()]),
+            need_show_import_menu: false,
 
             search_btn: DRectButton::new(),
             search_str: String::new(),
@@ -220,7 +226,6 @@ impl LibraryPage {
             fav_btn: DRectButton::new(),
             current_fav_index: None,
             sync_fav_task: None,
-            force_sync_to_cloud: Arc::default(),
 
             multi_operation_btn: DRectButton::new(),
             multi_operation_menu: Popup::new().with_size(0.5),
@@ -230,7 +235,6 @@ impl LibraryPage {
             manage_fav_menu: Popup::new().with_size(0.5).tap_mut(|it| it.set_auto_dismiss(false)),
             manage_fav_menu_options: Vec::new(),
             need_show_manage_fav_menu: false,
-            manage_fav_task: None,
             manage_fav_pre_task: None,
             refresh_local_fav_task: None,
 
@@ -282,7 +286,11 @@ impl LibraryPage {
         self.tabs.selected_mut().view.reset_scroll();
         self.tabs.selected_mut().view.clear();
         let page = self.current_page;
-        let search = self.search_str.clone();
+        let query = ChartQuery::parse(&self.search_str);
+        if page == 0 && query.has_local_filters() {
+            show_message(tl!("search-local-only")).warn();
+        }
+        let search = query.text();
         let order = {
             let order = match self.current_order {
                 ChartOrder::Default => "updated",
@@ -303,6 +311,7 @@ impl LibraryPage {
             .iter()
             .cloned()
             .chain(self.tags.unwanted.as_ref().unwrap().tags().iter().map(|it| format!("-{it}")))
+            .chain(query.tags())
             .join(",");
         let division = self.tags.division;
         let rating_range = format!("{},{}", self.rating.rate.score as f32 / 10., self.rating.rate_upper.as_ref().unwrap().score as f32 / 10.);
@@ -356,34 +365,19 @@ impl LibraryPage {
             charts_local.reverse();
         }
 
-        let search_by_id = if let Some(id_str) = self.search_str.strip_prefix('#') {
-            id_str.trim().parse::<i32>().ok()
-        } else {
-            None
-        };
-        let local_matcher = |chart: &ChartItem| {
-            if let Some(search_id) = search_by_id {
-                chart.info.id == Some(search_id)
-            } else {
-                chart.info.name.to_ascii_lowercase().contains(&self.search_str.to_ascii_lowercase())
-            }
-        };
-
+        let query = ChartQuery::parse(&self.search_str);
         let list = self.tabs.selected_mut();
         if list.ty == ChartListType::Local {
             let mut charts = Vec::new();
-            if let Some(fav_index) = self.current_fav_index {
-                let local_chart_map: HashMap<&str, &ChartItem> = charts_local.iter().map(|it| (it.local_path.as_deref().unwrap(), *it)).collect();
-                charts.extend(get_data().collection_by_index(fav_index).charts.iter().filter_map(|it| {
-                    if let Some(item) = local_chart_map.get(&*it.path) {
-                        local_matcher(item).then(|| ChartDisplayItem::new(Some((*item).clone()), None))
-                    } else if let Some(chart) = it.info.as_ref() {
-                        search_by_id
-                            .map_or_else(
-                                || chart.info.name.to_ascii_lowercase().contains(&self.search_str.to_ascii_lowercase()),
-                                |search_id| chart.info.id == Some(search_id),
-                            )
-                            .then(|| {
+            with_chart_index(|index| {
+                let local_matcher = |chart: &ChartItem| index.matches(&query, chart.local_path.as_deref(), &chart.info);
+                if let Some(fav_index) = self.current_fav_index {
+                    let local_chart_map: HashMap<&str, &ChartItem> = charts_local.iter().map(|it| (it.local_path.as_deref().unwrap(), *it)).collect();
+                    charts.extend(get_data().collection_by_index(fav_index).charts.iter().filter_map(|it| {
+                        if let Some(item) = local_chart_map.get(&*it.path) {
+                            local_matcher(item).then(|| ChartDisplayItem::new(Some((*item).clone()), None))
+                        } else if let Some(chart) = it.info.as_ref() {
+                            index.matches(&query, None, &chart.info).then(|| {
                                 ChartDisplayItem::new(
                                     Some(ChartItem {
                                         info: chart.info.clone(),
@@ -394,29 +388,42 @@ impl LibraryPage {
                                     None,
                                 )
                             })
-                    } else if let Some(local_path) = it.find_local_path().unwrap() {
-                        let item = local_chart_map.get(&*local_path).unwrap();
-                        local_matcher(item).then(|| ChartDisplayItem::new(Some((*item).clone()), None))
-                    } else {
-                        warn!("No info found for chart ref {it:?}");
-                        None
+                        } else if let Some(local_path) = it.find_local_path().unwrap() {
+                            let item = local_chart_map.get(&*local_path).unwrap();
+                            local_matcher(item).then(|| ChartDisplayItem::new(Some((*item).clone()), None))
+                        } else {
+                            warn!("No info found for chart ref {it:?}");
+                            None
+                        }
+                    }));
+                    self.current_order.apply(&mut charts, |it| it.chart.as_ref().unwrap());
+                    if self.order_rev {
+                        charts.reverse();
                     }
-                }));
-                self.current_order.apply(&mut charts, |it| it.chart.as_ref().unwrap());
-                if self.order_rev {
-                    charts.reverse();
-                }
-            } else {
-                if cfg!(closed) {
-                    charts.push(ChartDisplayItem::new(None, None));
+                } else {
+                    if cfg!(closed) {
+                        charts.push(ChartDisplayItem::new(None, None));
+                    }
+                    // charts of the same pack are shown as one song, listing every difficulty
+                    let mut packs = HashSet::new();
+                    charts.extend(charts_local.iter().filter(|it| local_matcher(it)).filter_map(|it| {
+                        let mut item = (*it).clone();
+                        if let Some(local_path) = it.local_path.as_deref() {
+                            if let Some(root) = pack_root(local_path) {
+                                if !packs.insert(root.to_owned()) {
+                                    return None;
+                                }
+                                item.info.level = pack_members(local_path)
+                                    .iter()
+                                    .map(|it| it.info.level.as_str())
+                                    .collect::<Vec<_>>()
+                                    .join(" / ");
+                            }
+                        }
+                        Some(ChartDisplayItem::new(Some(item), None))
+                    }))
                 }
-                charts.extend(
-                    charts_local
-                        .iter()
-                        .filter(|it| local_matcher(it))
-                        .map(|it| ChartDisplayItem::new(Some((*it).clone()), None)),
-                )
-            }
+            });
             list.view.set(s.t, charts);
         }
     }
@@ -689,7 +696,6 @@ impl Page for LibraryPage {
             || self.export_task.is_some()
             || self.multi_create_fav_task.is_some()
             || self.manage_fav_pre_task.is_some()
-            || self.manage_fav_task.is_some()
             || self.refresh_local_fav_task.is_some()
         {
             return Ok(true);
@@ -700,6 +706,10 @@ impl Page for LibraryPage {
                 self.order_menu.touch(touch, t);
                 return Ok(true);
             }
+            if self.import_menu.showing() {
+                self.import_menu.touch(touch, t);
+                return Ok(true);
+            }
             if self.order_meta_menu.showing() {
                 self.order_meta_menu.touch(touch, t);
                 return Ok(true);
@@ -757,7 +767,7 @@ impl Page for LibraryPage {
             ChartListType::Local => {
                 if self.tabs.selected().view.multi_select.is_none() {
                     if self.import_btn.touch(touch, t) {
-                        request_file("_import");
+                        self.need_show_import_menu = true;
                         return Ok(true);
                     }
                     if self.fav_btn.touch(touch, t) {
@@ -766,6 +776,7 @@ impl Page for LibraryPage {
                             self.rank_icons.clone(),
                             self.current_fav_index,
                             None,
+                            ChartQuery::parse(&self.search_str),
                         ))));
                         return Ok(true);
                     }
@@ -842,16 +853,12 @@ impl Page for LibraryPage {
                 self.rank_icons.clone(),
                 self.current_fav_index,
                 Some(chosen_cover),
+                ChartQuery::parse(&self.search_str),
             ))));
         }
 
         self.check_fav_page(s);
 
-        if self.tabs.selected().ty == ChartListType::Local && self.current_order == ChartOrder::Rating {
-            self.current_order = ChartOrder::Default;
-            self.order_rev = true;
-        }
-
         self.tags.update(t);
         self.rating.update(t);
 
@@ -869,7 +876,8 @@ impl Page for LibraryPage {
         }
         if cfg!(closed) && self.tabs.selected_mut().view.clicked_special {
             let icons = Arc::clone(&self.icons);
-            self.next_page_task = Some(Box::pin(async move { Ok(NextPage::Overlay(Box::new(CollectionPage::new(icons).await?))) }));
+            let query = ChartQuery::parse(&self.search_str);
+            self.next_page_task = Some(Box::pin(async move { Ok(NextPage::Overlay(Box::new(CollectionPage::new(icons, query).await?))) }));
             self.tabs.selected_mut().view.clicked_special = false;
         }
         if let Some(task) = &mut self.next_page_task {
@@ -910,6 +918,7 @@ impl Page for LibraryPage {
             }
         }
         self.order_menu.update(t);
+        self.import_menu.update(t);
         self.order_meta_menu.update(t);
         self.multi_operation_menu.update(t);
         self.multi_select_menu.update(t);
@@ -925,10 +934,8 @@ impl Page for LibraryPage {
                     let col = data.collection_by_index(index);
                     if let Some(col_id) = col.id {
                         if !data.config.offline_mode {
-                            self.sync_fav_task = Some(Task::new(async move {
-                                let resp: Collection = recv_raw(Client::get(format!("/collection/{col_id}"))).await?.json().await?;
-                                Ok(Some(resp))
-                            }));
+                            self.sync_fav_task =
+                                Some(Task::new(async move { Ok(recv_raw(Client::get(format!("/collection/{col_id}"))).await?.json().await?) }));
                         }
                     } else {
                         let charts = col.charts.clone();
@@ -1044,11 +1051,14 @@ impl Page for LibraryPage {
             let mut local_paths = HashSet::new();
             for chart in &selected {
                 if let Some(path) = chart.find_local_path()? {
-                    match std::fs::remove_dir_all(format!("{}/{path}", dir::charts()?)) {
+                    let members = pack_members(&path);
+                    let root = pack_root(&path).unwrap_or(&path);
+                    match std::fs::remove_dir_all(format!("{}/{root}", dir::charts()?)) {
                         Ok(_) => {}
                         Err(err) if err.kind() == io::ErrorKind::NotFound => {}
                         Err(err) => return Err(err.into()),
                     }
+                    local_paths.extend(members.into_iter().map(|it| Cow::Borrowed(it.local_path.as_str())));
                     local_paths.insert(path);
                 }
             }
@@ -1072,6 +1082,14 @@ impl Page for LibraryPage {
                 _ => {}
             }
         }
+        if self.import_menu.changed() {
+            match self.import_menu.selected() {
+                0 => request_file("_import"),
+                1 => request_folder("_import_bulk"),
+                _ => {}
+            }
+            self.import_menu.set_selected(usize::MAX);
+        }
         if self.order_menu.changed() {
             self.current_order = self.order_menu_options[self.order_menu.selected()];
             self.order_rev = matches!(self.current_order, ChartOrder::Default | ChartOrder::Rating);
@@ -1197,17 +1215,14 @@ impl Page for LibraryPage {
             let data = get_data_mut();
             if let Some(index) = self.current_fav_index {
                 let uuid = data.collection_uuids()[index];
-                let mut col = data.collection_info(&uuid).as_ref().clone();
-                let online = col.id.is_some();
+                let before = data.collection_info(&uuid);
+                let mut col = before.as_ref().clone();
                 let chart = col.charts.remove(from);
                 col.charts.insert(to, chart);
+                let charts = col.charts.iter().filter_map(|it| it.id()).collect();
                 data.set_collection_info(&uuid, col)?;
                 let _ = save_data();
-                if online && !data.config.offline_mode {
-                    if let Some(task) = FavoritesPage::sync_to_cloud_task(index, false) {
-                        self.sync_fav_task = Some(task);
-                    }
-                }
+                collection_sync::record_edit(uuid, &before, CollectionOp::Reorder { charts })?;
             } else {
                 if self.order_rev {
                     let chart = data.charts.remove(data.charts.len() - from - 1);
@@ -1248,30 +1263,17 @@ impl Page for LibraryPage {
             if let Some(res) = task.take() {
                 match res {
                     Err(err) => show_error(err.context(tl!("fav-sync-failed"))),
-                    Ok(Some(col)) => {
-                        let data = get_data();
-                        let uuid = data.collection_uuids()[self.current_fav_index.unwrap()];
-                        let local = data.collection_info(&uuid);
-                        data.set_collection_info(&uuid, local.merge(&col))?;
+                    Ok(col) => {
+                        let uuid = get_data().collection_uuids()[self.current_fav_index.unwrap()];
+                        collection_sync::merge_remote(&uuid, &col)?;
                         let _ = save_data();
                         show_message(tl!("fav-synced")).ok();
                         self.sync_local(s);
                     }
-                    Ok(None) => {
-                        use crate::page::favorites::{tl as ftl, L10N_LOCAL};
-                        confirm_dialog(ftl!("sync-to-cloud"), ftl!("sync-outdated"), self.force_sync_to_cloud.clone());
-                    }
                 }
                 self.sync_fav_task = None;
             }
         }
-        if self.force_sync_to_cloud.swap(false, Ordering::SeqCst) {
-            if let Some(index) = self.current_fav_index {
-                if let Some(task) = FavoritesPage::sync_to_cloud_task(index, true) {
-                    self.sync_fav_task = Some(task);
-                }
-            }
-        }
         if let Some(config) = take_export() {
             fn export_inner(paths: Vec<String>, output: File, progress: Arc<AtomicU32>) -> Result<()> {
                 let charts = dir::charts()?;
@@ -1280,12 +1282,21 @@ impl Page for LibraryPage {
                     .compression_method(zip::CompressionMethod::Stored)
                     .unix_permissions(0o755)
                     .last_modified_time(chrono::Utc::now().naive_utc().try_into().unwrap_or_default());
+                let mut packs = HashSet::new();
                 for (i, name) in paths.iter().enumerate() {
-                    zip.start_file(format!("{name}.zip"), options)?;
+                    progress.store(i as u32 + 1, Ordering::Relaxed);
                     let mut chart_bytes = Vec::new();
-                    compress_folder(Path::new(&format!("{charts}/{name}")), &mut Cursor::new(&mut chart_bytes))?;
+                    if let Some(root) = pack_root(name) {
+                        if !packs.insert(root) {
+                            continue;
+                        }
+                        zip.start_file(format!("{root}.zip"), options)?;
+                        export_pack(name, &mut Cursor::new(&mut chart_bytes))?;
+                    } else {
+                        zip.start_file(format!("{name}.zip"), options)?;
+                        export_chart(Path::new(&format!("{charts}/{name}")), &mut Cursor::new(&mut chart_bytes))?;
+                    }
                     zip.write_all(&chart_bytes)?;
-                    progress.store(i as u32 + 1, Ordering::Relaxed);
                 }
 
                 zip.start_file("export.json", options.compression_method(zip::CompressionMethod::Deflated))?;
@@ -1348,10 +1359,8 @@ impl Page for LibraryPage {
                         let col = data.collection_info(&uuid).as_ref().clone();
                         match col.update(uuid, &charts, add) {
                             CollectionUpdate::Unchanged => {}
-                            CollectionUpdate::Updated { sync_task, add } => {
-                                if let Some(task) = sync_task {
-                                    self.manage_fav_task = Some(task);
-                                } else if add {
+                            CollectionUpdate::Updated { add } => {
+                                if add {
                                     show_message(tl!("multi-added-to-fav")).duration(1.).ok();
                                 } else {
                                     show_message(tl!("multi-removed-from-fav")).duration(1.).ok();
@@ -1373,29 +1382,6 @@ impl Page for LibraryPage {
                 self.manage_fav_pre_task = None;
             }
         }
-        if let Some(task) = &mut self.manage_fav_task {
-            if let Some(res) = task.take() {
-                match res {
-                    Err(err) => {
-                        show_error(err);
-                    }
-                    Ok((col, added)) => {
-                        let data = get_data();
-                        if let Some(uuid) = data.collection_uuids().iter().find(|it| data.collection_info(it).id == Some(col.id)) {
-                            let uuid = *uuid;
-                            let local = data.collection_info(&uuid);
-                            data.set_collection_info(&uuid, local.merge(&col))?;
-                        }
-                        if added {
-                            show_message(tl!("multi-added-to-fav")).ok();
-                        } else {
-                            show_message(tl!("multi-removed-from-fav")).ok();
-                        }
-                    }
-                }
-                self.manage_fav_task = None;
-            }
-        }
 
         Ok(())
     }
@@ -1480,6 +1466,13 @@ impl Page for LibraryPage {
                         let cr = r.feather(-0.01);
                         ui.fill_rect(cr, (*self.icons.plus, cr, ScaleType::Fit));
                     });
+                    if self.need_show_import_menu {
+                        self.need_show_import_menu = false;
+                        self.import_menu.set_auto_adjust(Some(ui.screen_rect().nonuniform_feather(-0.03, -0.05)));
+                        self.import_menu.set_bottom(true);
+                        self.import_menu.set_selected(usize::MAX);
+                        self.import_menu.show(ui, t, Rect::new(r.x, r.bottom() + 0.02, 0.35, 0.2));
+                    }
                     r.x -= r.w + 0.02;
                 }
 
@@ -1513,11 +1506,7 @@ impl Page for LibraryPage {
                     self.need_show_order_meta_menu = false;
                     self.order_meta_menu
                         .set_auto_adjust(Some(ui.screen_rect().nonuniform_feather(-0.03, -0.05)));
-                    if self.tabs.selected().ty == ChartListType::Local {
-                        self.order_menu_options = vec![ChartOrder::Default, ChartOrder::Name, ChartOrder::Difficulty];
-                    } else {
-                        self.order_menu_options = vec![ChartOrder::Default, ChartOrder::Rating, ChartOrder::Name, ChartOrder::Difficulty];
-                    }
+                    self.order_menu_options = vec![ChartOrder::Default, ChartOrder::Rating, ChartOrder::Name, ChartOrder::Difficulty];
                     self.order_meta_menu.set_bottom(true);
                     self.order_meta_menu.set_auto_dismiss(false);
                     self.update_order_meta_menu_options();
@@ -1576,6 +1565,7 @@ impl Page for LibraryPage {
         }
         self.order_menu.render(ui, t, 1.);
         self.order_meta_menu.render(ui, t, 1.);
+        self.import_menu.render(ui, t, 1.);
         if self.need_show_order_menu {
             self.need_show_order_menu = false;
             self.order_menu.set_bottom(true);
@@ -1622,11 +1612,7 @@ impl Page for LibraryPage {
             let total = self.export_total;
             ui.full_loading(tl!("multi-exporting", "current" => current, "total" => total), t);
         }
-        if self.multi_create_fav_task.is_some()
-            || self.manage_fav_pre_task.is_some()
-            || self.manage_fav_task.is_some()
-            || self.refresh_local_fav_task.is_some()
-        {
+        if self.multi_create_fav_task.is_some() || self.manage_fav_pre_task.is_some() || self.refresh_local_fav_task.is_some() {
             ui.full_loading_simple(t);
         }
         Ok(())
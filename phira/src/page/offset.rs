@@ -3,16 +3,30 @@ prpr_l10n::tl_file!("cali");
 use std::borrow::Cow;
 
 use super::{Page, SharedState};
-use crate::{get_data, get_data_mut, save_data};
+use crate::{
+    calibration::{self, Confidence},
+    get_data, get_data_mut, save_data,
+};
 use anyhow::{Context, Result};
 use macroquad::prelude::*;
 use prpr::{
-    core::{ParticleEmitter, ResourcePack, NOTE_WIDTH_RATIO_BASE},
-    ext::{create_audio_manger, semi_black, RectExt, SafeTexture, ScaleType},
+    config::Config,
+    core::{NoteTexture, ParticleEmitter, ResourcePack, NOTE_WIDTH_RATIO_BASE},
+    ext::{audio_output_device, create_audio_manger, semi_black, semi_white, RectExt, SafeTexture, ScaleType},
+    scene::show_message,
     time::TimeManager,
-    ui::{Slider, Ui},
+    ui::{DRectButton, Dialog, Slider, Ui},
 };
 use sasa::{AudioClip, AudioManager, Music, MusicParams, PlaySfxParams, Sfx};
+use tracing::warn;
+
+/// The offset being calibrated: the one of `device` if it has its own, otherwise the shared one.
+fn offset_mut<'a>(config: &'a mut Config, device: Option<&str>) -> &'a mut f32 {
+    match device.and_then(|it| config.device_offsets.get_mut(it)) {
+        Some(offset) => offset,
+        None => &mut config.offset,
+    }
+}
 
 pub struct OffsetPage {
     _audio: AudioManager,
@@ -22,12 +36,15 @@ pub struct OffsetPage {
     tm: TimeManager,
     cali_last: bool,
 
-    click: SafeTexture,
+    click: NoteTexture,
     _hit_fx: SafeTexture,
     emitter: ParticleEmitter,
     color: Color,
 
     slider: Slider,
+    auto_btn: DRectButton,
+    shared_btn: DRectButton,
+    device: Option<String>,
 
     touched: bool,
     touch: Option<(f32, f32)>,
@@ -70,11 +87,69 @@ impl OffsetPage {
             color: respack.info.fx_perfect(),
 
             slider: Slider::new(-500.0..500.0, 5.),
+            auto_btn: DRectButton::new(),
+            shared_btn: DRectButton::new(),
+            device: audio_output_device(),
 
             touched: false,
             touch: None,
         })
     }
+
+    fn has_device_offset(&self) -> bool {
+        self.device.as_ref().is_some_and(|it| get_data().config.device_offsets.contains_key(it))
+    }
+
+    fn auto_calibrate(&self) {
+        let Some(estimate) = calibration::estimate(self.device.as_deref()) else {
+            show_message(tl!("auto-not-enough", "plays" => calibration::MIN_PLAYS)).warn();
+            return;
+        };
+        let ms = |secs: f32| (secs * 1000.).round() as i32;
+        let confidence = match estimate.confidence {
+            Confidence::Low => tl!("auto-confidence-low"),
+            Confidence::Medium => tl!("auto-confidence-medium"),
+            Confidence::High => tl!("auto-confidence-high"),
+        };
+        let current = get_data().config.offset_for(self.device.as_deref());
+        let content = tl!(
+            "auto-content",
+            "offset" => ms(estimate.offset),
+            "current" => ms(current),
+            "error" => ms(estimate.error).max(1),
+            "plays" => estimate.plays,
+            "hits" => estimate.hits,
+            "confidence" => confidence
+        );
+        let mut buttons = vec![tl!("auto-cancel").into_owned(), tl!("auto-apply").into_owned()];
+        if self.device.is_some() {
+            buttons.push(tl!("auto-apply-device").into_owned());
+        }
+        let device = self.device.clone();
+        let offset = estimate.offset;
+        Dialog::plain(tl!("auto-title"), content)
+            .buttons(buttons)
+            .listener(move |_dialog, pos| {
+                let config = &mut get_data_mut().config;
+                match (pos, &device) {
+                    (1, _) => {
+                        config.offset = offset;
+                        if let Some(device) = &device {
+                            config.device_offsets.remove(device);
+                        }
+                    }
+                    (2, Some(device)) => {
+                        config.device_offsets.insert(device.clone(), offset);
+                    }
+                    _ => return false,
+                }
+                if let Err(err) = save_data() {
+                    warn!(?err, "failed to save offset");
+                }
+                false
+            })
+            .show();
+    }
 }
 
 impl Page for OffsetPage {
@@ -114,9 +189,19 @@ impl Page for OffsetPage {
     fn touch(&mut self, touch: &Touch, s: &mut SharedState) -> Result<bool> {
         let t = s.t;
         let config = &mut get_data_mut().config;
-        let mut offset = config.offset * 1000.;
+        let current = offset_mut(config, self.device.as_deref());
+        let mut offset = *current * 1000.;
         if self.slider.touch(touch, t, &mut offset).is_some() {
-            config.offset = offset / 1000.;
+            *current = offset / 1000.;
+            return Ok(true);
+        }
+        if self.auto_btn.touch(touch, t) {
+            self.auto_calibrate();
+            return Ok(true);
+        }
+        if self.has_device_offset() && self.shared_btn.touch(touch, t) {
+            get_data_mut().config.device_offsets.remove(self.device.as_ref().unwrap());
+            save_data()?;
             return Ok(true);
         }
         if touch.phase == TouchPhase::Started && touch.position.x < 0. {
@@ -158,7 +243,8 @@ impl Page for OffsetPage {
             let ot = t;
 
             let config = &get_data().config;
-            let mut t = self.tm.now() as f32 - config.offset;
+            let config_offset = config.offset_for(self.device.as_deref());
+            let mut t = self.tm.now() as f32 - config_offset;
             if t < 0. {
                 t += 2.;
             }
@@ -201,9 +287,31 @@ impl Page for OffsetPage {
                 }
             }
 
-            let offset = config.offset * 1000.;
+            let offset = config_offset * 1000.;
             self.slider
                 .render(ui, Rect::new(0.46, -0.1, 0.45, 0.2), ot, offset, format!("{offset:.0}ms"));
+
+            let has_device_offset = self.has_device_offset();
+            if let Some(device) = &self.device {
+                let text = if has_device_offset {
+                    tl!("device-own", "device" => device.as_str())
+                } else {
+                    tl!("device-shared", "device" => device.as_str())
+                };
+                ui.text(text)
+                    .pos(0.46 + 0.225, 0.12)
+                    .anchor(0.5, 0.)
+                    .max_width(0.45)
+                    .size(0.36)
+                    .color(semi_white(0.6))
+                    .draw();
+            }
+            let mut r = Rect::new(0.46, 0.2, 0.45, 0.09);
+            self.auto_btn.render_text(ui, r, ot, tl!("auto"), 0.5, true);
+            if has_device_offset {
+                r.y += r.h + 0.02;
+                self.shared_btn.render_text(ui, r, ot, tl!("use-shared"), 0.5, false);
+            }
         });
 
         self.emitter.draw(get_frame_time());
@@ -10,27 +10,272 @@ use crate::{
 use anyhow::Result;
 use macroquad::prelude::*;
 use prpr::{
-    core::{NoteStyle, ParticleEmitter, ResPackInfo, ResourcePack},
+    config::{Config, Mods},
+    core::{Chart, NoteStyle, ParticleEmitter, ResPackInfo, ResPackProblem, Resource, ResourcePack},
     ext::{create_audio_manger, poll_future, semi_black, semi_white, LocalTask, RectExt, SafeTexture, ScaleType},
-    scene::{request_file, show_error, show_message},
+    fs::{fs_from_assets, fs_from_file, FileSystem, PatchedFileSystem},
+    info::ChartInfo,
+    judge::Judge,
+    scene::{request_file, show_error, show_message, GameScene},
     ui::{DRectButton, Dialog, Scroll, Ui},
 };
 use sasa::{AudioManager, PlaySfxParams, Sfx};
 use serde_yaml::Error;
 use std::{
     borrow::Cow,
+    collections::HashMap,
     fs::File,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::SystemTime,
 };
 
 fn build_emitter(pack: &ResourcePack) -> Result<ParticleEmitter> {
     ParticleEmitter::new(pack, get_data().config.note_scale * 0.6, pack.info.hide_particles)
 }
 
+fn problem_to_string(problem: &ResPackProblem) -> String {
+    match problem {
+        ResPackProblem::MissingFile(file) => tl!("problem-missing", "file" => file.as_str()),
+        ResPackProblem::InvalidInfo(error) => tl!("problem-info", "error" => error.as_str()),
+        ResPackProblem::InvalidImage { file, error } => tl!("problem-image", "file" => file.as_str(), "error" => error.as_str()),
+        ResPackProblem::AtlasTooLarge { file, atlas, height } => {
+            tl!("problem-atlas", "file" => file.as_str(), "head" => atlas.1, "tail" => atlas.0, "height" => *height)
+        }
+        ResPackProblem::HitFxGrid { file, size, grid } => tl!(
+            "problem-hit-fx", "file" => file.as_str(), "width" => size.0, "height" => size.1, "columns" => grid.0, "rows" => grid.1
+        ),
+        ResPackProblem::AnimationFrames { file, width, frames } => {
+            tl!("problem-frames", "file" => file.as_str(), "width" => *width, "frames" => *frames)
+        }
+        ResPackProblem::InvalidFont { file, error } => tl!("problem-font", "file" => file.as_str(), "error" => error.as_str()),
+        ResPackProblem::InvalidParticles(error) => tl!("problem-particles", "error" => error.as_str()),
+        ResPackProblem::UnsupportedAudio { file, error } => tl!("problem-audio", "file" => file.as_str(), "error" => error.as_str()),
+        ResPackProblem::IgnoredAudio(file) => tl!("problem-audio-ignored", "file" => file.as_str()),
+    }
+}
+
+fn show_problems(problems: &[ResPackProblem]) {
+    let content = problems
+        .iter()
+        .map(|it| format!("- {}", problem_to_string(it)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Dialog::plain(tl!("problems", "count" => problems.len()), content)
+        .listener(|_dialog, pos| pos == -2)
+        .show();
+}
+
+/// Draws a hold note whose body covers `r`, with its head below and its tail above.
+fn draw_hold(ui: &mut Ui, pack: &ResourcePack, style: &NoteStyle, r: Rect) {
+    let width = r.w;
+    let conv = |r: Rect, tex: &SafeTexture| Rect::new(r.x * tex.width(), r.y * tex.height(), r.w * tex.width(), r.h * tex.height());
+    let tr = conv(style.hold_tail_rect(), &style.hold);
+    let factor = if pack.info.hold_compact { 0.5 } else { 1. };
+    let h = tr.h / tr.w * width;
+    let r2 = Rect::new(r.x, r.y - h * factor, width, h);
+    let r2 = ui.rect_to_global(r2);
+    draw_texture_ex(
+        *style.hold,
+        r2.x,
+        r2.y,
+        semi_white(ui.alpha),
+        DrawTextureParams {
+            source: Some(tr),
+            dest_size: Some(vec2(r2.w, r2.h)),
+            ..Default::default()
+        },
+    );
+    let tr = conv(style.hold_head_rect(), &style.hold);
+    let h = tr.h / tr.w * width;
+    let r2 = Rect::new(r.x, r.bottom() - h * (1. - factor), width, h);
+    let r2 = ui.rect_to_global(r2);
+    draw_texture_ex(
+        *style.hold,
+        r2.x,
+        r2.y,
+        semi_white(ui.alpha),
+        DrawTextureParams {
+            source: Some(tr),
+            dest_size: Some(vec2(r2.w, r2.h)),
+            ..Default::default()
+        },
+    );
+    let r2 = ui.rect_to_global(r);
+    draw_texture_ex(
+        if pack.info.hold_repeat {
+            **style.hold_body.as_ref().unwrap()
+        } else {
+            *style.hold
+        },
+        r2.x,
+        r2.y,
+        semi_white(ui.alpha),
+        DrawTextureParams {
+            source: Some({
+                if pack.info.hold_repeat {
+                    let hold_body = style.hold_body.as_ref().unwrap();
+                    let w = hold_body.width();
+                    Rect::new(0., 0., w, r2.h / width / 2. * w)
+                } else {
+                    conv(style.hold_body_rect(), &style.hold)
+                }
+            }),
+            dest_size: Some(vec2(r2.w, r2.h)),
+            ..Default::default()
+        },
+    )
+}
+
+/// The chart played by [`DemoPlayer`]: every kind of note, with simultaneous ones to show the
+/// highlighted style. The bpm is 60, so a note at time `32` is hit at 1s.
+const DEMO_CHART: &str = include_str!("respack_demo.json");
+/// How long the demo chart plays before starting over, in seconds
+const DEMO_LENGTH: f64 = 6.5;
+
+/// Plays the demo chart in a loop with the resource pack being previewed, through the same chart
+/// renderer and autoplay judge as the game.
+struct DemoPlayer {
+    chart: Chart,
+    res: Resource,
+    judge: Judge,
+    target: Option<RenderTarget>,
+    start: Option<f32>,
+}
+
+impl DemoPlayer {
+    async fn load(path: Option<PathBuf>) -> Result<Self> {
+        let info = ChartInfo {
+            chart: "chart.json".to_owned(),
+            // only needed for its length, the demo plays just the hitsounds
+            music: "cali.ogg".to_owned(),
+            ..Default::default()
+        };
+        let patches = HashMap::from([(info.chart.clone(), DEMO_CHART.as_bytes().to_vec())]);
+        let mut fs: Box<dyn FileSystem> = Box::new(PatchedFileSystem(fs_from_assets("")?, patches));
+        let (chart, ..) = GameScene::load_chart(fs.as_mut(), &info).await?;
+        let config = Config {
+            res_pack_path: path.map(|it| it.to_string_lossy().into_owned()),
+            mods: Mods::AUTOPLAY,
+            speed: 1.,
+            aspect_ratio: None,
+            sample_count: 1,
+            ..get_data().config.clone()
+        };
+        let background = SafeTexture::from(Texture2D::from_rgba8(1, 1, &[0, 0, 0, 0]));
+        let res = Resource::new(config, info, fs, None, background.clone(), background, true).await?;
+        let judge = Judge::new(&chart, None);
+        Ok(Self {
+            chart,
+            res,
+            judge,
+            target: None,
+            start: None,
+        })
+    }
+
+    fn render(&mut self, ui: &mut Ui, r: Rect, t: f32) {
+        let start = *self.start.get_or_insert(t);
+        let time = (t - start) as f64 % DEMO_LENGTH;
+        if time < self.res.time {
+            self.chart.reset();
+            self.judge.reset();
+        }
+        self.res.time = time;
+
+        // the chart is drawn at the size it takes on the screen
+        let gr = ui.rect_to_global(r);
+        let size = ((gr.w / 2. * screen_width()) as u32, (gr.h / 2. * screen_width()) as u32);
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        let target = match self.target {
+            Some(target) if (target.texture.width() as u32, target.texture.height() as u32) == size => target,
+            _ => {
+                let target = render_target(size.0, size.1);
+                target.texture.set_filter(FilterMode::Linear);
+                self.target = Some(target);
+                target
+            }
+        };
+        let res = &mut self.res;
+        res.camera.render_target = Some(target);
+        res.update_size((0, 0, size.0 as i32, size.1 as i32));
+        self.judge.update(res, &mut self.chart, &mut Vec::new());
+        self.judge.schedule_hitsounds(res);
+        self.chart.update(res);
+
+        push_camera_state();
+        set_camera(&Camera2D {
+            zoom: vec2(1., -(size.0 as f32 / size.1 as f32)),
+            render_target: Some(target),
+            ..Default::default()
+        });
+        clear_background(Color::default());
+        let gl = unsafe { get_internal_gl() };
+        set_camera(&res.camera);
+        gl.quad_gl.render_pass(Some(target.render_pass));
+        gl.quad_gl.viewport(res.camera.viewport);
+        self.chart.render(ui, res);
+        if res.config.particle {
+            res.emitter.draw(get_frame_time());
+            self.chart.render_particles(res);
+        }
+        gl.flush();
+        pop_camera_state();
+
+        draw_texture_ex(
+            target.texture,
+            gr.x,
+            gr.y,
+            semi_white(ui.alpha),
+            DrawTextureParams {
+                dest_size: Some(vec2(gr.w, gr.h)),
+                flip_y: true,
+                ..Default::default()
+            },
+        );
+    }
+}
+
+/// Polls a pack folder for changes, so that pack authors can edit it in place.
+struct PackWatcher {
+    path: PathBuf,
+    stamp: Option<SystemTime>,
+    next_check: f32,
+}
+
+impl PackWatcher {
+    const INTERVAL: f32 = 0.5;
+
+    fn new(path: PathBuf) -> Self {
+        let stamp = Self::stamp(&path);
+        Self { path, stamp, next_check: 0. }
+    }
+
+    /// The latest modification time of the folder or any file in it. Deleting a file changes the
+    /// time of the folder itself.
+    fn stamp(path: &Path) -> Option<SystemTime> {
+        std::fs::read_dir(path)
+            .ok()?
+            .filter_map(|it| it.ok()?.metadata().ok()?.modified().ok())
+            .chain(path.metadata().ok()?.modified().ok())
+            .max()
+    }
+
+    fn changed(&mut self, t: f32) -> bool {
+        if t < self.next_check {
+            return false;
+        }
+        self.next_check = t + Self::INTERVAL;
+        let stamp = Self::stamp(&self.path);
+        stamp != std::mem::replace(&mut self.stamp, stamp)
+    }
+}
+
 pub struct ResPackItem {
     path: Option<PathBuf>,
     name: String,
@@ -72,12 +317,22 @@ pub struct ResPackPage {
 
     info_btn: DRectButton,
     delete_btn: DRectButton,
+    dev_btn: DRectButton,
+    preview_btn: DRectButton,
 
     should_delete: Arc<AtomicBool>,
 
     emitter: Option<ParticleEmitter>,
     sfxs: Option<[Sfx; 3]>,
     last_round: u32,
+
+    /// Watches the selected pack while in dev mode
+    watcher: Option<PackWatcher>,
+    validate_task: LocalTask<Result<Vec<ResPackProblem>>>,
+    /// The error the last load failed with, shown if validation finds nothing
+    load_error: Option<anyhow::Error>,
+    demo: Option<DemoPlayer>,
+    demo_task: LocalTask<Result<DemoPlayer>>,
 }
 
 impl ResPackPage {
@@ -138,6 +393,8 @@ impl ResPackPage {
             icons,
 
             info_btn: delete_btn.clone(),
+            dev_btn: delete_btn.clone(),
+            preview_btn: delete_btn.clone(),
             delete_btn,
 
             should_delete: Arc::new(AtomicBool::default()),
@@ -145,8 +402,51 @@ impl ResPackPage {
             emitter: None,
             sfxs: None,
             last_round: u32::MAX,
+
+            watcher: None,
+            validate_task: None,
+            load_error: None,
+            demo: None,
+            demo_task: None,
         })
     }
+
+    fn validate(&mut self) {
+        let Some(path) = self.items[self.index].path.clone() else {
+            return;
+        };
+        self.validate_task = Some(Box::pin(async move {
+            let mut fs = fs_from_file(&path)?;
+            Ok(ResourcePack::validate(fs.as_mut()).await)
+        }));
+    }
+
+    fn demo_on(&self) -> bool {
+        self.demo.is_some() || self.demo_task.is_some()
+    }
+
+    /// Starts over the demo with the selected pack.
+    fn load_demo(&mut self) {
+        self.demo = None;
+        self.demo_task = Some(Box::pin(DemoPlayer::load(self.items[self.index].path.clone())));
+    }
+
+    fn select(&mut self, index: usize) -> Result<()> {
+        self.index = index;
+        get_data_mut().respack_id = index;
+        save_data()?;
+        self.items[index].load();
+        if self.demo_on() {
+            self.load_demo();
+        }
+        if self.watcher.is_some() {
+            self.watcher = self.items[index].path.clone().map(PackWatcher::new);
+            if self.watcher.is_none() {
+                show_message(tl!("dev-mode-off")).ok();
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Page for ResPackPage {
@@ -164,17 +464,15 @@ impl Page for ResPackPage {
             return Ok(true);
         }
         if self.items[self.index].load_task.is_none() {
-            for (index, item) in self.items.iter_mut().enumerate() {
-                if item.btn.touch(touch, t) {
-                    self.index = index;
-                    get_data_mut().respack_id = index;
-                    save_data()?;
-                    item.load();
-                    return Ok(true);
-                }
+            if let Some(index) = self.items.iter_mut().position(|item| item.btn.touch(touch, t)) {
+                self.select(index)?;
+                return Ok(true);
             }
         }
-        if self.info_btn.touch(touch, t) {
+        // the info and preview buttons are only shown for a loaded pack, and a failed reload leaves
+        // their stale rects behind
+        let loaded = self.items[self.index].loaded.is_some();
+        if loaded && self.info_btn.touch(touch, t) {
             let item = &self.items[self.index];
             let info = &item.loaded.as_ref().unwrap().info;
             Dialog::plain(
@@ -193,6 +491,27 @@ impl Page for ResPackPage {
             confirm_delete(self.should_delete.clone());
             return Ok(true);
         }
+        if self.dev_btn.touch(touch, t) {
+            if self.watcher.take().is_some() {
+                show_message(tl!("dev-mode-off")).ok();
+            } else if let Some(path) = self.items[self.index].path.clone() {
+                self.watcher = Some(PackWatcher::new(path));
+                self.validate();
+                show_message(tl!("dev-mode-on")).ok();
+            } else {
+                show_message(tl!("dev-mode-builtin")).error();
+            }
+            return Ok(true);
+        }
+        if loaded && self.preview_btn.touch(touch, t) {
+            if self.demo_on() {
+                self.demo = None;
+                self.demo_task = None;
+            } else {
+                self.load_demo();
+            }
+            return Ok(true);
+        }
         Ok(false)
     }
 
@@ -204,6 +523,16 @@ impl Page for ResPackPage {
             if let Some(res) = poll_future(task.as_mut()) {
                 match res {
                     Err(err) => {
+                        // don't keep playing the effects of the pack that failed to reload
+                        self.emitter = None;
+                        self.sfxs = None;
+                        if item.path.is_some() {
+                            // tell everything that is wrong with the pack, not just the first problem
+                            self.load_error = Some(err);
+                            item.load_task = None;
+                            self.validate();
+                            return Ok(());
+                        }
                         show_error(err.context(tl!("load-failed")));
                     }
                     Ok(val) => {
@@ -219,7 +548,43 @@ impl Page for ResPackPage {
                 item.load_task = None;
             }
         }
+        if let Some(task) = &mut self.validate_task {
+            if let Some(res) = poll_future(task.as_mut()) {
+                self.validate_task = None;
+                let error = self.load_error.take();
+                match res {
+                    Err(err) => show_error(err.context(tl!("validate-failed"))),
+                    Ok(problems) if !problems.is_empty() => show_problems(&problems),
+                    Ok(_) => {
+                        if let Some(err) = error {
+                            show_error(err.context(tl!("load-failed")));
+                        } else if self.watcher.is_some() {
+                            show_message(tl!("no-problems")).ok();
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(task) = &mut self.demo_task {
+            if let Some(res) = poll_future(task.as_mut()) {
+                self.demo_task = None;
+                match res {
+                    Err(err) => show_error(err.context(tl!("preview-failed"))),
+                    Ok(demo) => self.demo = Some(demo),
+                }
+            }
+        }
+        let item = &mut self.items[self.index];
+        if item.load_task.is_none() && self.watcher.as_mut().is_some_and(|it| it.changed(t)) {
+            item.loaded = None;
+            item.load();
+            self.validate();
+            if self.demo_on() {
+                self.load_demo();
+            }
+        }
         if self.should_delete.fetch_and(false, Ordering::Relaxed) {
+            self.watcher = None;
             std::fs::remove_dir_all(self.items[self.index].path.as_ref().unwrap())?;
             self.items.remove(self.index);
             get_data_mut().respacks.remove(self.index - 1);
@@ -227,6 +592,9 @@ impl Page for ResPackPage {
             get_data_mut().respack_id = self.index;
             save_data()?;
             self.items[self.index].load();
+            if self.demo_on() {
+                self.load_demo();
+            }
             show_message(tl!("deleted")).ok();
         }
         if let Some(item) = MainScene::take_imported_respack() {
@@ -270,124 +638,76 @@ impl Page for ResPackPage {
             ui.fill_path(&cr.rounded(0.005), semi_black(0.4));
             let item = &self.items[self.index];
             if let Some(pack) = &item.loaded {
-                let width = 0.16;
-                let mut r = Rect::new(cr.x + 0.07, cr.y + 0.1, width, 0.);
-                let mut draw = |mut r: Rect, tex: Texture2D, mh: Texture2D| {
-                    let y = r.y;
-                    r.h = tex.height() / tex.width() * r.w;
-                    r.y = y - r.h / 2.;
-                    ui.fill_rect(r, (tex, r, ScaleType::Fit));
-                    r.x += r.w * 1.8;
-                    r.w *= mh.width() / tex.width();
-                    r.x -= r.w / 2.;
-                    r.h = mh.height() / mh.width() * r.w;
-                    r.y = y - r.h / 2.;
-                    ui.fill_rect(r, (mh, r, ScaleType::Fit));
-                };
-                let sp = (cr.h - 0.4) / 2.;
-                draw(r, *pack.note_style.click, *pack.note_style_mh.click);
-                r.y += sp;
-                draw(r, *pack.note_style.drag, *pack.note_style_mh.drag);
-                r.y += sp;
-                draw(r, *pack.note_style.flick, *pack.note_style_mh.flick);
-                let mut r = Rect::new(0.1, cr.y + 0.1, width, cr.h - 0.38);
-                let draw = |mut r: Rect, style: &NoteStyle, width: f32| {
-                    let conv = |r: Rect, tex: &SafeTexture| Rect::new(r.x * tex.width(), r.y * tex.height(), r.w * tex.width(), r.h * tex.height());
-                    let tr = conv(style.hold_tail_rect(), &style.hold);
-                    let factor = if pack.info.hold_compact { 0.5 } else { 1. };
-                    let h = tr.h / tr.w * width;
-                    let r2 = Rect::new(r.x, r.y - h * factor, width, h);
-                    let r2 = ui.rect_to_global(r2);
-                    draw_texture_ex(
-                        *style.hold,
-                        r2.x,
-                        r2.y,
-                        semi_white(ui.alpha),
-                        DrawTextureParams {
-                            source: Some(tr),
-                            dest_size: Some(vec2(r2.w, r2.h)),
-                            ..Default::default()
-                        },
-                    );
-                    let tr = conv(style.hold_head_rect(), &style.hold);
-                    let h = tr.h / tr.w * width;
-                    let r2 = Rect::new(r.x, r.bottom() - h * (1. - factor), width, h);
-                    let r2 = ui.rect_to_global(r2);
-                    draw_texture_ex(
-                        *style.hold,
-                        r2.x,
-                        r2.y,
-                        semi_white(ui.alpha),
-                        DrawTextureParams {
-                            source: Some(tr),
-                            dest_size: Some(vec2(r2.w, r2.h)),
-                            ..Default::default()
-                        },
-                    );
-                    r.w = width;
-                    let r2 = ui.rect_to_global(r);
-                    draw_texture_ex(
-                        if pack.info.hold_repeat {
-                            **style.hold_body.as_ref().unwrap()
-                        } else {
-                            *style.hold
-                        },
-                        r2.x,
-                        r2.y,
-                        semi_white(ui.alpha),
-                        DrawTextureParams {
-                            source: Some({
-                                if pack.info.hold_repeat {
-                                    let hold_body = style.hold_body.as_ref().unwrap();
-                                    let w = hold_body.width();
-                                    Rect::new(0., 0., w, r2.h / width / 2. * w)
-                                } else {
-                                    conv(style.hold_body_rect(), &style.hold)
-                                }
-                            }),
-                            dest_size: Some(vec2(r2.w, r2.h)),
-                            ..Default::default()
-                        },
-                    )
-                };
-                draw(r, &pack.note_style, width);
-                r.x += width + 0.04;
-                draw(r, &pack.note_style_mh, width * pack.note_style_mh.hold.width() / pack.note_style.hold.width());
-                let x = cr.x + 0.05;
-                if let Some(emitter) = &mut self.emitter {
-                    emitter.draw(get_frame_time());
-                };
-
-                let inter = 1.5;
-                let rnd = t.div_euclid(inter);
-                let irnd = rnd as u32;
-                let tex = match irnd % 3 {
-                    0 => *pack.note_style.click,
-                    1 => *pack.note_style.drag,
-                    2 => *pack.note_style.flick,
-                    _ => unreachable!(),
-                };
-                let st = r.y + 0.06;
-                let cx = r.x + 0.43;
-                let line = 0.12;
-                ui.fill_rect(Rect::new(cx - 0.2, line - 0.004, 0.4, 0.008), WHITE);
-                let p = (t - inter * rnd) / 0.9;
-                if p <= 1. {
-                    let y = st + (line - st) * p;
-                    let h = tex.height() / tex.width() * width;
-                    let r = Rect::new(cx - width / 2., y - h / 2., width, h);
-                    ui.fill_rect(r, (tex, r, ScaleType::Fit));
-                } else if irnd != self.last_round {
-                    if let Some(emitter) = &mut self.emitter {
-                        emitter.emit_at(vec2(cx, line), 0., pack.info.fx_perfect());
+                if self.demo_on() {
+                    let r = Rect::new(cr.x + 0.05, cr.y + 0.05, cr.w - 0.1, cr.h - 0.3);
+                    if let Some(demo) = &mut self.demo {
+                        demo.render(ui, r, t);
+                    } else {
+                        let ct = r.center();
+                        ui.loading(ct.x, ct.y, t, WHITE, ());
                     }
-                    if let Some(sfxs) = &mut self.sfxs {
-                        let _ = sfxs[(irnd % 3) as usize].play(PlaySfxParams {
-                            amplifier: get_data().config.volume_sfx,
-                        });
+                } else {
+                    let width = 0.16;
+                    let mut r = Rect::new(cr.x + 0.07, cr.y + 0.1, width, 0.);
+                    let mut draw = |mut r: Rect, tex: Texture2D, mh: Texture2D| {
+                        let y = r.y;
+                        r.h = tex.height() / tex.width() * r.w;
+                        r.y = y - r.h / 2.;
+                        ui.fill_rect(r, (tex, r, ScaleType::Fit));
+                        r.x += r.w * 1.8;
+                        r.w *= mh.width() / tex.width();
+                        r.x -= r.w / 2.;
+                        r.h = mh.height() / mh.width() * r.w;
+                        r.y = y - r.h / 2.;
+                        ui.fill_rect(r, (mh, r, ScaleType::Fit));
+                    };
+                    let sp = (cr.h - 0.4) / 2.;
+                    draw(r, *pack.note_style.click, *pack.note_style_mh.click);
+                    r.y += sp;
+                    draw(r, *pack.note_style.drag, *pack.note_style_mh.drag);
+                    r.y += sp;
+                    draw(r, *pack.note_style.flick, *pack.note_style_mh.flick);
+                    let mut r = Rect::new(0.1, cr.y + 0.1, width, cr.h - 0.38);
+                    draw_hold(ui, pack, &pack.note_style, r);
+                    r.x += width + 0.04;
+                    r.w = width * pack.note_style_mh.hold.width() / pack.note_style.hold.width();
+                    draw_hold(ui, pack, &pack.note_style_mh, r);
+
+                    let inter = 1.5;
+                    let rnd = t.div_euclid(inter);
+                    let irnd = rnd as u32;
+                    let tex = match irnd % 3 {
+                        0 => *pack.note_style.click,
+                        1 => *pack.note_style.drag,
+                        2 => *pack.note_style.flick,
+                        _ => unreachable!(),
+                    };
+                    let st = r.y + 0.06;
+                    let cx = r.x + 0.43;
+                    let line = 0.12;
+                    ui.fill_rect(Rect::new(cx - 0.2, line - 0.004, 0.4, 0.008), WHITE);
+                    let p = (t - inter * rnd) / 0.9;
+                    if p <= 1. {
+                        let y = st + (line - st) * p;
+                        let h = tex.height() / tex.width() * width;
+                        let r = Rect::new(cx - width / 2., y - h / 2., width, h);
+                        ui.fill_rect(r, (tex, r, ScaleType::Fit));
+                    } else if irnd != self.last_round {
+                        if let Some(emitter) = &mut self.emitter {
+                            emitter.emit_at(vec2(cx, line), 0., pack.info.fx_perfect());
+                        }
+                        if let Some(sfxs) = &mut self.sfxs {
+                            let _ = sfxs[(irnd % 3) as usize].play(PlaySfxParams {
+                                amplifier: get_data().config.volume_sfx,
+                            });
+                        }
+                        self.last_round = irnd;
                     }
-                    self.last_round = irnd;
                 }
+                if let Some(emitter) = &mut self.emitter {
+                    emitter.draw(get_frame_time());
+                };
+                let x = cr.x + 0.05;
                 ui.text(&item.name)
                     .pos(x, cr.bottom() - 0.05)
                     .anchor(0., 1.)
@@ -412,7 +732,19 @@ impl Page for ResPackPage {
                     let r = tr.feather(-0.02);
                     ui.fill_rect(r, (*self.icons.info, r, ScaleType::Fit));
                 });
+                tr.x -= tr.w + 0.02;
+                self.preview_btn.render_shadow(ui, tr, t, |ui, path| {
+                    ui.fill_path(&path, if self.demo_on() { semi_white(0.3) } else { semi_black(0.2) });
+                    let r = tr.feather(-0.02);
+                    ui.fill_rect(r, (*self.icons.play, r, ScaleType::Fit));
+                });
             }
+            tr.x -= tr.w + 0.02;
+            self.dev_btn.render_shadow(ui, tr, t, |ui, path| {
+                ui.fill_path(&path, if self.watcher.is_some() { semi_white(0.3) } else { semi_black(0.2) });
+                let r = tr.feather(-0.02);
+                ui.fill_rect(r, (*self.icons.edit, r, ScaleType::Fit));
+            });
         });
         Ok(())
     }
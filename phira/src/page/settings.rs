@@ -15,8 +15,11 @@ use inputbox::InputBox;
 use macroquad::prelude::*;
 use once_cell::sync::Lazy;
 use prpr::{
+    config::{FxPalette, Quality, SpeedPitch},
     core::BOLD_FONT,
     ext::{open_url, poll_future, semi_white, LocalTask, RectExt, SafeTexture},
+    input::{FlickDirection, InputAction, InputBinding, InputProfile, InputSource},
+    judge::Judge,
     scene::{request_input, return_input, show_error, show_message, take_input},
     task::Task,
     ui::{DRectButton, Scroll, Slider, Ui, PREFER_REDUCED_MOTION, UI_SFX_VOLUME},
@@ -217,6 +220,10 @@ impl Page for SettingsPage {
         } {
             if p {
                 self.save_time = t;
+                // options set one by one may no longer match the tier
+                let config = &mut get_data_mut().config;
+                config.quality = Quality::detect(config);
+                self.list_general.quality_btn.set_selected(GeneralList::quality_index(config.quality));
             }
             self.scroll.y_scroller.halt();
             return Ok(true);
@@ -393,6 +400,8 @@ struct GeneralList {
     server_status_btn: DRectButton,
     mp_btn: DRectButton,
     mp_addr_btn: DRectButton,
+    quality_btn: ChooseButton,
+    auto_quality_btn: DRectButton,
     #[cfg(not(target_env = "ohos"))]
     lowq_btn: DRectButton,
     prefer_reduced_motion_btn: DRectButton,
@@ -428,6 +437,10 @@ impl GeneralList {
             server_status_btn: DRectButton::new(),
             mp_btn: DRectButton::new(),
             mp_addr_btn: DRectButton::new(),
+            quality_btn: ChooseButton::new()
+                .with_options(Quality::ALL.iter().map(|it| Self::quality_name(*it).into_owned()).collect())
+                .with_selected(Self::quality_index(get_data().config.quality)),
+            auto_quality_btn: DRectButton::new(),
             #[cfg(not(target_env = "ohos"))]
             lowq_btn: DRectButton::new(),
             prefer_reduced_motion_btn: DRectButton::new(),
@@ -443,12 +456,25 @@ impl GeneralList {
     }
 
     pub fn top_touch(&mut self, touch: &Touch, t: f32) -> bool {
-        if self.lang_btn.top_touch(touch, t) {
+        if self.lang_btn.top_touch(touch, t) || self.quality_btn.top_touch(touch, t) {
             return true;
         }
         false
     }
 
+    fn quality_name(quality: Quality) -> Cow<'static, str> {
+        match quality {
+            Quality::Low => tl!("quality-low"),
+            Quality::Medium => tl!("quality-medium"),
+            Quality::High => tl!("quality-high"),
+            Quality::Custom => tl!("quality-custom"),
+        }
+    }
+
+    fn quality_index(quality: Quality) -> usize {
+        Quality::ALL.iter().position(|it| *it == quality).unwrap_or_default()
+    }
+
     fn dir_size(path: impl Into<PathBuf>) -> io::Result<u64> {
         fn inner(mut dir: fs::ReadDir) -> io::Result<u64> {
             dir.try_fold(0, |acc, file| {
@@ -510,6 +536,13 @@ impl GeneralList {
             request_input("mp_addr", InputBox::new().default_text(&config.mp_address));
             return Ok(Some(true));
         }
+        if self.quality_btn.touch(touch, t) {
+            return Ok(Some(false));
+        }
+        if self.auto_quality_btn.touch(touch, t) {
+            config.auto_quality ^= true;
+            return Ok(Some(true));
+        }
         #[cfg(not(target_env = "ohos"))]
         if self.lowq_btn.touch(touch, t) {
             config.sample_count = if config.sample_count == 1 { 2 } else { 1 };
@@ -537,12 +570,17 @@ impl GeneralList {
 
     pub fn update(&mut self, t: f32) -> Result<bool> {
         self.lang_btn.update(t);
+        self.quality_btn.update(t);
         let data = get_data_mut();
         if self.lang_btn.changed() {
             data.language = Some(LANG_IDENTS[self.lang_btn.selected()].to_string());
             sync_data();
             return Ok(true);
         }
+        if self.quality_btn.changed() {
+            Quality::ALL[self.quality_btn.selected()].apply(&mut data.config);
+            return Ok(true);
+        }
         if let Some((id, text)) = take_input() {
             if id == "mp_addr" {
                 if let Err(err) = text.parse::<http::uri::Authority>() {
@@ -621,6 +659,14 @@ impl GeneralList {
             render_title(ui, tl!("item-prefer-reduced-motion"), Some(tl!("item-prefer-reduced-motion-sub")));
             render_switch(ui, rr, t, &mut self.prefer_reduced_motion_btn, data.prefer_reduced_motion);
         }
+        item! {
+            render_title(ui, tl!("item-quality"), Some(tl!("item-quality-sub")));
+            self.quality_btn.render(ui, rr, t);
+        }
+        item! {
+            render_title(ui, tl!("item-auto-quality"), Some(tl!("item-auto-quality-sub")));
+            render_switch(ui, rr, t, &mut self.auto_quality_btn, config.auto_quality);
+        }
         #[cfg(not(target_env = "ohos"))]
         item! {
             render_title(ui, tl!("item-lowq"), Some(tl!("item-lowq-sub")));
@@ -650,6 +696,7 @@ impl GeneralList {
             self.anys_gateway_btn.render_text(ui, rr, t, &data.anys_gateway, 0.4, false);
         }
         self.lang_btn.render_top(ui, t, 1.);
+        self.quality_btn.render_top(ui, t, 1.);
         (w, h)
     }
 }
@@ -660,6 +707,9 @@ struct AudioList {
     sfx_slider: Slider,
     bgm_slider: Slider,
     cali_btn: DRectButton,
+    visual_offset_slider: Slider,
+    hitsound_scheduling_btn: DRectButton,
+    hitsound_latency_slider: Slider,
     #[cfg(not(target_os = "android"))]
     preferred_sample_rate_btn: DRectButton,
     #[cfg(target_env = "ohos")]
@@ -676,6 +726,9 @@ impl AudioList {
             sfx_slider: Slider::new(0.0..2.0, 0.05),
             bgm_slider: Slider::new(0.0..2.0, 0.05),
             cali_btn: DRectButton::new(),
+            visual_offset_slider: Slider::new(-0.2..0.2, 0.005),
+            hitsound_scheduling_btn: DRectButton::new(),
+            hitsound_latency_slider: Slider::new(0.0..0.5, 0.005),
             #[cfg(not(target_os = "android"))]
             preferred_sample_rate_btn: DRectButton::new(),
             #[cfg(target_env = "ohos")]
@@ -715,6 +768,18 @@ impl AudioList {
             self.cali_task = Some(Box::pin(OffsetPage::new()));
             return Ok(Some(false));
         }
+        if let wt @ Some(_) = self.visual_offset_slider.touch(touch, t, &mut config.visual_offset) {
+            return Ok(wt);
+        }
+        if self.hitsound_scheduling_btn.touch(touch, t) {
+            config.hitsound_scheduling ^= true;
+            return Ok(Some(true));
+        }
+        if config.hitsound_scheduling {
+            if let wt @ Some(_) = self.hitsound_latency_slider.touch(touch, t, &mut config.hitsound_latency) {
+                return Ok(wt);
+            }
+        }
         #[cfg(not(target_os = "android"))]
         if self.preferred_sample_rate_btn.touch(touch, t) {
             let options = [None, Some(44100), Some(48000), Some(88200), Some(96000), Some(192000)];
@@ -783,6 +848,22 @@ impl AudioList {
             render_title(ui, tl!("item-cali"), None);
             self.cali_btn.render_text(ui, rr, t, format!("{:.0}ms", config.offset * 1000.), 0.5, true);
         }
+        item! {
+            render_title(ui, tl!("item-visual-offset"), Some(tl!("item-visual-offset-sub")));
+            self.visual_offset_slider
+                .render(ui, rr, t, config.visual_offset, format!("{:.0}ms", config.visual_offset * 1000.));
+        }
+        item! {
+            render_title(ui, tl!("item-hitsound-scheduling"), Some(tl!("item-hitsound-scheduling-sub")));
+            render_switch(ui, rr, t, &mut self.hitsound_scheduling_btn, config.hitsound_scheduling);
+        }
+        if config.hitsound_scheduling {
+            item! {
+                render_title(ui, tl!("item-hitsound-latency"), Some(tl!("item-hitsound-latency-sub")));
+                self.hitsound_latency_slider
+                    .render(ui, rr, t, config.hitsound_latency, format!("{:.0}ms", config.hitsound_latency * 1000.));
+            }
+        }
         #[cfg(not(target_os = "android"))]
         item! {
             render_title(ui, tl!("item-preferred-sample-rate"), None);
@@ -815,12 +896,22 @@ struct ChartList {
     dhint_btn: DRectButton,
     opt_btn: DRectButton,
     use_keyboard_btn: DRectButton,
+    input_profile_btn: ChooseButton,
+    binding_btns: Vec<DRectButton>,
+    reset_bindings_btn: DRectButton,
+    /// Index of the binding waiting for a key or button press
+    rebinding: Option<usize>,
     speed_slider: Slider,
+    speed_pitch_btn: ChooseButton,
     size_slider: Slider,
+    outline_slider: Slider,
+    note_shapes_btn: DRectButton,
+    fx_palette_btn: ChooseButton,
 }
 
 impl ChartList {
     pub fn new() -> Self {
+        let config = &get_data().config;
         Self {
             show_acc_btn: DRectButton::new(),
             ap_fc_indicator_btn: DRectButton::new(),
@@ -829,18 +920,86 @@ impl ChartList {
             dhint_btn: DRectButton::new(),
             opt_btn: DRectButton::new(),
             use_keyboard_btn: DRectButton::new(),
+            input_profile_btn: ChooseButton::new()
+                .with_options(config.input_profiles.iter().map(|it| it.name.clone()).collect())
+                .with_selected(config.input_profile),
+            binding_btns: Vec::new(),
+            reset_bindings_btn: DRectButton::new(),
+            rebinding: None,
             speed_slider: Slider::new(0.5..2., 0.05),
+            speed_pitch_btn: ChooseButton::new()
+                .with_options(SpeedPitch::ALL.iter().map(|it| Self::speed_pitch_name(*it).into_owned()).collect())
+                .with_selected(SpeedPitch::ALL.iter().position(|it| *it == config.speed_pitch).unwrap_or_default()),
             size_slider: Slider::new(0.8..1.2, 0.005),
+            outline_slider: Slider::new(0.0..1., 0.05),
+            note_shapes_btn: DRectButton::new(),
+            fx_palette_btn: ChooseButton::new()
+                .with_options(FxPalette::ALL.iter().map(|it| Self::palette_name(*it).into_owned()).collect())
+                .with_selected(FxPalette::ALL.iter().position(|it| *it == config.fx_palette).unwrap_or_default()),
         }
     }
 
-    pub fn top_touch(&mut self, _touch: &Touch, _t: f32) -> bool {
-        false
+    pub fn top_touch(&mut self, touch: &Touch, t: f32) -> bool {
+        (get_data().config.use_keyboard && self.input_profile_btn.top_touch(touch, t))
+            || self.speed_pitch_btn.top_touch(touch, t)
+            || self.fx_palette_btn.top_touch(touch, t)
+    }
+
+    fn speed_pitch_name(pitch: SpeedPitch) -> Cow<'static, str> {
+        match pitch {
+            SpeedPitch::Keep => tl!("speed-pitch-keep"),
+            SpeedPitch::Follow => tl!("speed-pitch-follow"),
+        }
+    }
+
+    fn palette_name(palette: FxPalette) -> Cow<'static, str> {
+        match palette {
+            FxPalette::Pack => tl!("palette-pack"),
+            FxPalette::Colorblind => tl!("palette-colorblind"),
+            FxPalette::HighContrast => tl!("palette-high-contrast"),
+        }
+    }
+
+    fn binding_name(binding: &InputBinding) -> String {
+        let action = match binding.action {
+            InputAction::Tap => tl!("binding-tap"),
+            InputAction::Hold => tl!("binding-hold"),
+            InputAction::Flick { direction } => tl!("binding-flick", "direction" => match direction {
+                FlickDirection::Up => "up",
+                FlickDirection::Down => "down",
+                FlickDirection::Left => "left",
+                FlickDirection::Right => "right",
+            })
+            .into(),
+        };
+        match binding.zone {
+            Some(zone) => tl!("binding-zone", "action" => action, "zone" => zone + 1),
+            None => action.into_owned(),
+        }
     }
 
     pub fn touch(&mut self, touch: &Touch, t: f32) -> Result<Option<bool>> {
         let data = get_data_mut();
         let config = &mut data.config;
+        if config.use_keyboard {
+            if self.input_profile_btn.touch(touch, t) {
+                return Ok(Some(false));
+            }
+            for (index, btn) in self.binding_btns.iter_mut().enumerate() {
+                if btn.touch(touch, t) {
+                    self.rebinding = Some(index);
+                    return Ok(Some(false));
+                }
+            }
+            let profile = &mut config.input_profiles[config.input_profile];
+            if !profile.is_any_key() && self.reset_bindings_btn.touch(touch, t) {
+                if let Some(preset) = InputProfile::presets().into_iter().find(|it| it.name == profile.name) {
+                    *profile = preset;
+                }
+                self.rebinding = None;
+                return Ok(Some(true));
+            }
+        }
         if self.show_acc_btn.touch(touch, t) {
             config.show_acc ^= true;
             return Ok(Some(true));
@@ -867,18 +1026,66 @@ impl ChartList {
         }
         if self.use_keyboard_btn.touch(touch, t) {
             config.use_keyboard ^= true;
+            self.rebinding = None;
             return Ok(Some(true));
         }
         if let wt @ Some(_) = self.speed_slider.touch(touch, t, &mut config.speed) {
             return Ok(wt);
         }
+        if self.speed_pitch_btn.touch(touch, t) {
+            return Ok(Some(false));
+        }
         if let wt @ Some(_) = self.size_slider.touch(touch, t, &mut config.note_scale) {
             return Ok(wt);
         }
+        if let wt @ Some(_) = self.outline_slider.touch(touch, t, &mut config.note_outline) {
+            return Ok(wt);
+        }
+        if self.note_shapes_btn.touch(touch, t) {
+            config.note_shapes ^= true;
+            return Ok(Some(true));
+        }
+        if self.fx_palette_btn.touch(touch, t) {
+            return Ok(Some(false));
+        }
         Ok(None)
     }
 
-    pub fn update(&mut self, _t: f32) -> Result<bool> {
+    pub fn update(&mut self, t: f32) -> Result<bool> {
+        self.input_profile_btn.update(t);
+        self.speed_pitch_btn.update(t);
+        self.fx_palette_btn.update(t);
+        let config = &mut get_data_mut().config;
+        if self.speed_pitch_btn.changed() {
+            config.speed_pitch = SpeedPitch::ALL[self.speed_pitch_btn.selected()];
+            return Ok(true);
+        }
+        if self.fx_palette_btn.changed() {
+            config.fx_palette = FxPalette::ALL[self.fx_palette_btn.selected()];
+            return Ok(true);
+        }
+        if self.input_profile_btn.changed() {
+            config.input_profile = self.input_profile_btn.selected();
+            self.rebinding = None;
+            return Ok(true);
+        }
+        if let Some(index) = self.rebinding {
+            let Some(input) = Judge::get_inputs().into_iter().find_map(|(input, down)| down.then_some(input)) else {
+                return Ok(false);
+            };
+            self.rebinding = None;
+            if input == InputSource::Key(KeyCode::Escape) {
+                return Ok(false);
+            }
+            let bindings = &mut config.input_profiles[config.input_profile].bindings;
+            // an input can only have one binding, so the one previously using it takes the old input
+            let old = bindings[index].input;
+            if let Some(other) = bindings.iter_mut().find(|it| it.input == input) {
+                other.input = old;
+            }
+            bindings[index].input = input;
+            return Ok(true);
+        }
         Ok(false)
     }
 
@@ -924,14 +1131,59 @@ impl ChartList {
             render_title(ui, tl!("item-use-keyboard"), Some(tl!("item-use-keyboard-sub")));
             render_switch(ui, rr, t, &mut self.use_keyboard_btn, config.use_keyboard);
         }
+        if config.use_keyboard {
+            item! {
+                render_title(ui, tl!("item-input-profile"), Some(tl!("item-input-profile-sub")));
+                self.input_profile_btn.render(ui, rr, t);
+            }
+            let profile = &config.input_profiles[config.input_profile];
+            self.binding_btns.resize_with(profile.bindings.len(), DRectButton::new);
+            for (index, (binding, btn)) in profile.bindings.iter().zip(&mut self.binding_btns).enumerate() {
+                item! {
+                    render_title(ui, Self::binding_name(binding), None);
+                    if self.rebinding == Some(index) {
+                        btn.render_text(ui, rr, t, tl!("binding-press"), 0.5, true);
+                    } else {
+                        btn.render_text(ui, rr, t, binding.input.name(), 0.5, false);
+                    }
+                }
+            }
+            if !profile.is_any_key() {
+                item! {
+                    render_title(ui, tl!("item-reset-bindings"), None);
+                    self.reset_bindings_btn.render_text(ui, rr, t, tl!("reset-bindings"), 0.5, false);
+                }
+            }
+        }
         item! {
             render_title(ui, tl!("item-speed"), None);
             self.speed_slider.render(ui, rr, t, config.speed, format!("{:.2}", config.speed));
         }
+        item! {
+            render_title(ui, tl!("item-speed-pitch"), Some(tl!("item-speed-pitch-sub")));
+            self.speed_pitch_btn.render(ui, rr, t);
+        }
         item! {
             render_title(ui, tl!("item-note-size"), None);
             self.size_slider.render(ui, rr, t, config.note_scale, format!("{:.3}", config.note_scale));
         }
+        item! {
+            render_title(ui, tl!("item-note-outline"), Some(tl!("item-note-outline-sub")));
+            self.outline_slider.render(ui, rr, t, config.note_outline, format!("{:.2}", config.note_outline));
+        }
+        item! {
+            render_title(ui, tl!("item-note-shapes"), Some(tl!("item-note-shapes-sub")));
+            render_switch(ui, rr, t, &mut self.note_shapes_btn, config.note_shapes);
+        }
+        item! {
+            render_title(ui, tl!("item-fx-palette"), Some(tl!("item-fx-palette-sub")));
+            self.fx_palette_btn.render(ui, rr, t);
+        }
+        if config.use_keyboard {
+            self.input_profile_btn.render_top(ui, t, 1.);
+        }
+        self.speed_pitch_btn.render_top(ui, t, 1.);
+        self.fx_palette_btn.render_top(ui, t, 1.);
         (w, h)
     }
 }
@@ -939,6 +1191,7 @@ impl ChartList {
 struct DebugList {
     chart_debug_btn: DRectButton,
     touch_debug_btn: DRectButton,
+    profiler_btn: DRectButton,
 }
 
 impl DebugList {
@@ -946,6 +1199,7 @@ impl DebugList {
         Self {
             chart_debug_btn: DRectButton::new(),
             touch_debug_btn: DRectButton::new(),
+            profiler_btn: DRectButton::new(),
         }
     }
 
@@ -964,6 +1218,10 @@ impl DebugList {
             config.touch_debug ^= true;
             return Ok(Some(true));
         }
+        if self.profiler_btn.touch(touch, t) {
+            config.profiler ^= true;
+            return Ok(Some(true));
+        }
         Ok(None)
     }
 
@@ -993,6 +1251,10 @@ impl DebugList {
             render_title(ui, tl!("item-touch-debug"), Some(tl!("item-touch-debug-sub")));
             render_switch(ui, rr, t, &mut self.touch_debug_btn, config.touch_debug);
         }
+        item! {
+            render_title(ui, tl!("item-profiler"), Some(tl!("item-profiler-sub")));
+            render_switch(ui, rr, t, &mut self.profiler_btn, config.profiler);
+        }
         (w, h)
     }
 }
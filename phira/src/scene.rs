@@ -1,5 +1,8 @@
 prpr_l10n::tl_file!("import" itl);
 
+mod bulk_import;
+pub use bulk_import::{bulk_import, is_archive_of_archives, ImportChart};
+
 mod chart_order;
 pub use chart_order::ChartOrder;
 
@@ -12,6 +15,12 @@ pub use event::EventScene;
 mod main;
 pub use main::{MainScene, BGM_VOLUME_UPDATED, MP_PANEL};
 
+mod manifest;
+pub use manifest::{export_chart, verify_manifest};
+
+mod pack;
+pub use pack::{export_pack, import_pack, is_pack_archive, pack_members, pack_root};
+
 mod song;
 pub use song::{compress_folder, Downloading, SongScene, RECORD_ID};
 #[cfg(feature = "video")]
@@ -35,10 +44,10 @@ use once_cell::sync::{Lazy, OnceCell};
 use prpr::{
     config::Mods,
     core::{BOLD_FONT, PGR_FONT},
-    ext::{open_url, semi_white, unzip_into, RectExt, SafeTexture},
+    ext::{open_url, semi_white, spawn_task, unzip_into, RectExt, SafeTexture},
     fs::{self, FileSystem},
     info::{ChartFormat, ChartInfo},
-    parse::ParseWarnings,
+    parse::{ParseWarnings, RpeFieldStatus, RpeScope},
     scene::{show_error, show_message, FullLoadingView, GameScene},
     task::Task,
     ui::{Dialog, RectButton, Scroll, Scroller, Ui},
@@ -292,6 +301,36 @@ pub fn parse_warnings_to_string(w: &ParseWarnings) -> Option<String> {
     if w.has_attach_ui {
         warnings.push(format!("- {}", itl!("warning-attach-ui")));
     }
+    for (status, key) in [
+        (RpeFieldStatus::Unsupported, "warning-rpe-unsupported"),
+        (RpeFieldStatus::Unknown, "warning-rpe-unknown"),
+    ] {
+        let fields: Vec<_> = w.rpe_fields.iter().filter(|it| it.status == status).collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let version = w.rpe_version.unwrap_or_default();
+        let mut lines = vec![format!("- {}", itl!(key, "version" => version))];
+        for field in fields.iter().take(8) {
+            let scope = match field.scope {
+                RpeScope::Chart => itl!("rpe-scope-chart").into_owned(),
+                RpeScope::Meta => itl!("rpe-scope-meta").into_owned(),
+                RpeScope::Line(id) => itl!("rpe-scope-line", "id" => id),
+                RpeScope::Events(id) => itl!("rpe-scope-events", "id" => id, "count" => field.count, "first" => field.first),
+                RpeScope::Notes(id) => itl!("rpe-scope-notes", "id" => id, "count" => field.count, "first" => field.first),
+            };
+            lines.push(format!("  · {}: {scope}", field.field));
+        }
+        if fields.len() > 8 {
+            lines.push(format!("  · {}", itl!("rpe-fields-more", "count" => fields.len() - 8)));
+        }
+        warnings.push(lines.join("\n"));
+    }
+    if !w.shader_problems.is_empty() {
+        let mut lines = vec![format!("- {}", itl!("warning-shader"))];
+        lines.extend(w.shader_problems.iter().map(|it| format!("  · {}", it.replace('\n', "\n    "))));
+        warnings.push(lines.join("\n"));
+    }
     if warnings.is_empty() {
         None
     } else {
@@ -299,6 +338,11 @@ pub fn parse_warnings_to_string(w: &ParseWarnings) -> Option<String> {
     }
 }
 
+async fn chart_format(fs: &mut dyn FileSystem, info: &ChartInfo) -> Result<ChartFormat> {
+    let bytes = GameScene::load_chart_bytes(fs, info).await.context("Failed to load chart")?;
+    Ok(GameScene::infer_chart_format(info, &bytes))
+}
+
 async fn lint_chart(fs: &mut dyn FileSystem, info: &ChartInfo) -> Result<ParseWarnings> {
     let bytes = GameScene::load_chart_bytes(fs, info).await.context("Failed to load chart")?;
     let format = GameScene::infer_chart_format(info, &bytes);
@@ -309,20 +353,52 @@ async fn lint_chart(fs: &mut dyn FileSystem, info: &ChartInfo) -> Result<ParseWa
     prpr::parse::lint(&source).await
 }
 
-pub async fn import_chart_to(dir: &Path, local_path: String, file: File) -> Result<(LocalChart, ParseWarnings)> {
-    let dir = prpr::dir::Dir::new(dir)?;
-    unzip_into(BufReader::new(file), &dir, true)?;
+/// Lints the shaders of the chart's effects, if it has any.
+///
+/// Charts load regardless of what's found, so a broken `extra.json` is left for playing to report.
+async fn lint_extra(fs: &mut dyn FileSystem) -> Vec<String> {
+    let Ok(extra) = fs.load_file("extra.json").await else {
+        return Vec::new();
+    };
+    match prpr::parse::lint_effects(&String::from_utf8_lossy(&extra), fs).await {
+        Ok(problems) => problems,
+        Err(err) => {
+            warn!(?err, "failed to lint effects");
+            Vec::new()
+        }
+    }
+}
+
+pub async fn import_chart_to(dir_path: &Path, local_path: String, file: File) -> Result<(LocalChart, ParseWarnings)> {
+    let path = dir_path.to_owned();
+    let verified = spawn_task(move || {
+        unzip_into(BufReader::new(file), &prpr::dir::Dir::new(&path)?, true)?;
+        Ok(verify_manifest(&path))
+    })
+    .await?;
+    verified.with_context(|| itl!("manifest-verify-failed"))?;
+    let dir = prpr::dir::Dir::new(dir_path)?;
     let mut fs = fs_from_path(&local_path)?;
     let mut info = fs::load_info(fs.as_mut()).await.with_context(|| itl!("info-fail"))?;
     fs::fix_info(fs.as_mut(), &mut info).await.with_context(|| itl!("invalid-chart"))?;
-    let warnings = if info.use_rpe_170_speed.is_none() || info.use_attach_ui_fix.is_none() {
+    // RPE charts of an unknown version are linted for it as well
+    let warnings = if info.use_rpe_170_speed.is_none()
+        || info.use_attach_ui_fix.is_none()
+        || (info.rpe_version.is_none() && chart_format(fs.as_mut(), &info).await? == ChartFormat::Rpe)
+    {
         if info.use_attach_ui_fix.is_none() {
             info.use_attach_ui_fix = Some(true);
         }
-        lint_chart(fs.as_mut(), &info).await?
+        let warnings = lint_chart(fs.as_mut(), &info).await?;
+        info.rpe_version = warnings.rpe_version;
+        warnings
     } else {
         ParseWarnings::default()
     };
+    let warnings = ParseWarnings {
+        shader_problems: lint_extra(fs.as_mut()).await,
+        ..warnings
+    };
     dir.create("info.yml")?.write_all(serde_yaml::to_string(&info)?.as_bytes())?;
     Ok((
         LocalChart {
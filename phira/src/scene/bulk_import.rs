@@ -0,0 +1,264 @@
+use super::{fs_from_path, import_chart, import_pack, is_pack_archive, L10N_LOCAL};
+use crate::data::LocalChart;
+use anyhow::{bail, Context, Error, Result};
+use futures_util::{stream, StreamExt};
+use prpr::{
+    ext::spawn_task,
+    fs::{fix_info, load_info, FileSystem, PackFileSystem, ZipFileSystem},
+    info::ChartInfo,
+    parse::ParseWarnings,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+};
+use tempfile::tempfile;
+use tracing::warn;
+use walkdir::WalkDir;
+
+/// How many archives are imported at once.
+const CONCURRENCY: usize = 4;
+
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "pez"];
+
+fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|it| it.to_str())
+        .is_some_and(|ext| ARCHIVE_EXTENSIONS.iter().any(|it| it.eq_ignore_ascii_case(ext)))
+}
+
+/// Hashes the chart, music and illustration of a chart, so that the same chart imported twice
+/// (possibly with a different `info.yml`) can be recognized.
+pub async fn chart_content_hash(fs: &mut dyn FileSystem, info: &ChartInfo) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for path in [&info.chart, &info.music, &info.illustration] {
+        let bytes = fs.load_file(path).await.with_context(|| format!("failed to load {path}"))?;
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Computes the content hash of a chart that's already in the library.
+pub async fn local_content_hash(local_path: &str) -> Result<String> {
+    let mut fs = fs_from_path(local_path)?;
+    let info = load_info(fs.as_mut()).await?;
+    chart_content_hash(fs.as_mut(), &info).await
+}
+
+/// Computes the content hash of the chart in an archive without unpacking it, the same way it's
+/// found once imported. For a pack, only its first chart is hashed.
+async fn archive_content_hash(bytes: Vec<u8>, pack: bool) -> Result<String> {
+    let zip = ZipFileSystem::new(bytes)?;
+    let mut fs: Box<dyn FileSystem> = if pack {
+        Box::new(PackFileSystem {
+            chart: zip.open_dir("0"),
+            shared: zip,
+        })
+    } else {
+        Box::new(zip)
+    };
+    let mut info = load_info(fs.as_mut()).await?;
+    fix_info(fs.as_mut(), &mut info).await?;
+    chart_content_hash(fs.as_mut(), &info).await
+}
+
+/// A chart archive found while scanning the import source.
+struct BulkImportSource {
+    name: String,
+    file: File,
+}
+
+/// Finds every chart archive in `path`, which is either a folder (scanned recursively) or an
+/// archive of chart archives.
+///
+/// Nested archives are extracted into temporary files so that they can be imported concurrently.
+fn scan_sources(path: &Path) -> Result<Vec<BulkImportSource>> {
+    let mut sources = Vec::new();
+    if path.is_dir() {
+        for entry in WalkDir::new(path).follow_links(true) {
+            let entry = entry?;
+            if !entry.file_type().is_file() || !is_archive(entry.path()) {
+                continue;
+            }
+            let name = entry.path().strip_prefix(path).unwrap_or(entry.path()).display().to_string();
+            match File::open(entry.path()) {
+                Ok(file) => sources.push(BulkImportSource { name, file }),
+                Err(err) => warn!(?err, "failed to open {name}, skipping"),
+            }
+        }
+    } else {
+        let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path).context("cannot open file")?))?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            let Some(name) = entry.enclosed_name() else {
+                continue;
+            };
+            if !entry.is_file() || !is_archive(&name) {
+                continue;
+            }
+            let mut file = tempfile()?;
+            std::io::copy(&mut entry, &mut file)?;
+            file.seek(SeekFrom::Start(0))?;
+            sources.push(BulkImportSource {
+                name: name.display().to_string(),
+                file,
+            });
+        }
+    }
+    sources.sort_by(|x, y| x.name.cmp(&y.name));
+    Ok(sources)
+}
+
+/// Checks whether the archive at `path` is an archive of chart archives rather than a single chart.
+pub fn is_archive_of_archives(path: &Path) -> Result<bool> {
+    let archive = zip::ZipArchive::new(BufReader::new(File::open(path)?))?;
+    let mut nested = false;
+    for name in archive.file_names() {
+        let name = Path::new(name);
+        if is_archive(name) {
+            nested = true;
+        } else if name.file_name().and_then(|it| it.to_str()).is_some_and(|it| {
+            let it = it.to_ascii_lowercase();
+            it.starts_with("info.") || it.ends_with(".json") || it.ends_with(".pec")
+        }) {
+            return Ok(false);
+        }
+    }
+    Ok(nested)
+}
+
+/// Progress of a batch import, sent as each chart is handled.
+pub enum ImportChart {
+    Imported(Box<LocalChart>, ParseWarnings),
+    /// A downloaded chart that's already in the library.
+    Skipped(String),
+    /// An archive whose chart is already in the library, with the name of the existing chart.
+    Duplicate(String, String),
+    Failed(String, Error),
+}
+
+/// Imports every chart archive found in `path`, skipping charts whose content is already present.
+///
+/// `existing` lists the charts already in the library as `(local path, name, known hash)`. Archives
+/// are hashed before they're unpacked, so that duplicates are never extracted. Returns the content
+/// hashes of the imported charts and of existing charts whose hash was missing, by local path.
+///
+/// Up to [`CONCURRENCY`] archives are imported at once. `total` is set to the number of archives
+/// found, and `done` counts the archives handled so far, whatever their outcome.
+pub async fn bulk_import(
+    path: String,
+    existing: Vec<(String, String, Option<String>)>,
+    total: Arc<AtomicUsize>,
+    done: Arc<AtomicUsize>,
+    tx: mpsc::Sender<ImportChart>,
+) -> Result<Vec<(String, String)>> {
+    let sources = spawn_task(move || scan_sources(Path::new(&path)))
+        .await
+        .with_context(|| itl!("bulk-import-scan-failed"))?;
+    if sources.is_empty() {
+        bail!(itl!("bulk-import-empty"));
+    }
+    total.store(sources.len(), Ordering::Relaxed);
+
+    let mut hashes = Vec::new();
+    let mut known = HashMap::new();
+    for (local_path, name, hash) in existing {
+        let hash = match hash {
+            Some(hash) => hash,
+            None => match local_content_hash(&local_path).await {
+                Ok(hash) => {
+                    hashes.push((local_path, hash.clone()));
+                    hash
+                }
+                Err(err) => {
+                    warn!(?err, "failed to hash existing chart {local_path}");
+                    continue;
+                }
+            },
+        };
+        known.insert(hash, name);
+    }
+
+    let known = Mutex::new(known);
+    let tx = &tx;
+    let results: Vec<_> = stream::iter(sources)
+        .map(|source| {
+            let known = &known;
+            let done = &done;
+            async move {
+                let msg = match import_one(&source.name, source.file, known).await {
+                    Ok(Outcome::Imported(charts, hash)) => {
+                        let local_paths: Vec<_> = charts.iter().map(|(chart, _)| chart.local_path.clone()).collect();
+                        for (chart, warnings) in charts {
+                            let _ = tx.send(ImportChart::Imported(Box::new(chart), warnings));
+                        }
+                        done.fetch_add(1, Ordering::Relaxed);
+                        return local_paths.into_iter().map(|it| (it, hash.clone())).collect();
+                    }
+                    Ok(Outcome::Duplicate(existing)) => ImportChart::Duplicate(source.name, existing),
+                    Err(err) => ImportChart::Failed(source.name, err),
+                };
+                let _ = tx.send(msg);
+                done.fetch_add(1, Ordering::Relaxed);
+                Vec::new()
+            }
+        })
+        .buffer_unordered(CONCURRENCY)
+        .collect()
+        .await;
+    hashes.extend(results.into_iter().flatten());
+    Ok(hashes)
+}
+
+enum Outcome {
+    /// A single chart, or every chart of a pack, with the content hash of the first one.
+    Imported(Vec<(LocalChart, ParseWarnings)>, String),
+    Duplicate(String),
+}
+
+/// Imports a single archive unless its content is already known.
+///
+/// The hash is claimed in `known` before the archive is unpacked, so that a copy of it being
+/// imported at the same time is reported as a duplicate of this one rather than imported twice.
+async fn import_one(name: &str, mut file: File, known: &Mutex<HashMap<String, String>>) -> Result<Outcome> {
+    let (file, bytes, pack) = spawn_task(move || {
+        let pack = is_pack_archive(&mut file)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok((file, bytes, pack))
+    })
+    .await?;
+    let hash = archive_content_hash(bytes, pack).await?;
+    {
+        let mut known = known.lock().unwrap();
+        if let Some(existing) = known.get(&hash) {
+            return Ok(Outcome::Duplicate(existing.clone()));
+        }
+        known.insert(hash.clone(), name.to_owned());
+    }
+    let charts = if pack {
+        import_pack(file).await
+    } else {
+        import_chart(file).await.map(|it| vec![it])
+    };
+    let mut known = known.lock().unwrap();
+    match charts {
+        Ok(charts) => {
+            known.insert(hash.clone(), charts[0].0.info.name.clone());
+            Ok(Outcome::Imported(charts, hash))
+        }
+        Err(err) => {
+            known.remove(&hash);
+            Err(err)
+        }
+    }
+}
@@ -2,6 +2,7 @@ prpr_l10n::tl_file!("chapter");
 
 use crate::{
     anim::Anim,
+    chart_query::{with_chart_index, ChartQuery},
     data::BriefChartInfo,
     dir,
     icons::Icons,
@@ -49,6 +50,14 @@ impl Difficulty {
             Self::Extreme => 0xdc2626,
         })
     }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            Self::Easy => "ez",
+            Self::Hard => "hd",
+            Self::Extreme => "ex",
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -74,6 +83,35 @@ struct ChartInstance {
     btn: DRectButton,
 }
 
+impl ChartInstance {
+    fn local_path(&self, diff: Difficulty) -> String {
+        format!(":{}:{}", self.id, diff.suffix())
+    }
+
+    fn brief_info(&self, diff: Difficulty) -> BriefChartInfo {
+        let info = &self.info;
+        let level = &info.levels[diff as usize];
+        BriefChartInfo {
+            id: None,
+            uploader: None,
+            name: info.name.clone(),
+            level: level.level.clone(),
+            difficulty: level.difficulty,
+            intro: info.intro.clone(),
+            charter: level.charter.clone(),
+            composer: info.composer.clone(),
+            illustrator: info.illustrator.clone(),
+            created: None,
+            updated: None,
+            chart_updated: None,
+            has_unlock: false,
+            tags: Vec::new(),
+            format: None,
+            rating: None,
+        }
+    }
+}
+
 pub struct ChapterScene {
     id: String,
 
@@ -95,6 +133,7 @@ pub struct ChapterScene {
 
     scroll: Scroll,
     charts: Vec<ChartInstance>,
+    query: ChartQuery,
 }
 
 impl ChapterScene {
@@ -102,7 +141,7 @@ impl ChapterScene {
     const HEIGHT: f32 = 0.3;
     const PAD: f32 = 0.05;
 
-    pub async fn new(id: String, icons: Arc<Icons>, rank_icons: [SafeTexture; 8], cover: SafeTexture) -> Result<Self> {
+    pub async fn new(id: String, icons: Arc<Icons>, rank_icons: [SafeTexture; 8], cover: SafeTexture, query: ChartQuery) -> Result<Self> {
         let songs = match id.as_str() {
             "c1" => vec!["snow", "jumping23"],
             _ => vec![],
@@ -138,6 +177,17 @@ impl ChapterScene {
 
             scroll: Scroll::new().tap_mut(|it| it.y_scroller.step = Self::HEIGHT + Self::PAD),
             charts,
+            query,
+        })
+    }
+
+    /// Whether each chart matches the library search at the current difficulty.
+    fn shown(&self) -> Vec<bool> {
+        with_chart_index(|index| {
+            self.charts
+                .iter()
+                .map(|it| index.matches(&self.query, Some(&it.local_path(self.diff)), &it.brief_info(self.diff)))
+                .collect()
         })
     }
 }
@@ -171,36 +221,13 @@ impl Scene for ChapterScene {
         if self.scroll.touch(touch, t) {
             return Ok(true);
         }
-        for chart in &mut self.charts {
-            if chart.btn.touch(touch, t) {
+        let shown = self.shown();
+        for (chart, shown) in self.charts.iter_mut().zip(shown) {
+            if shown && chart.btn.touch(touch, t) {
                 button_hit();
-                let info = &chart.info;
-                let level = &info.levels[self.diff as usize];
-                let local_path = format!(
-                    ":{}:{}",
-                    chart.id,
-                    match self.diff {
-                        Difficulty::Easy => "ez",
-                        Difficulty::Hard => "hd",
-                        Difficulty::Extreme => "ex",
-                    }
-                );
+                let local_path = chart.local_path(self.diff);
                 let item = ChartItem {
-                    info: BriefChartInfo {
-                        id: None,
-                        uploader: None,
-                        name: info.name.clone(),
-                        level: level.level.clone(),
-                        difficulty: level.difficulty,
-                        intro: info.intro.clone(),
-                        charter: level.charter.clone(),
-                        composer: info.composer.clone(),
-                        illustrator: info.illustrator.clone(),
-                        created: None,
-                        updated: None,
-                        chart_updated: None,
-                        has_unlock: false,
-                    },
+                    info: chart.brief_info(self.diff),
                     illu: Illustration::from_done(chart.illu.clone()),
                     local_path: Some(local_path.clone()),
                     chart_type: ChartType::Integrated,
@@ -258,6 +285,7 @@ impl Scene for ChapterScene {
                     force_aspect_ratio: false,
                     use_rpe_170_speed: Some(false),
                     use_attach_ui_fix: Some(true),
+                    rpe_version: None,
 
                     created: None,
                     updated: None,
@@ -320,6 +348,8 @@ impl Scene for ChapterScene {
                 .draw_using(&BOLD_FONT);
         });
 
+        let shown = self.shown();
+        let count = shown.iter().filter(|it| **it).count();
         let r = Rect::new(0.2, -ui.top, 0.6, ui.top * 2.);
         self.scroll.size((r.w, r.h));
         ui.scope(|ui| {
@@ -330,7 +360,10 @@ impl Scene for ChapterScene {
                 ui.dy(ui.top);
                 let mut y = 0.;
                 let step = Self::HEIGHT + Self::PAD;
-                for chart in &mut self.charts {
+                for (chart, shown) in self.charts.iter_mut().zip(shown) {
+                    if !shown {
+                        continue;
+                    }
                     let r = Rect::new(-Self::WIDTH / 2., y - Self::HEIGHT / 2., Self::WIDTH, Self::HEIGHT);
                     chart.btn.render_shadow(ui, r, t, |ui, path| {
                         ui.fill_path(&path, (*chart.illu, r));
@@ -357,7 +390,7 @@ impl Scene for ChapterScene {
                     y += step;
                 }
 
-                (Self::WIDTH, step * (self.charts.len() - 1) as f32 + ui.top * 2.)
+                (Self::WIDTH, step * count.saturating_sub(1) as f32 + ui.top * 2.)
             });
         });
 
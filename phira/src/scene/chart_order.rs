@@ -36,7 +36,10 @@ impl ChartOrder {
                         .unwrap_or(std::cmp::Ordering::Equal)
                 });
             }
-            Self::Rating => {}
+            Self::Rating => {
+                // unrated charts come first, and thus last once reversed to put the best rated first
+                charts.sort_by(|x, y| f(x).info.rating.partial_cmp(&f(y).info.rating).unwrap_or(std::cmp::Ordering::Equal));
+            }
         }
     }
 }
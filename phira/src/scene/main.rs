@@ -1,4 +1,4 @@
-use super::{import_chart, L10N_LOCAL};
+use super::{bulk_import, import_chart, import_pack, is_archive_of_archives, is_pack_archive, ImportChart, L10N_LOCAL};
 use crate::{
     charts_view::NEED_UPDATE,
     data::LocalChart,
@@ -28,9 +28,9 @@ use std::{
     fs::File,
     io::{BufReader, Read, Seek, SeekFrom},
     mem,
-    path::{Component, PathBuf},
+    path::{Component, Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         mpsc, Arc,
     },
     thread_local,
@@ -64,7 +64,7 @@ pub struct MainScene {
 
     pages: Vec<Box<dyn Page>>,
 
-    import_task: Option<Task<Result<(LocalChart, ParseWarnings)>>>,
+    import_task: Option<Task<Result<Vec<(LocalChart, ParseWarnings)>>>>,
 
     mp_btn: RectButton,
     mp_icon: SafeTexture,
@@ -76,15 +76,13 @@ pub struct MainScene {
     // batch import
     batch_import_confirm: Arc<AtomicBool>,
     batch_import: Option<(String, ExportInfo)>,
-    batch_import_task: Option<Task<Result<()>>>,
+    // resolves to the content hashes to record, by local path
+    batch_import_task: Option<Task<Result<Vec<(String, String)>>>>,
     batch_import_rx: Option<mpsc::Receiver<ImportChart>>,
     batch_imported_charts: Vec<ImportChart>,
-    batch_import_total: usize,
-}
-
-enum ImportChart {
-    Imported(Box<LocalChart>, ParseWarnings),
-    Skipped(String),
+    batch_import_total: Arc<AtomicUsize>,
+    // archives handled so far, as a pack yields several charts
+    batch_import_done: Arc<AtomicUsize>,
 }
 
 impl MainScene {
@@ -172,7 +170,8 @@ impl MainScene {
             batch_import_task: None,
             batch_import_rx: None,
             batch_imported_charts: Vec::new(),
-            batch_import_total: 0,
+            batch_import_total: Arc::default(),
+            batch_import_done: Arc::default(),
         })
     }
 
@@ -188,6 +187,22 @@ impl MainScene {
     pub fn take_imported_respack() -> Option<ResPackItem> {
         RESPACK_ITEM.with(|it| it.borrow_mut().take())
     }
+
+    fn start_bulk_import(&mut self, path: String) {
+        let data = get_data();
+        let existing: Vec<_> = data
+            .charts
+            .iter()
+            .map(|it| (it.local_path.clone(), it.info.name.clone(), data.chart_hashes.get(&it.local_path).cloned()))
+            .collect();
+        let (tx, rx) = mpsc::channel();
+        self.batch_import_rx = Some(rx);
+        self.batch_imported_charts.clear();
+        self.batch_import_total = Arc::default();
+        self.batch_import_done = Arc::default();
+        self.batch_import_task =
+            Some(Task::new(bulk_import(path, existing, Arc::clone(&self.batch_import_total), Arc::clone(&self.batch_import_done), tx)));
+    }
 }
 
 impl Scene for MainScene {
@@ -231,7 +246,7 @@ impl Scene for MainScene {
         if self.state.fader.transiting() {
             return Ok(false);
         }
-        if self.import_task.is_some() {
+        if self.import_task.is_some() || self.batch_import_task.is_some() {
             return Ok(true);
         }
 
@@ -303,6 +318,7 @@ impl Scene for MainScene {
                 }
             })?;
         }
+        crate::collection_sync::update()?;
         let s = &mut self.state;
         s.update(tm);
         if s.fader.transiting() {
@@ -347,12 +363,14 @@ impl Scene for MainScene {
                     Err(err) => {
                         show_error(err.context(itl!("import-failed")));
                     }
-                    Ok((chart, warnings)) => {
-                        if let Some(warn) = parse_warnings_to_string(&warnings) {
-                            Dialog::plain(itl!("warning"), warn).show();
+                    Ok(charts) => {
+                        let mut warnings: Vec<_> = charts.iter().filter_map(|(_, warnings)| parse_warnings_to_string(warnings)).collect();
+                        warnings.dedup();
+                        if !warnings.is_empty() {
+                            Dialog::plain(itl!("warning"), warnings.join("\n")).show();
                         }
                         show_message(itl!("import-success")).ok();
-                        get_data_mut().charts.push(chart);
+                        get_data_mut().charts.extend(charts.into_iter().map(|(chart, _)| chart));
                         save_data()?;
                         self.state.reload_local_charts();
                         NEED_UPDATE.store(true, Ordering::Relaxed);
@@ -402,15 +420,22 @@ impl Scene for MainScene {
                         Err(err) => {
                             show_error(err.context(itl!("import-failed")));
                         }
+                        Ok(None) if is_archive_of_archives(Path::new(&file)).unwrap_or(false) => {
+                            self.start_bulk_import(file);
+                        }
                         Ok(None) => {
                             self.import_task = Some(Task::new(async move {
-                                let file = File::open(&file).context("cannot open file")?;
-                                import_chart(file).await
+                                let mut file = File::open(&file).context("cannot open file")?;
+                                if is_pack_archive(&mut file)? {
+                                    import_pack(file).await
+                                } else {
+                                    Ok(vec![import_chart(file).await?])
+                                }
                             }));
                         }
                         Ok(Some((info, count))) => {
                             self.batch_import = Some((file, info));
-                            self.batch_import_total = count;
+                            self.batch_import_total = Arc::new(AtomicUsize::new(count));
                             confirm_dialog(itl!("batch-import"), itl!("batch-import-confirm", "count" => count), self.batch_import_confirm.clone());
                         }
                     };
@@ -485,6 +510,9 @@ impl Scene for MainScene {
                         }
                     }
                 }
+                "_import_bulk" => {
+                    self.start_bulk_import(file);
+                }
                 _ => return_file(id, file),
             }
         }
@@ -493,6 +521,8 @@ impl Scene for MainScene {
                 let (tx, rx) = mpsc::channel();
                 self.batch_import_rx = Some(rx);
                 self.batch_imported_charts.clear();
+                self.batch_import_done = Arc::default();
+                let done = Arc::clone(&self.batch_import_done);
                 self.batch_import_task = Some(Task::new(async move {
                     let mut archive = zip::ZipArchive::new(BufReader::new(File::open(&file)?))?;
                     let charts_dir = dir::charts()?;
@@ -515,11 +545,17 @@ impl Scene for MainScene {
                         };
                         match dir.to_str() {
                             Some("custom") => {
-                                let tf = to_tempfile()?;
-                                let (chart, warnings) = import_chart(tf)
-                                    .await
-                                    .with_context(|| itl!("batch-import-failed-chart", "chart" => name.display().to_string()))?;
-                                let _ = tx.send(ImportChart::Imported(Box::new(chart), warnings)).ok();
+                                let mut tf = to_tempfile()?;
+                                let charts = if is_pack_archive(&mut tf)? {
+                                    import_pack(tf).await
+                                } else {
+                                    import_chart(tf).await.map(|it| vec![it])
+                                }
+                                .with_context(|| itl!("batch-import-failed-chart", "chart" => name.display().to_string()))?;
+                                for (chart, warnings) in charts {
+                                    let _ = tx.send(ImportChart::Imported(Box::new(chart), warnings)).ok();
+                                }
+                                done.fetch_add(1, Ordering::Relaxed);
                             }
                             Some("download") => {
                                 let Some(id) = name.to_str().and_then(|it| it.strip_suffix(".zip")).and_then(|it| it.parse::<i32>().ok()) else {
@@ -531,6 +567,7 @@ impl Scene for MainScene {
                                 if std::fs::exists(&path)? {
                                     let info: ChartInfo = serde_yaml::from_reader(File::open(path.join("info.yml"))?)?;
                                     let _ = tx.send(ImportChart::Skipped(info.name));
+                                    done.fetch_add(1, Ordering::Relaxed);
                                     continue;
                                 }
                                 std::fs::create_dir(&path)?;
@@ -539,13 +576,14 @@ impl Scene for MainScene {
                                     .await
                                     .with_context(|| itl!("batch-import-failed-chart", "chart" => name.display().to_string()))?;
                                 let _ = tx.send(ImportChart::Imported(Box::new(chart), warnings)).ok();
+                                done.fetch_add(1, Ordering::Relaxed);
                             }
                             _ => {
                                 warn!("invalid batch import dir: {:?}", dir);
                             }
                         }
                     }
-                    Ok(())
+                    Ok(Vec::new())
                 }));
             }
         }
@@ -565,6 +603,10 @@ impl Scene for MainScene {
 
         if let Some(task) = &mut self.batch_import_task {
             if let Some(res) = task.take() {
+                // charts sent in the last frames haven't been received yet
+                if let Some(rx) = &self.batch_import_rx {
+                    self.batch_imported_charts.extend(rx.try_iter());
+                }
                 match res {
                     Err(err) => {
                         let charts = dir::charts()?;
@@ -576,11 +618,14 @@ impl Scene for MainScene {
                         }
                         show_error(err.context(itl!("batch-import-failed")));
                     }
-                    Ok(()) => {
+                    Ok(hashes) => {
                         let mut warning_messages = vec![];
                         let data = get_data_mut();
+                        data.chart_hashes.extend(hashes);
                         let mut count = 0;
                         let mut skipped = String::new();
+                        let mut duplicates = Vec::new();
+                        let mut failed = Vec::new();
                         for chart in self.batch_imported_charts.drain(..) {
                             match chart {
                                 ImportChart::Imported(chart, warnings) => {
@@ -596,6 +641,8 @@ impl Scene for MainScene {
                                     }
                                     skipped.push_str(&name);
                                 }
+                                ImportChart::Duplicate(source, existing) => duplicates.push(format!("\n- {source} → {existing}")),
+                                ImportChart::Failed(source, err) => failed.push(format!("\n- {source}: {err:#}")),
                             }
                         }
                         save_data()?;
@@ -607,6 +654,16 @@ impl Scene for MainScene {
                             message.push('\n');
                             message += &itl!("batch-import-downloaded-skipped", "charts" => skipped);
                         }
+                        if !duplicates.is_empty() {
+                            message += "\n\n";
+                            message += &itl!("bulk-import-duplicates");
+                            message += &duplicates.concat();
+                        }
+                        if !failed.is_empty() {
+                            message += "\n\n";
+                            message += &itl!("bulk-import-failures");
+                            message += &failed.concat();
+                        }
 
                         if !warning_messages.is_empty() {
                             message += "\n\n";
@@ -697,8 +754,8 @@ impl Scene for MainScene {
             ui.full_loading(itl!("importing"), s.t);
         }
         if self.batch_import_task.is_some() {
-            let current = self.batch_imported_charts.len();
-            let total = self.batch_import_total;
+            let current = self.batch_import_done.load(Ordering::Relaxed);
+            let total = self.batch_import_total.load(Ordering::Relaxed);
             ui.full_loading(itl!("batch-importing", "current" => current, "total" => total), s.t);
         }
 
@@ -0,0 +1,224 @@
+use super::L10N_LOCAL;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use prpr::{
+    info::{ChartFormat, ChartInfo},
+    scene::GameScene,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{Seek, Write},
+    path::Path,
+};
+use tracing::warn;
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileDigest {
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl FileDigest {
+    pub fn of(bytes: &[u8]) -> Self {
+        Self {
+            size: bytes.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(bytes)),
+        }
+    }
+}
+
+/// Describes the content of an exported chart archive, so that missing or modified files can be
+/// detected when it's imported again.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportManifest {
+    pub version: u32,
+    pub app_version: String,
+    pub exported_at: DateTime<Utc>,
+    pub format: Option<ChartFormat>,
+    /// Info of the chart, or of the first chart of a pack.
+    pub info: ChartInfo,
+    /// Every file in the archive (except the manifest itself), by path relative to the chart root.
+    pub files: BTreeMap<String, FileDigest>,
+}
+
+impl ExportManifest {
+    pub const FILE: &'static str = "export-manifest.json";
+    pub const VERSION: u32 = 1;
+
+    pub fn new(info: ChartInfo, format: Option<ChartFormat>, files: BTreeMap<String, FileDigest>) -> Self {
+        Self {
+            version: Self::VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_owned(),
+            exported_at: Utc::now(),
+            format,
+            info,
+            files,
+        }
+    }
+
+    /// Checks the files in `root` against the manifest.
+    ///
+    /// Missing and modified files are errors, while files that aren't listed are only logged.
+    pub fn verify(&self, root: &Path) -> Result<()> {
+        let dir = prpr::dir::Dir::new(root)?;
+        let mut missing = Vec::new();
+        let mut modified = Vec::new();
+        for (name, digest) in &self.files {
+            match dir.read(name) {
+                Ok(bytes) => {
+                    if FileDigest::of(&bytes) != *digest {
+                        modified.push(name.as_str());
+                    }
+                }
+                Err(_) => missing.push(name.as_str()),
+            }
+        }
+        if !missing.is_empty() {
+            bail!(itl!("manifest-missing-files", "files" => missing.join(", ")));
+        }
+        if !modified.is_empty() {
+            bail!(itl!("manifest-modified-files", "files" => modified.join(", ")));
+        }
+        for entry in WalkDir::new(root) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let name = relative_name(root, entry.path())?;
+            if name != Self::FILE && !self.files.contains_key(&name) {
+                warn!("file not listed in export manifest: {name}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The path of `path` relative to `root`, with `/` as separator whatever the platform.
+pub(super) fn relative_name(root: &Path, path: &Path) -> Result<String> {
+    let name = path.strip_prefix(root)?;
+    Ok(name.components().map(|it| it.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+/// Zips the chart folder at `src` for sharing, along with an [`ExportManifest`].
+pub fn export_chart<W: Write + Seek>(src: &Path, dst: &mut W) -> Result<()> {
+    let info: ChartInfo = serde_yaml::from_reader(File::open(src.join("info.yml")).context("cannot open info.yml")?)?;
+    let mut zip = ZipWriter::new(dst);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(0o755);
+    let mut files = BTreeMap::new();
+    let mut format = info.format.clone();
+    for entry in WalkDir::new(src) {
+        let entry = entry?;
+        let path = entry.path();
+        let name = relative_name(src, path)?;
+        if name.is_empty() || name == ExportManifest::FILE {
+            continue;
+        }
+        if entry.file_type().is_dir() {
+            zip.add_directory(name.as_str(), options)?;
+            continue;
+        }
+        let bytes = std::fs::read(path)?;
+        if format.is_none() && name == info.chart {
+            format = Some(GameScene::infer_chart_format(&info, &bytes));
+        }
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(&bytes)?;
+        files.insert(name, FileDigest::of(&bytes));
+    }
+    zip.start_file(ExportManifest::FILE, options)?;
+    serde_json::to_writer_pretty(&mut zip, &ExportManifest::new(info, format, files))?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Verifies the manifest of a chart or pack unpacked into `root`, if there is one, and removes it.
+pub fn verify_manifest(root: &Path) -> Result<()> {
+    let path = root.join(ExportManifest::FILE);
+    if !path.is_file() {
+        return Ok(());
+    }
+    let manifest: ExportManifest = serde_json::from_reader(File::open(&path)?).with_context(|| itl!("manifest-invalid"))?;
+    if manifest.version > ExportManifest::VERSION {
+        warn!("export manifest version {} is newer than supported, skipping verification", manifest.version);
+    } else {
+        manifest.verify(root)?;
+    }
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prpr::ext::unzip_into;
+    use std::io::Cursor;
+    use tempfile::TempDir;
+
+    /// Exports a small chart and unpacks it again, as an import would.
+    fn exported() -> Result<TempDir> {
+        let src = tempfile::tempdir()?;
+        let info = ChartInfo {
+            chart: "chart.json".to_owned(),
+            music: "song.ogg".to_owned(),
+            illustration: "res/bg.png".to_owned(),
+            ..ChartInfo::default()
+        };
+        std::fs::write(src.path().join("info.yml"), serde_yaml::to_string(&info)?)?;
+        std::fs::write(src.path().join("chart.json"), r#"{"formatVersion":3,"offset":0,"judgeLineList":[]}"#)?;
+        std::fs::write(src.path().join("song.ogg"), [1u8; 64])?;
+        std::fs::create_dir(src.path().join("res"))?;
+        std::fs::write(src.path().join("res/bg.png"), [2u8; 32])?;
+
+        let mut archive = Cursor::new(Vec::new());
+        export_chart(src.path(), &mut archive)?;
+        archive.set_position(0);
+        let dst = tempfile::tempdir()?;
+        unzip_into(archive, &prpr::dir::Dir::new(dst.path())?, true)?;
+        Ok(dst)
+    }
+
+    #[test]
+    fn matching() -> Result<()> {
+        let dir = exported()?;
+        let manifest: ExportManifest = serde_json::from_reader(File::open(dir.path().join(ExportManifest::FILE))?)?;
+        assert_eq!(manifest.files.keys().collect::<Vec<_>>(), ["chart.json", "info.yml", "res/bg.png", "song.ogg"]);
+        assert!(manifest.format == Some(ChartFormat::Pgr));
+
+        // files that aren't listed are only logged
+        std::fs::write(dir.path().join("notes.txt"), "hi")?;
+        verify_manifest(dir.path())?;
+        assert!(!dir.path().join(ExportManifest::FILE).exists());
+        // with the manifest gone there's nothing left to check
+        std::fs::write(dir.path().join("song.ogg"), b"")?;
+        verify_manifest(dir.path())?;
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_file() -> Result<()> {
+        let dir = exported()?;
+        // same size, different content
+        std::fs::write(dir.path().join("song.ogg"), [3u8; 64])?;
+        let err = verify_manifest(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("song.ogg"), "{err}");
+        assert!(!err.contains("chart.json"), "{err}");
+        Ok(())
+    }
+
+    #[test]
+    fn missing_file() -> Result<()> {
+        let dir = exported()?;
+        std::fs::remove_file(dir.path().join("res/bg.png"))?;
+        let err = verify_manifest(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("res/bg.png"), "{err}");
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+use super::{
+    fs_from_path, gen_custom_dir, lint_chart, lint_extra,
+    manifest::{relative_name, ExportManifest, FileDigest},
+    verify_manifest, L10N_LOCAL,
+};
+use crate::{
+    data::{pack_member_indices, LocalChart},
+    dir, get_data,
+};
+use anyhow::{bail, Context, Result};
+use prpr::{
+    config::Mods,
+    ext::{spawn_task, unzip_into},
+    fs,
+    info::{ChartInfo, ChartPack},
+    parse::ParseWarnings,
+    scene::GameScene,
+};
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, Seek, SeekFrom, Write},
+    path::Path,
+};
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// Returns the local path of the pack containing the chart at `local_path`, if any.
+///
+/// Charts in a pack live at `custom/<pack>/<index>`, one level deeper than ordinary charts.
+pub fn pack_root(local_path: &str) -> Option<&str> {
+    let (root, _) = local_path.rsplit_once('/')?;
+    root.contains('/').then_some(root)
+}
+
+/// Lists the charts of the pack containing `local_path`, in manifest order.
+pub fn pack_members(local_path: &str) -> Vec<&'static LocalChart> {
+    let Some(root) = pack_root(local_path) else {
+        return Vec::new();
+    };
+    let mut members: Vec<_> = get_data().charts.iter().filter(|it| pack_root(&it.local_path) == Some(root)).collect();
+    members.sort_by_key(|it| it.local_path.rsplit_once('/').and_then(|(_, index)| index.parse::<usize>().ok()));
+    members
+}
+
+/// Checks whether `file` is a chart pack archive, leaving the cursor at the start.
+///
+/// The manifest may be either at the root or inside a single top-level folder.
+pub fn is_pack_archive(file: &mut File) -> Result<bool> {
+    let res = zip::ZipArchive::new(BufReader::new(&mut *file)).map(|zip| {
+        zip.file_names().any(|name| match name.split_once('/') {
+            Some((_, rest)) => rest == ChartPack::MANIFEST,
+            None => name == ChartPack::MANIFEST,
+        })
+    });
+    file.seek(SeekFrom::Start(0))?;
+    Ok(res?)
+}
+
+/// Unpacks a chart pack into a new custom folder and registers each of its charts.
+pub async fn import_pack(file: File) -> Result<Vec<(LocalChart, ParseWarnings)>> {
+    let (path, id) = gen_custom_dir()?;
+    match import_pack_to(&path, &format!("custom/{id}"), file).await {
+        Err(err) => {
+            std::fs::remove_dir_all(path)?;
+            Err(err)
+        }
+        Ok(val) => Ok(val),
+    }
+}
+
+async fn import_pack_to(path: &Path, local_path: &str, file: File) -> Result<Vec<(LocalChart, ParseWarnings)>> {
+    let root = path.to_owned();
+    let verified = spawn_task(move || {
+        unzip_into(BufReader::new(file), &prpr::dir::Dir::new(&root)?, true)?;
+        Ok(verify_manifest(&root))
+    })
+    .await?;
+    verified.with_context(|| itl!("manifest-verify-failed"))?;
+    let dir = prpr::dir::Dir::new(path)?;
+    let pack: ChartPack = serde_yaml::from_reader(dir.open(ChartPack::MANIFEST)?).with_context(|| itl!("info-fail"))?;
+    if pack.charts.is_empty() {
+        bail!(itl!("pack-empty"));
+    }
+    let mut res = Vec::with_capacity(pack.charts.len());
+    for index in 0..pack.charts.len() {
+        let mut info = pack.chart_info(index).unwrap();
+        dir.create_dir_all(index.to_string())?;
+        let local_path = format!("{local_path}/{index}");
+        let mut fs = fs_from_path(&local_path)?;
+        fs::fix_info(fs.as_mut(), &mut info).await.with_context(|| itl!("invalid-chart"))?;
+        if info.use_attach_ui_fix.is_none() {
+            info.use_attach_ui_fix = Some(true);
+        }
+        let mut warnings = lint_chart(fs.as_mut(), &info).await?;
+        warnings.shader_problems = lint_extra(fs.as_mut()).await;
+        info.rpe_version = warnings.rpe_version;
+        dir.create(format!("{index}/info.yml"))?
+            .write_all(serde_yaml::to_string(&info)?.as_bytes())?;
+        res.push((
+            LocalChart {
+                info: info.into(),
+                local_path,
+                record: None,
+                mods: Mods::default(),
+                played_unlock: false,
+            },
+            warnings,
+        ));
+    }
+    Ok(res)
+}
+
+/// Writes the pack containing the chart at `local_path` as a pack archive, with the manifest
+/// rebuilt from the current info of each chart and an [`ExportManifest`] of its files.
+pub fn export_pack<W: Write + Seek>(local_path: &str, dst: &mut W) -> Result<()> {
+    let root_path = pack_root(local_path).context("not in a pack")?;
+    let root = format!("{}/{root_path}", dir::charts()?);
+    let dir = prpr::dir::Dir::new(&root)?;
+    let mut pack: ChartPack = serde_yaml::from_reader(dir.open(ChartPack::MANIFEST)?)?;
+    let indices = pack_member_indices(Path::new(&root))?;
+    let mut charts = Vec::with_capacity(indices.len());
+    for index in &indices {
+        let mut info: ChartInfo = serde_yaml::from_reader(dir.open(format!("{index}/info.yml"))?)?;
+        // these are per-device fields
+        info.id = None;
+        info.uploader = None;
+        charts.push(info);
+    }
+    pack.charts = charts;
+    let info = pack.chart_info(0).context("empty pack")?;
+
+    let mut zip = ZipWriter::new(dst);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .unix_permissions(0o755);
+    let mut files = BTreeMap::new();
+    let mut format = info.format.clone();
+    let bytes = serde_yaml::to_string(&pack)?.into_bytes();
+    zip.start_file(ChartPack::MANIFEST, options)?;
+    zip.write_all(&bytes)?;
+    files.insert(ChartPack::MANIFEST.to_owned(), FileDigest::of(&bytes));
+    for entry in WalkDir::new(&root).min_depth(1).sort_by_file_name() {
+        let entry = entry?;
+        let name = relative_name(Path::new(&root), entry.path())?;
+        // the info of each chart is in the rebuilt manifest
+        let chart_info = name
+            .split_once('/')
+            .is_some_and(|(folder, rest)| rest == "info.yml" && folder.parse::<usize>().is_ok());
+        if name == ChartPack::MANIFEST || name == ExportManifest::FILE || chart_info {
+            continue;
+        }
+        let Some(name) = archive_name(&name, &indices) else {
+            continue;
+        };
+        if entry.file_type().is_dir() {
+            zip.add_directory(name.as_str(), options)?;
+            continue;
+        }
+        let bytes = std::fs::read(entry.path())?;
+        if format.is_none() && (name == info.chart || name == format!("0/{}", info.chart)) {
+            format = Some(GameScene::infer_chart_format(&info, &bytes));
+        }
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(&bytes)?;
+        files.insert(name, FileDigest::of(&bytes));
+    }
+    zip.start_file(ExportManifest::FILE, options)?;
+    serde_json::to_writer_pretty(&mut zip, &ExportManifest::new(info, format, files))?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Maps a path in an unpacked pack to its path in the exported archive. The folders of the charts
+/// are renumbered to follow the rebuilt manifest, since charts may have been deleted in between.
+fn archive_name(name: &str, indices: &[usize]) -> Option<String> {
+    let (folder, rest) = match name.split_once('/') {
+        Some((folder, rest)) => (folder, Some(rest)),
+        None => (name, None),
+    };
+    let Ok(index) = folder.parse::<usize>() else {
+        return Some(name.to_owned());
+    };
+    let position = indices.iter().position(|it| *it == index)?;
+    Some(match rest {
+        Some(rest) => format!("{position}/{rest}"),
+        None => position.to_string(),
+    })
+}
@@ -3,15 +3,17 @@ prpr_l10n::tl_file!("song");
 #[cfg(feature = "video")]
 use super::UnlockScene;
 use super::{
-    confirm_delete, confirm_dialog, fs_from_path, gen_custom_dir, import_chart_to, render_ldb, LdbDisplayItem, ProfileScene, ASSET_CHART_INFO,
+    confirm_delete, confirm_dialog, export_chart, export_pack, fs_from_path, gen_custom_dir, import_chart_to, lint_extra, pack_members, pack_root,
+    parse_warnings_to_string, render_ldb, LdbDisplayItem, ProfileScene,
 };
 use crate::{
+    calibration,
     charts_view::NEED_UPDATE,
     client::{
-        basic_client_builder, recv_raw, Chart, ChartRef, ChartRefChartInfo, Client, Collection, CollectionUpdate, Permissions, Ptr, Record, User,
-        UserManager, CLIENT_TOKEN,
+        basic_client_builder, recv_raw, Chart, ChartRef, ChartRefChartInfo, Client, CollectionUpdate, Permissions, Ptr, Record, User, UserManager,
+        CLIENT_TOKEN,
     },
-    data::{BriefChartInfo, LocalChart},
+    data::{BriefChartInfo, ChartOverrides, LocalChart},
     dir, get_data, get_data_mut,
     icons::Icons,
     page::{
@@ -37,12 +39,13 @@ use prpr::{
     config::Mods,
     core::{Tweenable, BOLD_FONT},
     ext::{
-        open_url, poll_future, rect_shadow, semi_black, semi_white, unzip_into, JoinToString, LocalTask, RectExt, SafeTexture, ScaleType,
-        BLACK_TEXTURE,
+        audio_output_device, open_url, poll_future, rect_shadow, semi_black, semi_white, unzip_into, JoinToString, LocalTask, RectExt, SafeTexture,
+        ScaleType, BLACK_TEXTURE,
     },
     fs::{self},
     info::ChartInfo,
-    judge::{icon_index, Judge},
+    judge::{icon_index, Judge, PlayResult},
+    parse::ParseWarnings,
     scene::{
         request_file, request_input, return_file, return_input, show_error, show_message, take_file, take_input, BasicPlayer, GameMode, LoadingScene,
         LocalSceneTask, NextScene, RecordUpdateState, SaveFn, Scene, SimpleRecord, UpdateFn, UploadFn,
@@ -188,8 +191,7 @@ fn with_effects((mut frames, sample_rate): (Vec<Frame>, u32), range: Option<(f32
 }
 
 async fn load_local_tuple(local_path: &str, def_illu: SafeTexture, info: ChartInfo) -> Result<LocalTuple> {
-    let dir = prpr::dir::Dir::new(format!("{}/{local_path}", dir::charts()?))?;
-    let bytes = dir.read(&info.music)?;
+    let bytes = fs_from_path(local_path)?.load_file(&info.music).await?;
     let (frames, sample_rate) = AudioClip::decode(bytes)?;
     let length = frames.len() as f32 / sample_rate as f32;
     if info.preview_end.unwrap_or(info.preview_start + 1.) > length {
@@ -315,6 +317,8 @@ pub struct SongScene {
     entity: Option<Chart>,
     info: BriefChartInfo,
     local_path: Option<String>,
+    /// Charts of the same pack, as `(local path, level, button)`.
+    pack_btns: Vec<(String, String, DRectButton)>,
 
     downloading: Option<Downloading>,
     loading_last: f32,
@@ -338,12 +342,18 @@ pub struct SongScene {
     mod_btn: RectButton,
     mod_scroll: Scroll,
     mod_btns: Vec<(DRectButton, bool)>,
+    overrides: ChartOverrides,
+    /// Background dim of the chart itself, shown when not overridden
+    chart_dim: f32,
+    chart_dim_task: Option<Task<Result<f32>>>,
+    reset_overrides_btn: DRectButton,
 
     side_content: SideContent,
     side_enter_time: f32,
 
     save_task: Option<Task<Result<LocalTuple>>>,
-    upload_task: Option<Task<Result<BriefChartInfo>>>,
+    // resolves to the updated info and the problems found in the shaders of the chart's effects
+    upload_task: Option<Task<Result<(BriefChartInfo, Vec<String>)>>>,
 
     ldb: Option<(Option<u32>, Vec<LdbItem>)>,
     ldb_task: Option<Task<Result<Vec<LdbItem>>>>,
@@ -400,10 +410,8 @@ pub struct SongScene {
 
     update_cksum_passed: Option<bool>,
     update_cksum_task: Option<Task<Result<bool>>>,
-    chart_type: ChartType,
 
     is_fav: Option<bool>,
-    toggle_fav_task: Option<Task<Result<(Collection, bool)>>>,
 
     confirm_cancel_edit: Arc<AtomicBool>,
 
@@ -420,6 +428,15 @@ impl SongScene {
                 chart.info.id = Some(id.parse().unwrap());
             }
         }
+        let members = local_path.as_deref().map(pack_members).unwrap_or_default();
+        if let Some(member) = members.iter().find(|it| Some(&it.local_path) == local_path.as_ref()) {
+            // the library card shows the levels of the whole pack
+            chart.info = member.info.clone();
+        }
+        let pack_btns = members
+            .into_iter()
+            .map(|it| (it.local_path.clone(), it.info.level.clone(), DRectButton::new()))
+            .collect();
         let illu = if let Some(path) = &chart.local_path {
             let illu = local_illustration(path.clone(), chart.illu.texture.1.clone(), true);
             illu.notify.notify_one();
@@ -493,6 +510,7 @@ impl SongScene {
             entity: None,
             info: chart.info,
             local_path,
+            pack_btns,
 
             downloading: None,
             loading_last: 0.,
@@ -516,6 +534,10 @@ impl SongScene {
             mod_btn: RectButton::new(),
             mod_scroll: Scroll::new(),
             mod_btns: Vec::new(),
+            overrides: ChartOverrides::default(),
+            chart_dim: ChartInfo::default().background_dim,
+            chart_dim_task: None,
+            reset_overrides_btn: DRectButton::new(),
 
             side_content: SideContent::Edit,
             side_enter_time: f32::INFINITY,
@@ -590,10 +612,8 @@ impl SongScene {
 
             update_cksum_passed: None,
             update_cksum_task: None,
-            chart_type: chart.chart_type,
 
             is_fav: None,
-            toggle_fav_task: None,
 
             confirm_cancel_edit: Arc::default(),
 
@@ -855,13 +875,19 @@ impl SongScene {
         is_unlock: bool,
     ) -> Result<LocalSceneTask> {
         let mut fs = fs_from_path(local_path)?;
+        let overrides = get_data()
+            .chart_overrides
+            .get(&ChartOverrides::key(id, local_path))
+            .cloned()
+            .unwrap_or_default();
         let can_rated = id.is_some() || local_path.starts_with(':');
         #[cfg(feature = "video")]
         let local_path = local_path.to_owned();
         #[cfg(closed)]
         let rated = {
             let config = &get_data().config;
-            !config.offline_mode && can_rated && !mods.intersects(Mods::UNRATED) && !config.use_keyboard && config.speed >= 1.0 - 1e-3
+            let speed = overrides.speed.unwrap_or(config.speed);
+            !config.offline_mode && can_rated && !mods.intersects(Mods::UNRATED) && !config.use_keyboard && speed >= 1.0 - 1e-3
         };
         #[cfg(not(closed))]
         let rated = false;
@@ -970,9 +996,12 @@ impl SongScene {
             update_fn
         });
 
+        let device = audio_output_device();
+        let offset = get_data().config.offset_for(device.as_deref());
         let save_fn: Option<SaveFn> = Some(Box::new({
             let local_path = local_path.to_string();
-            move |new_rec| -> Result<()> {
+            move |new_rec, result: &PlayResult| -> Result<()> {
+                let mut changed = calibration::record_play(result, offset, device.clone());
                 let rec = get_data_mut()
                     .charts
                     .iter_mut()
@@ -981,11 +1010,12 @@ impl SongScene {
                     .or_else(|| Some(get_data_mut().local_records.entry(local_path.clone()).or_insert(None)))
                     .unwrap();
                 if let Some(rec) = rec {
-                    if rec.update(&new_rec) {
-                        save_data()?;
-                    }
+                    changed |= rec.update(&new_rec);
                 } else {
                     *rec = Some(new_rec);
+                    changed = true;
+                }
+                if changed {
                     save_data()?;
                 }
                 Ok(())
@@ -996,6 +1026,13 @@ impl SongScene {
             let mut info = fs::load_info(fs.as_mut()).await?;
             info.id = id;
             let mut config = get_data().config.clone();
+            config.offset = offset;
+            overrides.apply(&mut config, &mut info);
+            if mode == GameMode::TweakOffset {
+                // only the personal offset is tweaked, while the chart's own is kept
+                config.offset += info.offset - overrides.offset;
+                info.offset = overrides.offset;
+            }
             config.player_name = get_data()
                 .me
                 .as_ref()
@@ -1141,6 +1178,8 @@ impl SongScene {
                         show_message(tl!("upload-login-first"));
                     } else if path.starts_with(':') {
                         show_message(tl!("upload-builtin"));
+                    } else if pack_root(path).is_some() {
+                        show_message(tl!("upload-pack"));
                     } else {
                         self.update_cksum_passed = None;
                         Dialog::plain(tl!("upload-rules"), tl!("upload-rules-content"))
@@ -1410,6 +1449,43 @@ impl SongScene {
             item(tl!("mods-instant-death-ap"), Some(tl!("mods-instant-death-ap-sub")), Mods::INSTANT_DEATH_AP);
             item(tl!("mods-instant-death-fc"), Some(tl!("mods-instant-death-fc-sub")), Mods::INSTANT_DEATH_FC);
             item(tl!("mods-no-shader"), Some(tl!("mods-no-shader-sub")), Mods::NO_SHADER);
+
+            dy!(0.03);
+            dy!(ui.text(tl!("personal")).size(0.9).draw_using(&BOLD_FONT).h + 0.01);
+            dy!(ui
+                .text(tl!("personal-sub"))
+                .pos(0.03, 0.)
+                .size(0.35)
+                .max_width(width - 0.1)
+                .multiline()
+                .color(semi_white(0.6))
+                .draw()
+                .h
+                + 0.03);
+            let config = &get_data().config;
+            let len = Some(width - 0.3);
+            ui.dx(0.03);
+            let mut offset = self.overrides.offset * 1000.;
+            let r = ui.slider(tl!("personal-offset"), -300.0..300.0, 5., &mut offset, len);
+            if (offset - self.overrides.offset * 1000.).abs() > 1e-4 {
+                self.overrides.offset = offset / 1000.;
+            }
+            dy!(r.h + 0.03);
+            let slider = |ui: &mut Ui, title: Cow<'_, str>, range: std::ops::Range<f32>, step: f32, value: &mut Option<f32>, default: f32| {
+                let mut current = value.unwrap_or(default);
+                let r = ui.slider(title, range, step, &mut current, len);
+                if (current - value.unwrap_or(default)).abs() > 1e-4 {
+                    *value = Some(current);
+                }
+                r.h + 0.03
+            };
+            dy!(slider(ui, tl!("personal-speed"), 0.5..2., 0.05, &mut self.overrides.speed, config.speed));
+            dy!(slider(ui, tl!("personal-note-scale"), 0.8..1.2, 0.005, &mut self.overrides.note_scale, config.note_scale));
+            dy!(slider(ui, tl!("personal-dim"), 0.0..1.0, 0.05, &mut self.overrides.background_dim, self.chart_dim));
+            ui.dx(-0.03);
+            let r = Rect::new(0.03, 0., width - 0.1, 0.08);
+            self.reset_overrides_btn.render_text(ui, r, rt, tl!("personal-reset"), 0.5, false);
+            dy!(r.h);
             (width, h + 0.2)
         });
     }
@@ -1514,6 +1590,21 @@ impl SongScene {
         Self::global_update_chart_info(self.local_path.as_ref().unwrap(), self.info.clone())
     }
 
+    /// Switches to another difficulty of the same pack, keeping the shared illustration.
+    fn switch_pack_chart(&mut self, local_path: String) {
+        let Some(chart) = get_data().charts.iter().find(|it| it.local_path == local_path) else {
+            return;
+        };
+        let item = ChartItem {
+            info: chart.info.clone(),
+            local_path: Some(local_path.clone()),
+            illu: self.illu.clone(),
+            chart_type: ChartType::Imported,
+        };
+        self.next_scene =
+            Some(NextScene::Replace(Box::new(SongScene::new(item, Some(local_path), Arc::clone(&self.icons), self.rank_icons.clone(), chart.mods))));
+    }
+
     fn global_update_chart_info(local_path: &str, info: BriefChartInfo) -> Result<()> {
         let _ = std::fs::remove_file(thumbnail_path(local_path)?);
         get_data_mut().charts[get_data().find_chart_by_path(local_path).unwrap()].info = info;
@@ -1553,15 +1644,11 @@ impl SongScene {
         let add = col.charts.iter().all(|it| it != &chart_ref);
         match col.update(uuid, &[chart_ref], add) {
             CollectionUpdate::Unchanged => {}
-            CollectionUpdate::Updated { sync_task, add } => {
-                if let Some(task) = sync_task {
-                    self.toggle_fav_task = Some(task);
-                } else {
-                    self.is_fav = None;
-                    FAV_UPDATED.store(true, Ordering::SeqCst);
-                    if add {
-                        show_message(tl!("fav-added")).duration(1.5).ok();
-                    }
+            CollectionUpdate::Updated { add } => {
+                self.is_fav = None;
+                FAV_UPDATED.store(true, Ordering::SeqCst);
+                if add {
+                    show_message(tl!("fav-added")).duration(1.5).ok();
                 }
             }
         }
@@ -1615,29 +1702,15 @@ impl Scene for SongScene {
         let _res = match res.downcast::<Option<f32>>() {
             Ok(offset) => {
                 if let Some(offset) = *offset {
-                    let dir = format!("{}/{}", dir::charts()?, self.local_path.as_ref().unwrap().replace(':', "_"));
-                    let path = std::path::Path::new(&dir);
-                    if !path.exists() {
-                        std::fs::create_dir_all(path)?;
-                    }
-                    let dir = prpr::dir::Dir::new(dir)?;
-                    match self.chart_type {
-                        ChartType::Integrated => {
-                            dir.create("offset")?.write_all(&offset.to_be_bytes())?;
-                            if let Ok(Some(info)) = ASSET_CHART_INFO.lock().as_deref_mut() {
-                                info.offset = offset;
-                            }
-                        }
-                        _ => {
-                            let mut info: ChartInfo = serde_yaml::from_reader(&dir.open("info.yml")?)?;
-                            info.offset = offset;
-                            dir.create("info.yml")?.write_all(serde_yaml::to_string(&info)?.as_bytes())?;
-                            let path = thumbnail_path(self.local_path.as_ref().unwrap())?;
-                            if path.exists() {
-                                std::fs::remove_file(path)?;
-                            }
-                        }
+                    let key = ChartOverrides::key(self.info.id, self.local_path.as_deref().unwrap());
+                    let overrides = &mut get_data_mut().chart_overrides;
+                    let mut chart_overrides = overrides.remove(&key).unwrap_or_default();
+                    chart_overrides.offset = offset;
+                    if !chart_overrides.is_empty() {
+                        overrides.insert(key, chart_overrides);
                     }
+                    self.overrides.offset = offset;
+                    save_data()?;
                     show_message(tl!("edit-saved")).ok();
                 }
                 return Ok(());
@@ -1685,7 +1758,6 @@ impl Scene for SongScene {
             || self.rate_task.is_some()
             || self.overwrite_task.is_some()
             || self.update_cksum_task.is_some()
-            || self.toggle_fav_task.is_some()
             || self.export_task.is_some()
             || self.autocomplete_task.is_some()
         {
@@ -1719,13 +1791,28 @@ impl Scene for SongScene {
             if self.side_enter_time > 0. && tm.real_time() as f32 > self.side_enter_time + edit_transit().unwrap_or_default() {
                 if touch.position.x < 1. - self.side_content.width() && touch.phase == TouchPhase::Started && self.save_task.is_none() {
                     if matches!(self.side_content, SideContent::Mods) {
-                        if let Some(index) = get_data().find_chart_by_path(self.local_path.as_deref().unwrap()) {
+                        let local_path = self.local_path.as_deref().unwrap();
+                        let mut changed = false;
+                        if let Some(index) = get_data().find_chart_by_path(local_path) {
                             let chart = &mut get_data_mut().charts[index];
                             if chart.mods != self.mods {
                                 chart.mods = self.mods;
-                                save_data()?;
+                                changed = true;
                             }
                         }
+                        let key = ChartOverrides::key(self.info.id, local_path);
+                        let overrides = &mut get_data_mut().chart_overrides;
+                        if overrides.get(&key).map_or(!self.overrides.is_empty(), |it| *it != self.overrides) {
+                            if self.overrides.is_empty() {
+                                overrides.remove(&key);
+                            } else {
+                                overrides.insert(key, self.overrides.clone());
+                            }
+                            changed = true;
+                        }
+                        if changed {
+                            save_data()?;
+                        }
                     }
                     if matches!(self.side_content, SideContent::Edit) && self.info_edit.as_ref().is_some_and(|it| it.updated) {
                         confirm_dialog(tl!("warn"), tl!("cancel-not-saved"), self.confirm_cancel_edit.clone());
@@ -1796,6 +1883,10 @@ impl Scene for SongScene {
                                 return Ok(true);
                             }
                         }
+                        if self.reset_overrides_btn.touch(touch, rt) {
+                            self.overrides = ChartOverrides::default();
+                            return Ok(true);
+                        }
                     }
                 }
             }
@@ -1806,6 +1897,18 @@ impl Scene for SongScene {
             self.next_scene = Some(NextScene::PopWithResult(Box::new(false)));
             return Ok(true);
         }
+        if self.scene_task.is_none() && self.next_scene.is_none() {
+            if let Some(path) = self
+                .pack_btns
+                .iter_mut()
+                .find_map(|(path, _, btn)| btn.touch(touch, t).then(|| path.clone()))
+            {
+                if self.local_path.as_ref() != Some(&path) {
+                    self.switch_pack_chart(path);
+                }
+                return Ok(true);
+            }
+        }
         if self.scene_task.is_none() && self.next_scene.is_none() && self.play_btn.touch(touch, t) {
             if self.local_path.is_some() {
                 self.launch(GameMode::Normal, false)?;
@@ -1848,6 +1951,17 @@ impl Scene for SongScene {
             }
             if self.mod_btn.touch(touch) {
                 button_hit();
+                self.overrides = self
+                    .local_path
+                    .as_ref()
+                    .and_then(|it| get_data().chart_overrides.get(&ChartOverrides::key(self.info.id, it)).cloned())
+                    .unwrap_or_default();
+                if let Some(path) = self.local_path.clone() {
+                    self.chart_dim_task = Some(Task::new(async move {
+                        let mut fs = fs_from_path(&path)?;
+                        Ok(fs::load_info(fs.as_mut()).await?.background_dim)
+                    }));
+                }
                 self.side_content = SideContent::Mods;
                 self.side_enter_time = tm.real_time() as _;
                 return Ok(true);
@@ -1962,6 +2076,14 @@ impl Scene for SongScene {
                     Ok(chart) => {
                         if let Some(chart) = chart {
                             self.entity = Some(chart.as_ref().clone());
+                            // kept with the downloaded chart, so that the library can sort by it
+                            if let Some(index) = self.local_path.as_deref().and_then(|it| get_data().find_chart_by_path(it)) {
+                                let info = &mut get_data_mut().charts[index].info;
+                                if info.rating != chart.rating {
+                                    info.rating = chart.rating;
+                                    save_data()?;
+                                }
+                            }
                             if self
                                 .info
                                 .updated
@@ -2119,9 +2241,11 @@ impl Scene for SongScene {
         }
         if let Some(config) = take_export() {
             fn export_inner(path: String, output: File) -> Result<()> {
+                if pack_root(&path).is_some() {
+                    return export_pack(&path, &mut BufWriter::new(output));
+                }
                 let charts = dir::charts()?;
-                compress_folder(Path::new(&format!("{charts}/{path}")), &mut BufWriter::new(output))?;
-                Ok(())
+                export_chart(Path::new(&format!("{charts}/{path}")), &mut BufWriter::new(output))
             }
 
             match config {
@@ -2264,8 +2388,15 @@ impl Scene for SongScene {
                     Err(err) => {
                         show_error(err.context(tl!("upload-failed")));
                     }
-                    Ok(info) => {
+                    Ok((info, shader_problems)) => {
                         show_message(tl!("upload-success")).ok();
+                        let warnings = ParseWarnings {
+                            shader_problems,
+                            ..Default::default()
+                        };
+                        if let Some(warnings) = parse_warnings_to_string(&warnings) {
+                            Dialog::plain(tl!("warn"), warnings).show();
+                        }
                         self.info = info;
                         self.update_chart_info()?;
                         self.side_enter_time = -tm.real_time() as _;
@@ -2345,6 +2476,8 @@ impl Scene for SongScene {
             let path = self.local_path.clone().unwrap();
             let info = self.info.clone();
             self.upload_task = Some(Task::new(async move {
+                // uploaded regardless, like they're imported, but the uploader gets to know
+                let shader_problems = lint_extra(fs_from_path(&path)?.as_mut()).await;
                 let root = format!("{}/{path}", dir::charts()?);
                 let root = Path::new(&root);
                 let mut chart_bytes = Vec::new();
@@ -2371,7 +2504,7 @@ impl Scene for SongScene {
                     info.updated = Some(resp.updated);
                     info.chart_updated = Some(resp.chart_updated);
                     serde_yaml::to_writer(File::create(conf)?, &info)?;
-                    Ok(info.into())
+                    Ok((info.into(), shader_problems))
                 } else {
                     #[derive(Deserialize)]
                     struct Resp {
@@ -2395,7 +2528,7 @@ impl Scene for SongScene {
                     info.chart_updated = Some(resp.created);
                     info.uploader = Some(get_data().me.as_ref().unwrap().id);
                     serde_yaml::to_writer(File::create(conf)?, &info)?;
-                    Ok(info.into())
+                    Ok((info.into(), shader_problems))
                 }
             }));
         }
@@ -2601,33 +2734,23 @@ impl Scene for SongScene {
                 self.my_rating_task = None;
             }
         }
-        if let Some(task) = &mut self.scene_task {
-            if let Some(res) = poll_future(task.as_mut()) {
-                self.next_scene = Some(res?);
-                self.scene_task = None;
-            }
-        }
-        if let Some(task) = &mut self.toggle_fav_task {
+        if let Some(task) = &mut self.chart_dim_task {
             if let Some(res) = task.take() {
                 match res {
                     Err(err) => {
-                        show_error(err);
+                        warn!(?err, "failed to load background dim of chart");
                     }
-                    Ok((col, added)) => {
-                        let data = get_data();
-                        if let Some(uuid) = data.collection_uuids().iter().find(|it| data.collection_info(it).id == Some(col.id)) {
-                            let uuid = *uuid;
-                            let local = data.collection_info(&uuid);
-                            data.set_collection_info(&uuid, local.merge(&col))?;
-                        }
-                        if added {
-                            show_message(tl!("fav-added")).duration(1.5).ok();
-                        }
-                        FAV_UPDATED.store(true, Ordering::SeqCst);
-                        self.is_fav = None;
+                    Ok(dim) => {
+                        self.chart_dim = dim;
                     }
                 }
-                self.toggle_fav_task = None;
+                self.chart_dim_task = None;
+            }
+        }
+        if let Some(task) = &mut self.scene_task {
+            if let Some(res) = poll_future(task.as_mut()) {
+                self.next_scene = Some(res?);
+                self.scene_task = None;
             }
         }
         if self.confirm_cancel_edit.swap(false, Ordering::Relaxed) {
@@ -2658,11 +2781,22 @@ impl Scene for SongScene {
                 .size(1.2)
                 .pos(r.right() + 0.02, r.y)
                 .draw();
-            ui.text(&self.info.composer)
+            let r = ui
+                .text(&self.info.composer)
                 .size(0.5)
                 .pos(r.x + 0.02, r.bottom() + 0.03)
                 .color(semi_white(0.8))
                 .draw();
+            if self.pack_btns.len() > 1 {
+                let h = 0.06;
+                let mut x = r.x;
+                for (path, level, btn) in &mut self.pack_btns {
+                    let w = ui.text(level.as_str()).size(0.5).measure().w + 0.05;
+                    let r = Rect::new(x, r.bottom() + 0.02, w, h);
+                    btn.render_text(ui, r, t, level.as_str(), 0.5, self.local_path.as_ref() == Some(path));
+                    x += w + 0.015;
+                }
+            }
 
             // bottom bar
             let s = 0.25;
@@ -2848,7 +2982,6 @@ impl Scene for SongScene {
             || self.rate_task.is_some()
             || self.overwrite_task.is_some()
             || self.update_cksum_task.is_some()
-            || self.toggle_fav_task.is_some()
             || self.autocomplete_task.is_some()
         {
             ui.full_loading_simple(t);
@@ -25,10 +25,9 @@ expected-usize = Erwartetes usize
 expected-tween = Erwartetes Tween
 no-notes-inserted = Es scheinen keine Noten im Level vorhanden zu sein
 unknown-command = Unbekannter Befehl: { $cmd }
-bp-error = Bpm-Events (bp) müssen aufeinanderfolgend sein und am Anfang der Datei stehen
 expected-01 = Erwartet 0 / 1 (echte Note / Fake-Note)
 unexpected-extra = Unerwarteter zusätzlicher Inhalt: { $next }
-line-location = In Zeile #{ $lid }
+line-location = In Zeile #{ $lid }, Spalte { $col }:
 # extra
 shader-load-failed = Shader von { $path } konnte nicht geladen werden
 shader-not-found = Vorgabe-Shader { $shader } nicht gefunden
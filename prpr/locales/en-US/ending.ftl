@@ -8,6 +8,15 @@ max-combo = MAX COMBO
 rks-delta = RKS CHANGE
 accuracy = Accuracy
 error = Error
+quality = Quality
+quality-low = Low
+quality-medium = Medium
+quality-high = High
+quality-custom = Custom
+quality-lowered = { $tier }, lowered: { $steps }
+lowered-msaa = MSAA
+lowered-particles = particles
+lowered-effects = effects
 
 uploading = Uploading record…
 uploaded = Score uploaded.
@@ -16,3 +25,11 @@ upload-cancel = Cancel Upload
 upload-retry = Retry Upload
 
 still-uploading = Uploading record to leaderboard…
+
+no-timing = No timed hits
+timing-summary = Mean { $mean }ms  SD { $std }ms  UR { $ur }
+timing-click = Tap
+timing-hold = Hold
+timing-kind = { $kind } { $mean }ms ±{ $std }ms
+early = EARLY
+late = LATE
@@ -6,7 +6,80 @@ offset-reset = Reset
 offset-save = Save
 speed = Speed Multiplier
 game-over = Game Over
+trace-saved = Trace saved to { $path }
+trace-save-failed = Failed to save the trace
 
 ex-time-out-of-range = Make sure time is within bounds.
 ex-invalid-format = Invalid format.
 ex-time-set = Time changed.
+
+hud-edit = Edit HUD
+hud-select = Tap an element to edit
+hud-show = Show
+hud-hide = Hide
+hud-anchor = Anchor: { $anchor }
+hud-anchor-none = Default
+hud-anchor-top-left = Top left
+hud-anchor-top = Top
+hud-anchor-top-right = Top right
+hud-anchor-left = Left
+hud-anchor-center = Center
+hud-anchor-right = Right
+hud-anchor-bottom-left = Bottom left
+hud-anchor-bottom = Bottom
+hud-anchor-bottom-right = Bottom right
+hud-size = Size { $size }
+hud-opacity = Opacity { $opacity }
+hud-reset = Reset
+hud-done = Done
+
+hud-pause = Pause button
+hud-combo-number = Combo number
+hud-combo = Combo label
+hud-score = Score
+hud-bar = Progress bar
+hud-name = Chart name
+hud-level = Chart level
+hud-accuracy = Accuracy
+hud-hit-error-bar = Hit error bar
+hud-judge-counts = Judgement counts
+hud-remaining-time = Remaining time
+
+inspect-lines = Inspect Lines
+inspector-pick = Tap a line to inspect it
+inspector-prev = Previous
+inspector-next = Next
+inspector-done = Done
+inspector-close = Close
+inspector-line = Line #{ $id } · { $kind } · z-index { $z }
+inspector-kind-normal = Normal
+inspector-kind-texture = Texture { $path }
+inspector-kind-text = Text
+inspector-kind-paint = Paint
+inspector-attach = Attached to { $element }
+inspector-no-parent = No parent
+inspector-parents = Parents: { $chain }
+inspector-parents-rot = Parents: { $chain }, rotating with them
+inspector-local = Local: x { $x }  y { $y }  rotation { $rot }°
+inspector-world = World: x { $x }  y { $y }  rotation { $rot }°
+inspector-scale = Scale: { $x } × { $y }  Alpha: { $alpha }
+inspector-speed = Speed: { $speed }  Height: { $height }
+inspector-color = Color: { $color }
+inspector-default = Default
+inspector-ctrl = Control at the line: alpha { $alpha }  size { $size }  pos { $pos }  y { $y }
+inspector-notes = Upcoming notes ({ $count })
+inspector-note = { $time }s  { $kind }  x { $x }
+inspector-note-click = Tap
+inspector-note-hold = Hold
+inspector-note-flick = Flick
+inspector-note-drag = Drag
+inspector-note-below = , below
+inspector-note-fake = , fake
+inspector-keyframes = Keyframes from { $from }s to { $to }s
+inspector-anim-x = X
+inspector-anim-y = Y
+inspector-anim-rot = Rotation
+inspector-anim-alpha = Alpha
+inspector-anim-speed = Speed
+inspector-anim-scale = Scale
+inspector-anim-color = Color
@@ -20,6 +20,7 @@ hitsound-missing = Hitsound `{ $name }` is missing.
 
 # pgr
 event-not-contiguous = Events should be contiguous.
+unknown-format-version = Unknown format version: { $version }.
 
 alpha-events-parse-failed = Failed to parse alpha events.
 rotate-events-parse-failed = Failed to parse rotate events.
@@ -33,13 +34,26 @@ expected-tween = Expected tween.
 
 no-notes-inserted = No notes appear to be in the chart.
 unknown-command = Unknown command: { $cmd }.
-bp-error = Bpm events (bp) should be contiguous and in the head of the file.
+no-bpm = No bpm events (bp) in the chart.
 expected-01 = Expected 0 / 1 (real note / fake note).
+expected-12 = Expected 1 / 2 (above / below the line).
 unexpected-extra = Unexpected extra content: { $next }.
-line-location = On line #{ $lid }.
+line-location = On line #{ $lid }, column { $col }:
+more-errors = …and { $count } more.
+pec-errors = { $count ->
+    [one] Found an error in the chart.
+   *[other] Found { $count } errors in the chart.
+}
 
 # extra
 shader-load-failed = Cannot load shader from { $path }.
 shader-not-found = Cannot find preset shader { $shader }.
+shader-invalid = Shader { $shader } cannot be used.
 effect-location = In effect #{ $id }.
+effect-global-lines = A global effect cannot be limited to lines.
+effect-lines-overlap = Line { $line } is covered by effects with different lines.
+texture-load-failed = Failed to load texture from { $path }.
+particles-load-failed = Failed to load particle preset from { $path }.
+particles-location = In particles #{ $id }.
+extra-line-missing = Line { $line } doesn't exist, the chart has { $count } lines.
 video-load-failed = Failed to read video from { $path }.
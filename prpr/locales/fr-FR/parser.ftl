@@ -33,10 +33,9 @@ expected-tween = tween attendu
 
 no-notes-inserted = Aucune note n'a encore été insérée
 unknown-command = Commande inconnue : { $cmd }
-bp-error = Les événements Bpm (bp) doivent être contigus et en tête du fichier
 expected-01 = Attendu 0/1 (vraie note / fausse note)
 unexpected-extra = Contenu supplémentaire inattendu : { $next }
-line-location = À la ligne #{ $lid }
+line-location = À la ligne #{ $lid }, colonne { $col } :
 
 # extra
 shader-load-failed = Impossible de charger le shader depuis { $path }
@@ -33,10 +33,9 @@ expected-tween = tween байх ёстой байсан
 
 no-notes-inserted = No note has been inserted yet
 unknown-command = Unknown command: { $cmd }
-bp-error = Bpm events (bp) should be contiguous and in the head of the file
 expected-01 = 0 / 1 (жинхэнэ нот / хуурамч нот) байх ёстой байсан
 unexpected-extra = Гэнэтийн илүү контент: { $next }
-line-location = Мөр дээр #{ $lid }
+line-location = Мөр #{ $lid }, багана { $col } дээр:
 
 # extra
 shader-load-failed = { $path }-аас4 шейдер ачаалахад амжилтгүй боллоо
@@ -30,10 +30,9 @@ expected-tween = Oczekiwany tween
 
 no-notes-inserted = Nie ma jeszcze żadnej nuty
 unknown-command = Nieznane polecenie: { $cmd }
-bp-error = zdarzenia BPM (bp) powinny być styczne w pierwszych liniach kodu
 expected-01 = Oczekiwane 0 / 1 (prawdziwa nuta / fałszywa nuta)
 unexpected-extra = Nieoczekiwany błąd: { $next }
-line-location = Na linii #{ $lid }
+line-location = Na linii #{ $lid }, kolumna { $col }:
 
 # extra
 shader-load-failed = Nie załadowano shaderów z { $path }
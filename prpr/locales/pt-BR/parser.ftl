@@ -30,10 +30,9 @@ expected-tween = Interpolação esperada
 
 no-notes-inserted = Nenhuma nota foi inserida ainda
 unknown-command = Comando desconhecido : { $cmd }
-bp-error = Os eventos Bpm (bp) devem ser contíguos e no cabeçalho do arquivo
 expected-01 = Esperado 0/1 (nota real/nota falsa)
 unexpected-extra = Conteúdo extra inesperado : { $next }
-line-location = Na linha #{ $lid }
+line-location = Na linha #{ $lid }, coluna { $col }:
 
 # extra
 shader-load-failed = Não é possível carregar o shader de  { $path }
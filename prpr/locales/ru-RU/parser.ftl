@@ -33,10 +33,9 @@ expected-tween = Должно быть tween
 
 no-notes-inserted = Не обнаружена ни одна нота
 unknown-command = Неизвестная команда: { $cmd }
-bp-error = События BPM (bp) должны быть смежным и находиться в начале файла
 expected-01 = Должно быть 0 / 1 (настоящая нота/ненастоящая нота)
 unexpected-extra = Неожиданный дополнительный контент: { $next }
-line-location = В линии #{ $lid }
+line-location = В строке #{ $lid }, столбец { $col }:
 
 # extra
 shader-load-failed = Не удается загрузить шейдер в { $path }
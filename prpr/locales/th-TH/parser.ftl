@@ -22,10 +22,9 @@ expected-usize = Expected usize
 expected-tween = Expected tween
 no-notes-inserted = ยังไม่มี Note
 unknown-command = คำสั่งที่ไม่รู้จัก : { $cmd }
-bp-error = Bpm events (bp) ควรอยู่ติดกันและอยู่บรรทัดแรกของ File
 expected-01 = Expected 0 / 1 (real note / fake note)
 unexpected-extra = Unexpected extra content: { $next }
-line-location = On line #{ $lid }
+line-location = On line #{ $lid }, column { $col }:
 # extra
 shader-load-failed = ไม่สามารถโหลด Shader จาก { $path } ได้
 shader-not-found = ไม่สามารถหา Preset shader { $shader } ได้
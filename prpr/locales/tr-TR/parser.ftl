@@ -30,10 +30,9 @@ expected-tween = tam sayı beklenmişti
 
 no-notes-inserted = Hiçbir not eklenmedi
 unknown-command = Bilinmeyen komut: { $cmd }
-bp-error = Bpm etkinlikleri (bp) sayfanın başlangıcında kesintisiz olmalı
 expected-01 = Beklenen 0 / 1 (gerçek nota / sahte nota)
 unexpected-extra = Beklenmeyen ek içerik: { $next }
-line-location = #{ $lid }. satırda
+line-location = #{ $lid }. satırın { $col }. sütununda:
 
 # extra
 shader-load-failed = { $path } içindeki shader'lar yüklenemiyor
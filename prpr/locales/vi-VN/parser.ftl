@@ -33,10 +33,9 @@ expected-tween = Yêu cầu kiểu tween.
 
 no-notes-inserted = Chart hình như không có note nào cả.
 unknown-command = Lệnh không xác định: { $cmd }
-bp-error = Các sự kiện Bpm (bp) phải liền kề và nằm tại phần đầu của tệp
 expected-01 = Yêu cầu 0 / 1 (note thật / note giả).
 unexpected-extra = Nội dung bổ sung không mong đợi: { $next }
-line-location = Tại line #{ $lid }
+line-location = Tại dòng #{ $lid }, cột { $col }:
 
 # extra
 shader-load-failed = Không thể nạp shader tại { $path }
@@ -8,6 +8,15 @@ max-combo = 最高连击
 rks-delta = RKS变化
 accuracy = 准度
 error = 误差
+quality = 画质
+quality-low = 低
+quality-medium = 中
+quality-high = 高
+quality-custom = 自定义
+quality-lowered = { $tier }，已降低：{ $steps }
+lowered-msaa = 抗锯齿
+lowered-particles = 粒子
+lowered-effects = 特效
 
 uploading = 成绩上传中
 uploaded = 成绩上传成功
@@ -16,3 +25,11 @@ upload-cancel = 取消上传
 upload-retry = 重试
 
 still-uploading = 尚在上传成绩
+
+no-timing = 没有计时判定
+timing-summary = 平均 { $mean }ms  标准差 { $std }ms  UR { $ur }
+timing-click = Tap
+timing-hold = Hold
+timing-kind = { $kind } { $mean }ms ±{ $std }ms
+early = 早
+late = 晚
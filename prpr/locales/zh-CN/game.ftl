@@ -6,7 +6,80 @@ offset-reset = 重置
 offset-save = 保存
 speed = 速度
 game-over = 游戏失败
+trace-saved = 性能追踪已保存至 { $path }
+trace-save-failed = 保存性能追踪失败
 
 ex-time-out-of-range = 时间不在范围内
 ex-invalid-format = 格式有误
 ex-time-set = 设置成功
+
+hud-edit = 编辑界面
+hud-select = 点击元素进行编辑
+hud-show = 显示
+hud-hide = 隐藏
+hud-anchor = 锚点：{ $anchor }
+hud-anchor-none = 默认
+hud-anchor-top-left = 左上
+hud-anchor-top = 上
+hud-anchor-top-right = 右上
+hud-anchor-left = 左
+hud-anchor-center = 中间
+hud-anchor-right = 右
+hud-anchor-bottom-left = 左下
+hud-anchor-bottom = 下
+hud-anchor-bottom-right = 右下
+hud-size = 大小 { $size }
+hud-opacity = 不透明度 { $opacity }
+hud-reset = 重置
+hud-done = 完成
+
+hud-pause = 暂停按钮
+hud-combo-number = 连击数
+hud-combo = 连击标签
+hud-score = 分数
+hud-bar = 进度条
+hud-name = 谱面名称
+hud-level = 谱面难度
+hud-accuracy = 准确率
+hud-hit-error-bar = 判定误差条
+hud-judge-counts = 判定统计
+hud-remaining-time = 剩余时间
+
+inspect-lines = 检查判定线
+inspector-pick = 点击判定线进行检查
+inspector-prev = 上一条
+inspector-next = 下一条
+inspector-done = 完成
+inspector-close = 关闭
+inspector-line = 判定线 #{ $id } · { $kind } · 层级 { $z }
+inspector-kind-normal = 普通
+inspector-kind-texture = 贴图 { $path }
+inspector-kind-text = 文字
+inspector-kind-paint = 绘制
+inspector-attach = 绑定到 { $element }
+inspector-no-parent = 无父线
+inspector-parents = 父线：{ $chain }
+inspector-parents-rot = 父线：{ $chain }，跟随旋转
+inspector-local = 局部：x { $x }  y { $y }  旋转 { $rot }°
+inspector-world = 全局：x { $x }  y { $y }  旋转 { $rot }°
+inspector-scale = 缩放：{ $x } × { $y }  不透明度：{ $alpha }
+inspector-speed = 速度：{ $speed }  高度：{ $height }
+inspector-color = 颜色：{ $color }
+inspector-default = 默认
+inspector-ctrl = 线上控制：不透明度 { $alpha }  大小 { $size }  位置 { $pos }  y { $y }
+inspector-notes = 即将到来的音符（{ $count }）
+inspector-note = { $time }s  { $kind }  x { $x }
+inspector-note-click = Tap
+inspector-note-hold = Hold
+inspector-note-flick = Flick
+inspector-note-drag = Drag
+inspector-note-below = ，线下
+inspector-note-fake = ，假音符
+inspector-keyframes = 关键帧：{ $from }s 至 { $to }s
+inspector-anim-x = X
+inspector-anim-y = Y
+inspector-anim-rot = 旋转
+inspector-anim-alpha = 不透明度
+inspector-anim-speed = 速度
+inspector-anim-scale = 缩放
+inspector-anim-color = 颜色
@@ -20,6 +20,7 @@ hitsound-missing = 缺少打击音 `{ $name }`
 
 # pgr
 event-not-contiguous = 事件应当连续
+unknown-format-version = 未知的格式版本: { $version }
 
 alpha-events-parse-failed = alpha 事件解析失败
 rotate-events-parse-failed = rotate 事件解析失败
@@ -33,13 +34,23 @@ expected-tween = 应当为 tween
 
 no-notes-inserted = 未插入音符
 unknown-command = 未知指令: { $cmd }
-bp-error = Bpm 事件 (bp) 应当连续且位于文件开头
+no-bpm = 谱面中没有 Bpm 事件 (bp)
 expected-01 = 应当为 0 / 1 (真 note / 假 note)
+expected-12 = 应当为 1 / 2 (判定线上方 / 下方)
 unexpected-extra = 未知的附加内容: { $next }
-line-location = #{ $lid } 行中
+line-location = 第 { $lid } 行第 { $col } 列:
+more-errors = …以及另外 { $count } 个错误
+pec-errors = 谱面中发现 { $count } 个错误
 
 # extra
 shader-load-failed = 无法从 { $path } 中加载 shader
 shader-not-found = 未找到预置 shader { $shader }
+shader-invalid = 无法使用 shader { $shader }
 effect-location = #{ $id } 号 effect 中
+effect-global-lines = 全局 effect 不能限定判定线
+effect-lines-overlap = 判定线 { $line } 被限定了不同判定线的 effect 覆盖
+texture-load-failed = 从 { $path } 中加载纹理失败
+particles-load-failed = 从 { $path } 中加载粒子预设失败
+particles-location = #{ $id } 号粒子中
+extra-line-missing = 判定线 { $line } 不存在，谱面只有 { $count } 条判定线
 video-load-failed = 从 { $path } 中加载视频失败
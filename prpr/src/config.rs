@@ -1,9 +1,12 @@
 //! Configuration module of the playing environment.\
 //! e.g. player name, volume, speed, autoplay, etc.
 
+use crate::{hud::HudLayout, input::InputProfile};
 use bitflags::bitflags;
+use macroquad::prelude::Color;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 pub static TIPS: Lazy<Vec<String>> = Lazy::new(|| include_str!("tips.txt").split('\n').map(str::to_owned).collect());
 
@@ -47,6 +50,95 @@ impl Mods {
     }
 }
 
+/// Colors of hit effects and the AP/FC indicator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FxPalette {
+    /// Colors of the resource pack
+    #[default]
+    Pack,
+    /// Orange and sky blue, which stay apart under all common kinds of color blindness
+    Colorblind,
+    /// Saturated yellow and magenta
+    HighContrast,
+}
+
+impl FxPalette {
+    pub const ALL: [Self; 3] = [Self::Pack, Self::Colorblind, Self::HighContrast];
+
+    /// Returns the perfect and good colors, or `None` to use those of the resource pack.
+    pub fn colors(self) -> Option<(Color, Color)> {
+        match self {
+            Self::Pack => None,
+            Self::Colorblind => Some((Color::from_hex_argb(0xe6e69f00), Color::from_hex_argb(0xe656b4e9))),
+            Self::HighContrast => Some((Color::from_hex_argb(0xf0ffee00), Color::from_hex_argb(0xf0ff33cc))),
+        }
+    }
+}
+
+/// How the music sounds when played at another speed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpeedPitch {
+    /// Time-stretches the music so that it keeps its pitch
+    #[default]
+    Keep,
+    /// The pitch follows the speed, higher when faster (nightcore) and lower when slower (daycore)
+    Follow,
+}
+
+impl SpeedPitch {
+    pub const ALL: [Self; 2] = [Self::Keep, Self::Follow];
+}
+
+/// Render-quality tier, setting the options that trade looks for speed together.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Quality {
+    /// No MSAA, particles or shader effects
+    Low,
+    /// Particles and shader effects, without MSAA
+    Medium,
+    /// 4x MSAA, with every note considered for drawing
+    High,
+    /// The options as set one by one
+    #[default]
+    Custom,
+}
+
+impl Quality {
+    pub const ALL: [Self; 4] = [Self::Low, Self::Medium, Self::High, Self::Custom];
+
+    /// `sample_count`, `fxaa`, `particle`, `disable_effect` and `aggressive` of the tier
+    fn options(self) -> Option<(u32, bool, bool, bool, bool)> {
+        Some(match self {
+            Self::Low => (1, false, false, true, true),
+            Self::Medium => (1, false, true, false, true),
+            // MSAA is too slow on the GPUs of OpenHarmony devices, see `Config::init`
+            Self::High => (if cfg!(target_env = "ohos") { 1 } else { 4 }, false, true, false, false),
+            Self::Custom => return None,
+        })
+    }
+
+    /// Sets the options of the tier. `Custom` leaves them as they are.
+    pub fn apply(self, config: &mut Config) {
+        config.quality = self;
+        if let Some((sample_count, fxaa, particle, disable_effect, aggressive)) = self.options() {
+            config.sample_count = sample_count;
+            config.fxaa = fxaa;
+            config.particle = particle;
+            config.disable_effect = disable_effect;
+            config.aggressive = aggressive;
+        }
+    }
+
+    /// Returns the tier whose options `config` has, or `Custom` if there's none.
+    pub fn detect(config: &Config) -> Self {
+        let options = (config.sample_count, config.fxaa, config.particle, config.disable_effect, config.aggressive);
+        Self::ALL.into_iter().find(|it| it.options() == Some(options)).unwrap_or(Self::Custom)
+    }
+}
+
 #[derive(Clone, Deserialize, Serialize)]
 #[serde(default)]
 #[serde(rename_all = "camelCase")]
@@ -57,30 +149,58 @@ pub struct Config {
     pub ap_fc_indicator: bool,
     pub aspect_ratio: Option<f32>,
     pub audio_buffer_size: Option<u32>,
+    /// Lowers the quality during play when frames stay too slow
+    pub auto_quality: bool,
     pub chart_debug: bool,
+    /// Offsets for specific audio output devices, overriding `offset`
+    pub device_offsets: HashMap<String, f32>,
     pub disable_effect: bool,
     pub double_click_to_pause: bool,
     pub double_hint: bool,
     pub fullscreen_mode: bool,
+    pub fx_palette: FxPalette,
     pub fxaa: bool,
+    /// Output latency of the device beyond the audio buffer (e.g. Bluetooth), used to schedule
+    /// hitsounds
+    pub hitsound_latency: f32,
+    /// Holds back the hitsounds of notes hit early, so that they're heard when the notes reach the
+    /// line despite the output latency
+    pub hitsound_scheduling: bool,
+    pub hud: HudLayout,
+    /// Index of the profile in `input_profiles` to play with
+    pub input_profile: usize,
+    pub input_profiles: Vec<InputProfile>,
     pub interactive: bool,
     pub mods: Mods,
     pub mp_address: String,
     pub mp_enabled: bool,
+    /// Thickness of the dark outline drawn around notes, `0` for none
+    pub note_outline: f32,
     pub note_scale: f32,
+    /// Draws a distinct shape on each kind of note
+    pub note_shapes: bool,
     pub offline_mode: bool,
     pub offset: f32,
     pub particle: bool,
     pub player_name: String,
     pub player_rks: f32,
     pub preferred_sample_rate: Option<u32>,
+    /// Times the subsystems of the game, shows them in an overlay and saves a trace on leaving
+    pub profiler: bool,
+    pub quality: Quality,
+    /// Whether the starting quality has been picked, by measuring the device on a fresh install or
+    /// from the options already set otherwise
+    pub quality_benchmarked: bool,
     pub res_pack_path: Option<String>,
     pub sample_count: u32,
     pub show_acc: bool,
     pub show_avg_fps: bool,
     pub speed: f32,
+    pub speed_pitch: SpeedPitch,
     pub touch_debug: bool,
     pub use_keyboard: bool,
+    /// Delay of the note display against judging, for screens with a latency of their own
+    pub visual_offset: f32,
     pub volume_bgm: f32,
     pub volume_music: f32,
     pub volume_sfx: f32,
@@ -97,16 +217,26 @@ impl Default for Config {
             ap_fc_indicator: true,
             aspect_ratio: None,
             audio_buffer_size: None,
+            auto_quality: false,
             chart_debug: false,
+            device_offsets: HashMap::new(),
             disable_effect: false,
             double_click_to_pause: true,
             double_hint: true,
+            fx_palette: FxPalette::Pack,
             fxaa: false,
+            hitsound_latency: 0.,
+            hitsound_scheduling: false,
+            hud: HudLayout::default(),
+            input_profile: 0,
+            input_profiles: InputProfile::presets(),
             interactive: true,
             mods: Mods::default(),
             mp_address: "mp2.phira.cn:12345".to_owned(),
             mp_enabled: false,
+            note_outline: 0.,
             note_scale: 1.0,
+            note_shapes: false,
             offline_mode: false,
             fullscreen_mode: false,
             offset: 0.,
@@ -114,13 +244,18 @@ impl Default for Config {
             player_name: "Mivik".to_string(),
             player_rks: 15.,
             preferred_sample_rate: None,
+            profiler: false,
+            quality: Quality::Custom,
+            quality_benchmarked: false,
             res_pack_path: None,
             sample_count: 1,
             show_acc: false,
             show_avg_fps: false,
             speed: 1.,
+            speed_pitch: SpeedPitch::Keep,
             touch_debug: false,
             use_keyboard: false,
+            visual_offset: 0.,
             volume_music: 1.,
             volume_sfx: 1.,
             volume_bgm: 1.,
@@ -135,10 +270,17 @@ impl Config {
         if let Some(flag) = self.autoplay {
             self.mods.set(Mods::AUTOPLAY, flag);
         }
+        if self.input_profiles.is_empty() {
+            self.input_profiles = InputProfile::presets();
+        }
+        if self.input_profile >= self.input_profiles.len() {
+            self.input_profile = 0;
+        }
         #[cfg(target_env = "ohos")]
         {
             // Due to the fucking poor performance of the Maloon GPU, the sample count must be set to 1.
             self.sample_count = 1;
+            self.quality = Quality::detect(self);
         }
     }
 
@@ -147,6 +289,11 @@ impl Config {
         self.mods.contains(m)
     }
 
+    /// Whether the music should keep its pitch at other speeds. Nightcore always changes it.
+    pub fn keeps_pitch(&self) -> bool {
+        self.speed_pitch == SpeedPitch::Keep && !self.has_mod(Mods::NIGHTCORE)
+    }
+
     #[inline]
     pub fn autoplay(&self) -> bool {
         self.has_mod(Mods::AUTOPLAY)
@@ -156,4 +303,17 @@ impl Config {
     pub fn flip_x(&self) -> bool {
         self.has_mod(Mods::FLIP_X)
     }
+
+    /// The offset to use with the audio output device named `device`.
+    pub fn offset_for(&self, device: Option<&str>) -> f32 {
+        device.and_then(|it| self.device_offsets.get(it)).copied().unwrap_or(self.offset)
+    }
+
+    /// The input profile to play with, if keyboard input is enabled.
+    pub fn input_profile(&self) -> Option<&InputProfile> {
+        if !self.use_keyboard {
+            return None;
+        }
+        self.input_profiles.get(self.input_profile).or_else(|| self.input_profiles.first())
+    }
 }
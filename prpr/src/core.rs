@@ -25,10 +25,13 @@ mod anim;
 pub use anim::{Anim, AnimFloat, AnimVector, Keyframe};
 
 mod chart;
-pub use chart::{Chart, ChartExtra, ChartSettings, HitSoundMap};
+pub use chart::{Chart, ChartExtra, ChartParticles, ChartSettings, HitSoundMap};
 
 mod effect;
-pub use effect::{Effect, Uniform};
+pub use effect::{
+    effect_package, effect_packages, load_effect_packages, register_effect_package, validate_shader, Effect, EffectLayer, EffectPackage,
+    EffectTarget, EffectValue, Uniform,
+};
 
 mod line;
 pub use line::{GifFrames, JudgeLine, JudgeLineCache, JudgeLineKind, UIElement};
@@ -44,7 +47,10 @@ mod render;
 pub use render::{copy_fbo, internal_id, MSRenderTarget};
 
 mod resource;
-pub use resource::{NoteStyle, ParticleEmitter, ResPackInfo, Resource, ResourcePack, BUFFER_SIZE, DPI_VALUE};
+pub use resource::{
+    NoteAnimation, NoteStyle, NoteTexture, ParticleEmitter, ParticleOverrides, PerKind, ResPackInfo, ResPackProblem, Resource, ResourcePack,
+    BUFFER_SIZE, DPI_VALUE,
+};
 
 mod smooth;
 pub use smooth::Smooth;
@@ -68,6 +74,8 @@ use std::cell::RefCell;
 thread_local! {
     pub static PGR_FONT: RefCell<Option<TextPainter>> = RefCell::default();
     pub static BOLD_FONT: RefCell<Option<TextPainter>> = RefCell::default();
+    /// Font of the resource pack in use, for combo and judgement text, if it has one
+    pub static PACK_FONT: RefCell<Option<TextPainter>> = RefCell::default();
 }
 
 pub fn init_assets() {
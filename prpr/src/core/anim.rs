@@ -130,6 +130,11 @@ impl<T: Tweenable> Anim<T> {
         })
     }
 
+    /// Times of the keyframes of this animation and the ones chained to it
+    pub fn keyframe_times(&self) -> impl Iterator<Item = f64> + '_ {
+        std::iter::successors(Some(self), |it| it.next.as_deref()).flat_map(|it| it.keyframes.iter().map(|kf| kf.time))
+    }
+
     pub fn map_value(&mut self, mut f: impl FnMut(T) -> T) {
         self.keyframes.iter_mut().for_each(|it| it.value = f(it.value.clone()));
         if let Some(next) = &mut self.next {
@@ -145,6 +150,26 @@ impl<T: Tweenable + Default> Anim<T> {
 }
 
 pub type AnimFloat = Anim<f32>;
+
+impl AnimFloat {
+    /// Rate of change of the value at the current time
+    pub fn now_slope(&self) -> f32 {
+        const EPS: f32 = 1e-3;
+        std::iter::successors(Some(self), |it| it.next.as_deref())
+            .filter_map(|anim| {
+                let kf1 = anim.keyframes.get(anim.cursor)?;
+                let kf2 = anim.keyframes.get(anim.cursor + 1)?;
+                let dt = (kf2.time - kf1.time) as f32;
+                if dt <= 0. {
+                    return None;
+                }
+                let t = ((anim.time - kf1.time) as f32 / dt).clamp(0., 1.);
+                let (l, r) = ((t - EPS).max(0.), (t + EPS).min(1.));
+                Some((kf2.value - kf1.value) * (kf1.tween.y(r) - kf1.tween.y(l)) / (r - l) / dt)
+            })
+            .sum()
+    }
+}
 #[derive(Default)]
 pub struct AnimVector(pub AnimFloat, pub AnimFloat);
 
@@ -1,15 +1,77 @@
-use super::{BpmList, Effect, JudgeLine, JudgeLineKind, Matrix, Resource, UIElement, Vector};
-use crate::{core::Object, fs::FileSystem, judge::JudgeStatus, ui::Ui};
+use super::{Anim, BpmList, Effect, EffectLayer, JudgeLine, JudgeLineKind, Matrix, Point, Resource, UIElement, Vector};
+use crate::{
+    core::Object,
+    ext::SafeTexture,
+    fs::FileSystem,
+    judge::JudgeStatus,
+    particle::Emitter,
+    profile::{self, Subsystem},
+    ui::Ui,
+};
 use anyhow::{Context, Result};
 use macroquad::prelude::*;
 use nalgebra::Rotation2;
 use sasa::AudioClip;
-use std::{cell::RefCell, collections::HashMap};
+use std::{cell::RefCell, collections::HashMap, ops::Range};
+
+/// Particles placed by a chart, emitted while the chart time is in `time_range`.
+pub struct ChartParticles {
+    pub time_range: Range<f64>,
+    pub emitter: Emitter,
+    /// Position in the coordinates of judge lines
+    pub position: Anim<Vec2>,
+    /// The line `position` is relative to
+    pub line: Option<usize>,
+    /// Emits `amount` particles once at the start, instead of emitting continuously
+    burst: bool,
+    amount: usize,
+    last_time: f64,
+    _texture: Option<SafeTexture>,
+}
+
+impl ChartParticles {
+    pub fn new(time_range: Range<f64>, mut emitter: Emitter, texture: Option<SafeTexture>, position: Anim<Vec2>, line: Option<usize>) -> Self {
+        let burst = emitter.config.one_shot;
+        emitter.config.emitting = false;
+        Self {
+            time_range,
+            amount: emitter.config.amount as usize,
+            emitter,
+            position,
+            line,
+            burst,
+            last_time: f64::NEG_INFINITY,
+            _texture: texture,
+        }
+    }
+
+    /// Emits and draws particles at `pos`, in screen coordinates. Particles follow the chart time
+    /// rather than the real time, and are cleared when the time goes back.
+    fn render(&mut self, time: f64, pos: Vec2) {
+        if time < self.last_time {
+            self.emitter.clear();
+            self.last_time = f64::NEG_INFINITY;
+        }
+        let active = self.time_range.contains(&time);
+        let burst = self.burst && active && self.last_time < self.time_range.start;
+        if !self.burst {
+            self.emitter.config.emitting = active;
+        }
+        // long jumps forward (like seeking) aren't simulated
+        let dt = (time - self.last_time).min(0.1) as f32;
+        self.last_time = time;
+        self.emitter.draw(pos, dt);
+        if burst {
+            self.emitter.emit(Vec2::ZERO, self.amount);
+        }
+    }
+}
 
 #[derive(Default)]
 pub struct ChartExtra {
     pub effects: Vec<Effect>,
     pub global_effects: Vec<Effect>,
+    pub particles: Vec<ChartParticles>,
     #[cfg(feature = "video")]
     pub videos: Vec<(super::Video, Option<super::VideoAttach>)>,
 }
@@ -131,11 +193,18 @@ impl Chart {
         for ((line, tr), rot) in self.lines.iter_mut().zip(trs).zip(rotations) {
             line.update(res, tr, rot);
         }
-        for effect in &mut self.extra.effects {
-            effect.update(res);
+        {
+            let _span = profile::span(Subsystem::Effects);
+            for effect in &mut self.extra.effects {
+                effect.update(res);
+            }
+        }
+        for particles in &mut self.extra.particles {
+            particles.position.set_time(res.time);
         }
         #[cfg(feature = "video")]
         for (video, _) in &mut self.extra.videos {
+            let _span = profile::span(Subsystem::Video);
             if let Err(err) = video.update(res.time) {
                 tracing::warn!("video error: {err:?}");
             }
@@ -145,6 +214,7 @@ impl Chart {
     pub fn render(&self, ui: &mut Ui, res: &mut Resource) {
         #[cfg(feature = "video")]
         for (video, attach) in &self.extra.videos {
+            let _span = profile::span(Subsystem::Video);
             if let Some(attach) = attach {
                 let line = &self.lines[attach.line];
                 let color = line.color.now_opt().unwrap_or(res.judge_line_color);
@@ -157,8 +227,27 @@ impl Chart {
             }
         }
         res.apply_model_of(&Matrix::identity().append_nonuniform_scaling(&Vector::new(if res.config.flip_x() { -1. } else { 1. }, -1.)), |res| {
+            // active effects limited to some lines, grouped by the lines they cover
+            let mut groups: Vec<(&[usize], Vec<&Effect>)> = Vec::new();
+            if !res.no_effect && res.chart_target.is_some() {
+                for effect in self.extra.effects.iter().filter(|it| it.is_active()) {
+                    let Some(lines) = &effect.lines else { continue };
+                    match groups.iter_mut().find(|it| it.0 == lines.as_slice()) {
+                        Some(group) => group.1.push(effect),
+                        None => groups.push((lines, vec![effect])),
+                    }
+                }
+            }
+            let mut drawn = vec![false; groups.len()];
             let mut guard = self.bpm_list.borrow_mut();
             for id in &self.order {
+                if let Some(index) = groups.iter().position(|it| it.0.contains(id)) {
+                    // the whole group is drawn at the place of its lowest line
+                    if !std::mem::replace(&mut drawn[index], true) {
+                        self.render_layer(ui, res, &mut guard, groups[index].0, &groups[index].1);
+                    }
+                    continue;
+                }
                 self.lines[*id].render(ui, res, &self.lines, &mut guard, &self.settings, *id);
             }
             drop(guard);
@@ -170,8 +259,9 @@ impl Chart {
                 }
             }
             if !res.no_effect {
+                let _span = profile::span(Subsystem::Effects);
                 let render = |res: &mut Resource| {
-                    for effect in &self.extra.effects {
+                    for effect in self.extra.effects.iter().filter(|it| it.lines.is_none()) {
                         effect.render(res);
                     }
                 };
@@ -183,4 +273,55 @@ impl Chart {
             }
         });
     }
+
+    /// Draws the particles placed by the chart, on top of the hit effects.
+    pub fn render_particles(&mut self, res: &Resource) {
+        for particles in &mut self.extra.particles {
+            let pos = particles.position.now();
+            let pt = Point::new(pos.x, pos.y / res.aspect_ratio);
+            let pt = match particles.line {
+                Some(line) => self.lines[line].now_transform(res, &self.lines).transform_point(&pt),
+                None => pt,
+            };
+            particles.render(res.time, vec2(if res.config.flip_x() { -pt.x } else { pt.x }, -pt.y));
+        }
+    }
+
+    /// Draws `lines` onto the effect layer, runs `effects` on it and composites the result back
+    /// onto the chart.
+    fn render_layer(&self, ui: &mut Ui, res: &mut Resource, bpm_list: &mut BpmList, lines: &[usize], effects: &[&Effect]) {
+        res.note_buffer.borrow_mut().draw_all();
+        unsafe { get_internal_gl() }.flush();
+        let target = res.chart_target.as_ref().unwrap();
+        let main = if res.config.sample_count > 1 { target.input() } else { target.output() };
+        let texture = target.output().texture;
+        let dim = (texture.width() as u32, texture.height() as u32);
+        let mut layer = match res.effect_layer.take() {
+            Some(layer) if layer.dim() == dim => layer,
+            _ => EffectLayer::new(dim),
+        };
+
+        unsafe { get_internal_gl() }.quad_gl.render_pass(Some(layer.output().render_pass));
+        clear_background(Color::new(0., 0., 0., 0.));
+        for id in self.order.iter().filter(|it| lines.contains(it)) {
+            self.lines[*id].render(ui, res, &self.lines, bpm_list, &self.settings, *id);
+        }
+        res.note_buffer.borrow_mut().draw_all();
+
+        let _span = profile::span(Subsystem::Effects);
+        let top = 1. / res.aspect_ratio;
+        let mut render = |_: &mut Resource| {
+            for effect in effects {
+                effect.render_on(&mut layer, top);
+            }
+            unsafe { get_internal_gl() }.quad_gl.render_pass(Some(main.render_pass));
+            layer.composite(top);
+        };
+        if res.config.flip_x() {
+            res.apply_model_of(&Matrix::identity().append_nonuniform_scaling(&Vector::new(-1., 1.)), render);
+        } else {
+            render(res);
+        }
+        res.effect_layer = Some(layer);
+    }
 }
@@ -1,12 +1,19 @@
-use super::{Anim, Resource, Tweenable};
-use crate::ext::{get_viewport, screen_aspect};
-use anyhow::{anyhow, bail, Result};
+use super::{copy_fbo, internal_id, Anim, MSRenderTarget, Resource, Tweenable};
+use crate::ext::{get_viewport, screen_aspect, SafeTexture};
+use anyhow::{anyhow, bail, Context, Result};
 use macroquad::prelude::*;
-use miniquad::UniformType;
+use miniquad::{BlendFactor, BlendState, BlendValue, Equation, PipelineParams, UniformType};
 use once_cell::sync::Lazy;
 use phf::phf_map;
 use regex::Regex;
-use std::{collections::HashSet, ops::Range};
+use serde::Deserialize;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 static SHADERS: phf::Map<&'static str, &'static str> = phf_map! {
     "chromatic" => include_str!("shaders/chromatic.glsl"),
@@ -69,21 +76,115 @@ impl<T: UniformValue + Tweenable> Uniform for (String, Anim<T>) {
     }
 }
 
-pub struct Effect {
-    time_range: Range<f64>,
-    t: f64,
+/// Render targets that effects read from and draw to in turn.
+pub trait EffectTarget {
+    fn swap(&mut self);
+    /// The image drawn so far, after a swap
+    fn old(&self) -> RenderTarget;
+    fn output(&self) -> RenderTarget;
+}
+
+impl EffectTarget for MSRenderTarget {
+    fn swap(&mut self) {
+        MSRenderTarget::swap(self)
+    }
+
+    fn old(&self) -> RenderTarget {
+        MSRenderTarget::old(self)
+    }
+
+    fn output(&self) -> RenderTarget {
+        MSRenderTarget::output(self)
+    }
+}
+
+/// A transparent layer that judge lines with effects of their own are drawn to, before being
+/// composited back onto the chart.
+pub struct EffectLayer {
+    dim: (u32, u32),
+    targets: [RenderTarget; 2],
+    composite: Material,
+}
+
+impl EffectLayer {
+    pub fn new(dim: (u32, u32)) -> Self {
+        let composite = load_material(
+            VERTEX_SHADER,
+            COMPOSITE_SHADER,
+            MaterialParams {
+                uniforms: vec![("UVScale".to_owned(), UniformType::Float2)],
+                textures: vec!["layerTexture".to_owned()],
+                // the layer is drawn onto a transparent background, so its colors are premultiplied
+                pipeline_params: PipelineParams {
+                    color_blend: Some(BlendState::new(Equation::Add, BlendFactor::One, BlendFactor::OneMinusValue(BlendValue::SourceAlpha))),
+                    ..Default::default()
+                },
+            },
+        )
+        .unwrap();
+        Self {
+            dim,
+            targets: [render_target(dim.0, dim.1), render_target(dim.0, dim.1)],
+            composite,
+        }
+    }
+
+    pub fn dim(&self) -> (u32, u32) {
+        self.dim
+    }
+
+    /// Draws the layer onto the current render pass.
+    pub fn composite(&self, top: f32) {
+        unsafe { get_internal_gl() }.flush();
+        let vp = get_viewport();
+        self.composite.set_texture("layerTexture", self.output().texture);
+        self.composite
+            .set_uniform("UVScale", vec2(vp.2 as _, vp.3 as _) / vec2(self.dim.0 as _, self.dim.1 as _));
+        gl_use_material(self.composite);
+        draw_rectangle(-1., -top, 2., top * 2., WHITE);
+        gl_use_default_material();
+    }
+}
+
+impl EffectTarget for EffectLayer {
+    fn swap(&mut self) {
+        self.targets.swap(0, 1);
+    }
+
+    fn old(&self) -> RenderTarget {
+        self.targets[1]
+    }
+
+    fn output(&self) -> RenderTarget {
+        self.targets[0]
+    }
+}
+
+impl Drop for EffectLayer {
+    fn drop(&mut self) {
+        for target in &self.targets {
+            target.delete();
+        }
+        self.composite.delete();
+    }
+}
+
+/// One shader of an [`Effect`].
+struct Pass {
     material: Material,
     defaults: Vec<Box<dyn Uniform>>,
     uniforms: Vec<Box<dyn Uniform>>,
-    pub global: bool,
+    /// Whether the shader reads `sourceTexture`, the image from before the first pass
+    uses_source: bool,
 }
 
-impl Effect {
-    pub fn get_preset(name: &str) -> Option<&'static str> {
-        SHADERS.get(name).copied()
-    }
-
-    pub fn new(time_range: Range<f64>, shader: &str, uniforms: Vec<Box<dyn Uniform>>, global: bool) -> Result<Self> {
+impl Pass {
+    fn new(shader: &str, uniforms: Vec<Box<dyn Uniform>>, textures: &[String]) -> Result<Self> {
+        // charts that play fine on some devices keep loading, while anything GL can't get past
+        // still fails when the shader is compiled below
+        if let Err(err) = validate_shader(shader, &uniforms.iter().map(|it| it.uniform_pair()).collect::<Vec<_>>(), textures) {
+            tracing::warn!("{err:?}");
+        }
         static DEF_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"uniform\s+(\w+)\s+(\w+);\s+//\s+%([^%]+)%").unwrap());
         let defaults = DEF_REGEX
             .captures_iter(shader)
@@ -91,21 +192,25 @@ impl Effect {
                 let type_name = caps.get(1).unwrap().as_str();
                 let name = caps.get(2).unwrap().as_str().to_owned();
                 let value = caps.get(3).unwrap().as_str();
-                Ok(match type_name {
-                    "float" => Box::new((name, value.parse::<f32>()?)),
-                    "vec2" => Box::new((name, {
-                        let (x, y) = value.split_once(',').ok_or_else(|| anyhow!("Expected x,y"))?;
-                        vec2(x.trim().parse()?, y.trim().parse()?)
-                    })),
-                    "vec4" => Box::new((name, {
-                        let values: Vec<_> = value.split(',').map(|it| it.trim()).collect();
-                        if values.len() != 4 {
-                            bail!("Expected r,g,b,a");
-                        }
-                        Color::new(values[0].parse()?, values[1].parse()?, values[2].parse()?, values[3].parse()?)
-                    })),
-                    _ => bail!("Unknown type: {type_name}"),
-                })
+                let context = format!("Invalid default value of uniform `{name}`: {value}");
+                (|| -> Result<Box<dyn Uniform>> {
+                    Ok(match type_name {
+                        "float" => Box::new((name, value.parse::<f32>()?)),
+                        "vec2" => Box::new((name, {
+                            let (x, y) = value.split_once(',').ok_or_else(|| anyhow!("Expected x,y"))?;
+                            vec2(x.trim().parse()?, y.trim().parse()?)
+                        })),
+                        "vec4" => Box::new((name, {
+                            let values: Vec<_> = value.split(',').map(|it| it.trim()).collect();
+                            if values.len() != 4 {
+                                bail!("Expected r,g,b,a");
+                            }
+                            Color::new(values[0].parse()?, values[1].parse()?, values[2].parse()?, values[3].parse()?)
+                        })),
+                        _ => bail!("Unknown type: {type_name}"),
+                    })
+                })()
+                .context(context)
             })
             .collect::<Result<Vec<Box<dyn Uniform>>>>()?;
         let mut ocurred_uniforms = HashSet::new();
@@ -124,70 +229,409 @@ impl Effect {
         for u in &uniforms {
             add_uniform(u.uniform_pair());
         }
+        let mut texture_names = vec!["screenTexture".to_owned()];
+        let uses_source = strip_comments(shader).contains("sourceTexture");
+        if uses_source {
+            texture_names.push("sourceTexture".to_owned());
+        }
+        texture_names.extend(textures.iter().cloned());
         Ok(Self {
-            time_range,
-            t: f64::NEG_INFINITY,
-            defaults,
             material: load_material(
                 VERTEX_SHADER,
                 shader,
                 MaterialParams {
                     uniforms: new_uniforms,
-                    textures: vec!["screenTexture".to_owned()],
+                    textures: texture_names,
                     ..Default::default()
                 },
-            )?,
+            )
+            .context("Failed to compile shader")?,
+            defaults,
             uniforms,
+            uses_source,
+        })
+    }
+}
+
+pub struct Effect {
+    time_range: Range<f64>,
+    t: f64,
+    passes: Vec<Pass>,
+    /// Extra textures, by the name of their `sampler2D` uniform
+    textures: Vec<(String, SafeTexture)>,
+    /// Copy of the image from before the first pass, for chains that read `sourceTexture`
+    source: RefCell<Option<RenderTarget>>,
+    pub global: bool,
+    /// Judge lines the effect is limited to, or `None` for the whole chart
+    pub lines: Option<Vec<usize>>,
+}
+
+impl Effect {
+    pub fn get_preset(name: &str) -> Option<&'static str> {
+        SHADERS.get(name).copied()
+    }
+
+    pub fn new(time_range: Range<f64>, shader: &str, uniforms: Vec<Box<dyn Uniform>>, global: bool) -> Result<Self> {
+        Self::chain(time_range, vec![(shader, uniforms)], Vec::new(), global)
+    }
+
+    /// Creates an effect of several passes, each reading the output of the previous one through
+    /// `screenTexture`. Every pass can sample `textures` as well.
+    pub fn chain(
+        time_range: Range<f64>,
+        passes: Vec<(&str, Vec<Box<dyn Uniform>>)>,
+        textures: Vec<(String, SafeTexture)>,
+        global: bool,
+    ) -> Result<Self> {
+        let names: Vec<_> = textures.iter().map(|it| it.0.clone()).collect();
+        let passes = passes
+            .into_iter()
+            .enumerate()
+            .map(|(index, (shader, uniforms))| Pass::new(shader, uniforms, &names).with_context(|| format!("In pass #{}", index + 1)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            time_range,
+            t: f64::NEG_INFINITY,
+            passes,
+            textures,
+            source: RefCell::default(),
             global,
+            lines: None,
         })
     }
 
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.time_range.contains(&self.t)
+    }
+
     pub fn update(&mut self, res: &Resource) {
         let t = res.time;
         self.t = t;
         if self.time_range.contains(&t) {
-            for uniform in &mut self.uniforms {
+            for uniform in self.passes.iter_mut().flat_map(|it| it.uniforms.iter_mut()) {
                 uniform.set_time(t);
             }
         }
     }
 
     pub fn render(&self, res: &mut Resource) {
-        if !self.time_range.contains(&self.t) {
+        let top = 1. / if self.global { screen_aspect() } else { res.aspect_ratio };
+        self.render_on(res.chart_target.as_mut().unwrap(), top);
+    }
+
+    /// Runs the passes of the effect on `target`.
+    pub fn render_on(&self, target: &mut impl EffectTarget, top: f32) {
+        if !self.is_active() {
             return;
         }
-        let mut gl = unsafe { get_internal_gl() };
-        gl.flush();
+        unsafe { get_internal_gl() }.flush();
+        let source = if self.passes.len() > 1 && self.passes.iter().any(|it| it.uses_source) {
+            let output = target.output();
+            let (w, h) = (output.texture.width() as u32, output.texture.height() as u32);
+            let mut source = self.source.borrow_mut();
+            let copy = match *source {
+                Some(it) if (it.texture.width() as u32, it.texture.height() as u32) == (w, h) => it,
+                _ => {
+                    if let Some(old) = source.take() {
+                        old.delete();
+                    }
+                    *source.insert(render_target(w, h))
+                }
+            };
+            copy_fbo(internal_id(output), internal_id(copy), (w, h));
+            Some(copy.texture)
+        } else {
+            None
+        };
+
+        for pass in &self.passes {
+            let mut gl = unsafe { get_internal_gl() };
+            gl.flush();
+
+            let material = &pass.material;
+            for def in &pass.defaults {
+                def.apply(material);
+            }
+            for uniform in &pass.uniforms {
+                uniform.apply(material);
+            }
+            material.set_uniform("time", self.t as f32);
+            target.swap();
+            let tex = target.old().texture;
+            material.set_texture("screenTexture", tex);
+            if pass.uses_source {
+                material.set_texture("sourceTexture", source.unwrap_or(tex));
+            }
+            for (name, texture) in &self.textures {
+                material.set_texture(name, **texture);
+            }
+            let screen_dim = vec2(tex.width(), tex.height());
+            material.set_uniform("screenSize", screen_dim);
+            gl.quad_gl.render_pass(Some(target.output().render_pass));
+
+            let vp = get_viewport();
+            material.set_uniform("UVScale", vec2(vp.2 as _, vp.3 as _) / screen_dim);
+
+            gl_use_material(*material);
+            draw_rectangle(-1., -top, 2., top * 2., WHITE);
+            gl_use_default_material();
+        }
+    }
+}
 
-        for def in &self.defaults {
-            def.apply(&self.material);
+impl Drop for Effect {
+    fn drop(&mut self) {
+        for pass in &self.passes {
+            pass.material.delete();
         }
-        for uniform in &self.uniforms {
-            uniform.apply(&self.material);
+        if let Some(source) = self.source.get_mut() {
+            source.delete();
         }
-        self.material.set_uniform("time", self.t as f32);
-        let target = res.chart_target.as_mut().unwrap();
-        target.swap();
-        let tex = target.old().texture;
-        self.material.set_texture("screenTexture", tex);
-        let screen_dim = vec2(tex.width(), tex.height());
-        self.material.set_uniform("screenSize", screen_dim);
-        gl.quad_gl.render_pass(Some(target.output().render_pass));
+    }
+}
 
-        let vp = get_viewport();
-        self.material.set_uniform("UVScale", vec2(vp.2 as _, vp.3 as _) / screen_dim);
+/// Replaces comments with spaces, keeping line breaks so that line numbers stay the same.
+fn strip_comments(source: &str) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek().copied()) {
+            ('/', Some('/')) => {
+                while chars.peek().is_some_and(|it| *it != '\n') {
+                    chars.next();
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        result.push('\n');
+                    }
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+    result
+}
 
-        gl_use_material(self.material);
-        let top = 1. / if self.global { screen_aspect() } else { res.aspect_ratio };
-        draw_rectangle(-1., -top, 2., top * 2., WHITE);
-        gl_use_default_material();
+fn glsl_type(ty: UniformType) -> &'static str {
+    match ty {
+        UniformType::Float1 => "float",
+        UniformType::Float2 => "vec2",
+        UniformType::Float3 => "vec3",
+        UniformType::Float4 => "vec4",
+        UniformType::Int1 => "int",
+        UniformType::Int2 => "ivec2",
+        UniformType::Int3 => "ivec3",
+        UniformType::Int4 => "ivec4",
+        UniformType::Mat4 => "mat4",
     }
 }
 
-impl Drop for Effect {
-    fn drop(&mut self) {
-        self.material.delete();
+/// Checks a fragment shader for mistakes that would otherwise only show up as a GL error at load,
+/// or only on some devices, and checks the values in `vars` and the extra `textures` against the
+/// uniforms it declares.
+///
+/// Effects in charts only warn about the problems found, but effect packages are rejected for them
+/// when registered.
+pub fn validate_shader(shader: &str, vars: &[(String, UniformType)], textures: &[String]) -> Result<()> {
+    static MAIN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bvoid\s+main\s*\(\s*(void\s*)?\)").unwrap());
+    static PRECISION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bprecision\s+(lowp|mediump|highp)\s+float\s*;").unwrap());
+    static UNIFORM_REGEX: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\buniform\s+(?:(?:lowp|mediump|highp)\s+)?(\w+)\s+(\w+)\s*(?:\[[^\]]*\]\s*)?;").unwrap());
+
+    let source = strip_comments(shader);
+    let line_of = |offset: usize| source[..offset].matches('\n').count() + 1;
+    let mut errors = Vec::new();
+
+    let mut brackets = Vec::new();
+    'check: for (index, line) in source.lines().enumerate() {
+        for c in line.chars() {
+            let open = match c {
+                '(' | '[' | '{' => {
+                    brackets.push((c, index + 1));
+                    continue;
+                }
+                ')' => '(',
+                ']' => '[',
+                '}' => '{',
+                _ => continue,
+            };
+            match brackets.pop() {
+                Some((it, _)) if it == open => {}
+                Some((it, line)) => {
+                    errors.push(format!("line {}: `{c}` does not match the `{it}` on line {line}", index + 1));
+                    // everything after a mismatch is likely to be reported as well
+                    brackets.clear();
+                    break 'check;
+                }
+                None => errors.push(format!("line {}: `{c}` was never opened", index + 1)),
+            }
+        }
+    }
+    for (c, line) in brackets {
+        errors.push(format!("line {line}: `{c}` is never closed"));
+    }
+    if !MAIN_REGEX.is_match(&source) {
+        errors.push("no `void main()` function".to_owned());
+    }
+    if !PRECISION_REGEX.is_match(&source) {
+        errors.push("no default float precision such as `precision mediump float;`, which mobile devices require".to_owned());
+    }
+
+    let mut declared = HashMap::new();
+    for caps in UNIFORM_REGEX.captures_iter(&source) {
+        let name = caps.get(2).unwrap();
+        declared.insert(name.as_str(), (caps.get(1).unwrap().as_str(), line_of(name.start())));
+    }
+    let builtins = [
+        ("time", "float"),
+        ("screenSize", "vec2"),
+        ("UVScale", "vec2"),
+        ("screenTexture", "sampler2D"),
+        ("sourceTexture", "sampler2D"),
+    ];
+    for (name, ty) in builtins {
+        if let Some((found, line)) = declared.get(name).filter(|it| it.0 != ty) {
+            errors.push(format!("line {line}: uniform `{name}` is provided by the game as `{ty}`, but declared as `{found}`"));
+        }
+    }
+    for (name, ty) in vars {
+        let ty = glsl_type(*ty);
+        match declared.get(name.as_str()) {
+            Some((found, line)) if *found != ty => {
+                errors.push(format!("line {line}: uniform `{name}` is given a `{ty}` value, but declared as `{found}`"));
+            }
+            None => errors.push(format!("`{name}` is given a value, but the shader has no such uniform")),
+            _ => {}
+        }
     }
+    for name in textures {
+        match declared.get(name.as_str()) {
+            Some((found, line)) if *found != "sampler2D" => {
+                errors.push(format!("line {line}: uniform `{name}` is given a texture, but declared as `{found}`"));
+            }
+            None => errors.push(format!("`{name}` is given a texture, but the shader has no such uniform")),
+            _ => {}
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        bail!("Invalid shader:\n{}", errors.join("\n"))
+    }
+}
+
+/// A value of a uniform given by an [`EffectPackage`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum EffectValue {
+    Float(f32),
+    Vec2([f32; 2]),
+    /// RGBA, from 0 to 255
+    Color([u8; 4]),
+}
+
+impl EffectValue {
+    pub fn uniform(&self, name: String) -> Box<dyn Uniform> {
+        match *self {
+            Self::Float(value) => Box::new((name, value)),
+            Self::Vec2([x, y]) => Box::new((name, vec2(x, y))),
+            Self::Color([r, g, b, a]) => Box::new((name, Color::from_rgba(r, g, b, a))),
+        }
+    }
+}
+
+/// A reusable effect: a shader together with default values of its uniforms. Registered
+/// packages can be used by any chart, just like preset shaders.
+#[derive(Clone, Debug)]
+pub struct EffectPackage {
+    pub name: String,
+    pub author: String,
+    pub description: String,
+    pub shader: String,
+    /// Defaults of uniforms, overridden by the `vars` of the effect in the chart
+    pub vars: HashMap<String, EffectValue>,
+}
+
+/// `effect.yml` in the directory of a package.
+#[derive(Deserialize)]
+struct EffectManifest {
+    name: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    description: String,
+    /// Path of the shader, relative to the manifest
+    shader: String,
+    #[serde(default)]
+    vars: HashMap<String, EffectValue>,
+}
+
+impl EffectPackage {
+    /// Loads a package from a directory containing `effect.yml` and the shader it refers to.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let manifest: EffectManifest = serde_yaml::from_str(&std::fs::read_to_string(dir.join("effect.yml"))?)?;
+        let shader = std::fs::read_to_string(dir.join(&manifest.shader)).with_context(|| format!("Cannot read shader {}", manifest.shader))?;
+        Ok(Self {
+            name: manifest.name,
+            author: manifest.author,
+            description: manifest.description,
+            shader,
+            vars: manifest.vars,
+        })
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let vars: Vec<_> = self.vars.iter().map(|(name, value)| value.uniform(name.clone()).uniform_pair()).collect();
+        validate_shader(&self.shader, &vars, &[])
+    }
+}
+
+static EFFECT_LIBRARY: Lazy<Mutex<HashMap<String, Arc<EffectPackage>>>> = Lazy::new(Mutex::default);
+
+/// Validates and registers `package`, replacing any package of the same name. Preset shaders
+/// can't be replaced.
+pub fn register_effect_package(package: EffectPackage) -> Result<()> {
+    if SHADERS.contains_key(&package.name) {
+        bail!("`{}` is the name of a preset shader", package.name);
+    }
+    package.validate()?;
+    EFFECT_LIBRARY.lock().unwrap().insert(package.name.clone(), Arc::new(package));
+    Ok(())
+}
+
+pub fn effect_package(name: &str) -> Option<Arc<EffectPackage>> {
+    EFFECT_LIBRARY.lock().unwrap().get(name).cloned()
+}
+
+pub fn effect_packages() -> Vec<Arc<EffectPackage>> {
+    let mut packages: Vec<_> = EFFECT_LIBRARY.lock().unwrap().values().cloned().collect();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    packages
+}
+
+/// Registers every package found in the subdirectories of `dir`, returning the ones that failed.
+pub fn load_effect_packages(dir: &Path) -> Result<Vec<(PathBuf, anyhow::Error)>> {
+    let mut failed = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if let Err(err) = EffectPackage::load(&path).and_then(register_effect_package) {
+            failed.push((path, err));
+        }
+    }
+    Ok(failed)
 }
 
 const VERTEX_SHADER: &str = r#"#version 100
@@ -205,3 +649,13 @@ void main() {
     gl_Position = Projection * Model * vec4(position, 1);
     uv = (texcoord - vec2(0.5)) * UVScale + vec2(0.5);
 }"#;
+
+const COMPOSITE_SHADER: &str = r#"#version 100
+precision mediump float;
+
+varying lowp vec2 uv;
+uniform sampler2D layerTexture;
+
+void main() {
+    gl_FragColor = texture2D(layerTexture, uv);
+}"#;
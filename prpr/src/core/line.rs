@@ -10,7 +10,7 @@ use nalgebra::Rotation2;
 use serde::Deserialize;
 use std::cell::RefCell;
 
-#[derive(Clone, Copy, Deserialize)]
+#[derive(Clone, Copy, Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum UIElement {
@@ -208,6 +208,11 @@ impl JudgeLine {
         });
     }
 
+    /// Indices of the lines this line is attached to, starting from its parent
+    pub fn ancestors<'a>(&self, lines: &'a [JudgeLine]) -> impl Iterator<Item = usize> + 'a {
+        std::iter::successors(self.parent, |it| lines[*it].parent)
+    }
+
     pub fn fetch_rot(&self, lines: &[JudgeLine]) -> f32 {
         let mut rot = self.object.rotation.now();
         if self.rot_with_parent {
@@ -10,6 +10,17 @@ const HOLD_PARTICLE_INTERVAL: f64 = 0.15;
 const FADEOUT_TIME: f64 = 0.16;
 const BAD_TIME: f64 = 0.5;
 
+/// Outline thickness at `note_outline = 1`, relative to the note width.
+const OUTLINE_WIDTH: f32 = 0.08;
+/// Half size of shape markers, relative to the note width.
+const MARKER_SIZE: f32 = 0.14;
+
+// Each note order is split into layers in the note buffer, so that outlines stay below all notes
+// of the same order and markers above them.
+const LAYER_OUTLINE: i8 = 0;
+const LAYER_NOTE: i8 = 1;
+const LAYER_MARKER: i8 = 2;
+
 #[derive(Clone, Debug)]
 pub enum NoteKind {
     Click,
@@ -27,6 +38,10 @@ impl NoteKind {
             Self::Flick => 3,
         }
     }
+
+    fn layer(&self, layer: i8) -> i8 {
+        self.order() * 3 + layer
+    }
 }
 
 pub struct Note {
@@ -128,6 +143,55 @@ fn draw_center(res: &Resource, tex: Texture2D, order: i8, scale: f32, color: Col
     );
 }
 
+/// Draws a dark outline around the note occupying `rect`, if enabled.
+fn draw_outline(res: &Resource, kind: &NoteKind, rect: Rect, scale: f32, color: Color) {
+    let t = res.config.note_outline * OUTLINE_WIDTH * scale;
+    if t <= 0. {
+        return;
+    }
+    draw_tex(
+        res,
+        *res.note_markers.blank,
+        kind.layer(LAYER_OUTLINE),
+        rect.x - t,
+        rect.y - t,
+        Color::new(0., 0., 0., color.a * 0.85),
+        DrawTextureParams {
+            dest_size: Some(vec2(rect.w + t * 2., rect.h + t * 2.)),
+            ..Default::default()
+        },
+        false,
+    );
+}
+
+/// Draws the shape telling the kind of the note apart at `center`, if enabled.
+fn draw_marker(res: &Resource, kind: &NoteKind, center: Vec2, scale: f32, color: Color) {
+    if !res.config.note_shapes {
+        return;
+    }
+    let markers = &res.note_markers;
+    let tex = match kind {
+        NoteKind::Click => &markers.click,
+        NoteKind::Hold { .. } => &markers.hold,
+        NoteKind::Flick => &markers.flick,
+        NoteKind::Drag => &markers.drag,
+    };
+    let r = MARKER_SIZE * scale;
+    draw_tex(
+        res,
+        **tex,
+        kind.layer(LAYER_MARKER),
+        center.x - r,
+        center.y - r,
+        Color { a: color.a, ..WHITE },
+        DrawTextureParams {
+            dest_size: Some(vec2(r * 2., r * 2.)),
+            ..Default::default()
+        },
+        false,
+    );
+}
+
 impl Note {
     pub fn rotation(&self, line: &JudgeLine) -> f32 {
         line.object.rotation.now() + if self.above { 0. } else { 180. }
@@ -143,13 +207,7 @@ impl Note {
         if let Some(color) = if let JudgeStatus::Hold(perfect, at, ..) = &mut self.judge {
             if res.time > *at {
                 *at += HOLD_PARTICLE_INTERVAL / res.config.speed as f64;
-                Some(self.fx_color.unwrap_or_else(|| {
-                    if *perfect {
-                        res.res_pack.info.fx_perfect()
-                    } else {
-                        res.res_pack.info.fx_good()
-                    }
-                }))
+                Some(self.fx_color.unwrap_or_else(|| if *perfect { res.fx_perfect() } else { res.fx_good() }))
             } else {
                 None
             }
@@ -158,7 +216,7 @@ impl Note {
         } {
             self.init_ctrl_obj(ctrl_obj, line_height);
             res.with_model(parent_tr * self.now_transform(res, ctrl_obj, 0., 0.), |res| {
-                res.emit_at_origin(parent_rot + if self.above { 0. } else { 180. }, color)
+                res.emit_at_origin(parent_rot + if self.above { 0. } else { 180. }, color, &self.kind)
             });
         }
     }
@@ -239,7 +297,7 @@ impl Note {
         {
             return;
         }
-        let order = self.kind.order();
+        let order = self.kind.layer(LAYER_NOTE);
         let style = if res.config.double_hint && self.multiple_hint {
             &res.res_pack.note_style_mh
         } else {
@@ -260,12 +318,15 @@ impl Note {
             }
             color.a *= mod_alpha as f32;
             res.with_model(self.now_transform(res, ctrl_obj, base as f32, config.incline_sin), |res| {
+                let hf = vec2(scale, tex.height() * scale / tex.width());
+                draw_outline(res, &self.kind, Rect::new(-hf.x, -hf.y, hf.x * 2., hf.y * 2.), scale, color);
                 draw_center(res, tex, order, scale, color);
+                draw_marker(res, &self.kind, Vec2::ZERO, scale, color);
             });
         };
         match self.kind {
             NoteKind::Click => {
-                draw(res, *style.click);
+                draw(res, style.click.at(res.time));
             }
             NoteKind::Hold { end_time, end_height } => {
                 res.with_model(self.now_transform(res, ctrl_obj, 0., 0.), |res| {
@@ -289,6 +350,19 @@ impl Note {
                     let top = (end_height - line_height) as f32;
                     let tex = &style.hold;
                     let ratio = style.hold_ratio();
+                    let show_head = res.time < self.time || res.res_pack.info.hold_keep_head;
+                    let compact = res.res_pack.info.hold_compact;
+                    let head = style.hold_head_rect();
+                    let head_hf = head.h / head.w * scale * ratio;
+                    let tail = style.hold_tail_rect();
+                    let tail_hf = tail.h / tail.w * scale * ratio;
+                    let outline_bottom = if show_head {
+                        bottom - head_hf * if compact { 1. } else { 2. }
+                    } else {
+                        bottom
+                    };
+                    let outline_top = top + tail_hf * if compact { 1. } else { 2. };
+                    draw_outline(res, &self.kind, Rect::new(-scale, outline_bottom, scale * 2., outline_top - outline_bottom), scale, color);
                     // body
                     // TODO (end_height - height) is not always total height
                     draw_tex(
@@ -319,7 +393,7 @@ impl Note {
                         false,
                     );
                     // head
-                    if res.time < self.time || res.res_pack.info.hold_keep_head {
+                    if show_head {
                         let r = style.hold_head_rect();
                         let hf = vec2(scale, r.h / r.w * scale * ratio);
                         draw_tex(
@@ -336,6 +410,9 @@ impl Note {
                             },
                             false,
                         );
+                        if res.time < self.time {
+                            draw_marker(res, &self.kind, vec2(0., bottom - if compact { 0. } else { head_hf }), scale, color);
+                        }
                     }
                     // tail
                     let r = style.hold_tail_rect();
@@ -357,10 +434,10 @@ impl Note {
                 });
             }
             NoteKind::Flick => {
-                draw(res, *style.flick);
+                draw(res, style.flick.at(res.time));
             }
             NoteKind::Drag => {
-                draw(res, *style.drag);
+                draw(res, style.drag.at(res.time));
             }
         }
     }
@@ -387,7 +464,7 @@ impl BadNote {
                     NoteKind::Flick => *style.flick,
                     _ => unreachable!(),
                 },
-                self.kind.order(),
+                self.kind.layer(LAYER_NOTE),
                 res.note_width,
                 Color::new(0.423529, 0.262745, 0.262745, ((self.time - res.time).max(-1.) / BAD_TIME + 1.) as f32),
             );
@@ -1,12 +1,19 @@
-use super::{MSRenderTarget, Matrix, Point, NOTE_WIDTH_RATIO_BASE};
+use super::{EffectLayer, MSRenderTarget, Matrix, NoteKind, Point, NOTE_WIDTH_RATIO_BASE, PACK_FONT};
 use crate::{
     config::Config,
-    ext::{create_audio_manger, nalgebra_to_glm, SafeTexture},
+    ext::{audio_buffer_latency, create_audio_manger, nalgebra_to_glm, spawn_task, SafeTexture},
     fs::FileSystem,
     info::ChartInfo,
-    particle::{AtlasConfig, ColorCurve, Emitter, EmitterConfig},
+    particle::{AtlasConfig, ColorCurve, Emitter, EmitterConfig, ParticlePreset},
+    profile::{self, Subsystem},
+    quality::Downgrade,
+    stretch,
+    task::Task,
+    ui::TextPainter,
 };
 use anyhow::{bail, Context, Result};
+use glyph_brush::ab_glyph::FontArc;
+use image::DynamicImage;
 use macroquad::prelude::*;
 use miniquad::{
     gl::{GLuint, GL_LINEAR},
@@ -17,7 +24,7 @@ use serde::Deserialize;
 use std::{
     cell::RefCell,
     collections::{BTreeMap, HashMap},
-    ops::DerefMut,
+    ops::{Deref, DerefMut},
     path::Path,
     sync::atomic::AtomicU32,
 };
@@ -51,6 +58,70 @@ fn default_tinted() -> bool {
     true
 }
 
+#[inline]
+fn default_version() -> u32 {
+    1
+}
+
+/// Values that can be set for each note kind separately.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PerKind<T> {
+    pub click: Option<T>,
+    pub drag: Option<T>,
+    pub flick: Option<T>,
+    pub hold: Option<T>,
+}
+
+impl<T> Default for PerKind<T> {
+    fn default() -> Self {
+        Self {
+            click: None,
+            drag: None,
+            flick: None,
+            hold: None,
+        }
+    }
+}
+
+impl<T> PerKind<T> {
+    pub fn get(&self, kind: &NoteKind) -> Option<&T> {
+        match kind {
+            NoteKind::Click => self.click.as_ref(),
+            NoteKind::Drag => self.drag.as_ref(),
+            NoteKind::Flick => self.flick.as_ref(),
+            NoteKind::Hold { .. } => self.hold.as_ref(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &T)> {
+        [("click", &self.click), ("drag", &self.drag), ("flick", &self.flick), ("hold", &self.hold)]
+            .into_iter()
+            .filter_map(|(name, it)| Some((name, it.as_ref()?)))
+    }
+}
+
+/// Note textures that are a horizontal strip of frames.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct NoteAnimation {
+    pub frames: u32,
+    pub fps: f32,
+}
+
+/// Changes to the particles emitted along with hit effects. Sizes and velocities are relative
+/// to the defaults. Packs with a [`ParticlePreset`] of their own ignore these.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ParticleOverrides {
+    pub count: Option<usize>,
+    pub lifetime: Option<f32>,
+    pub size: Option<f32>,
+    pub size_randomness: Option<f32>,
+    pub velocity: Option<f32>,
+    pub velocity_randomness: Option<f32>,
+    pub acceleration: Option<f32>,
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -88,6 +159,18 @@ pub struct ResPackInfo {
 
     #[serde(default)]
     pub description: String,
+
+    /// Version of the pack format. Version 2 adds the fields below, and more optional files.
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// Animations of click, drag and flick notes
+    #[serde(default)]
+    pub animations: PerKind<NoteAnimation>,
+    /// Grids of the hit effects of each note kind, for those that differ from `hit_fx`
+    #[serde(default)]
+    pub hit_fx_grids: PerKind<(u32, u32)>,
+    #[serde(default)]
+    pub particles: ParticleOverrides,
 }
 
 fn parse_color_guess_alpha(c: u32) -> Color {
@@ -112,6 +195,22 @@ impl ResPackInfo {
         if !(1..=10240).contains(&self.hit_fx.0.saturating_mul(self.hit_fx.1)) {
             bail!("Invalid hit_fx");
         }
+        if !(1..=ResourcePack::MAX_VERSION).contains(&self.version) {
+            bail!("Unsupported version {}", self.version);
+        }
+        for (kind, grid) in self.hit_fx_grids.iter() {
+            if !(1..=10240).contains(&grid.0.saturating_mul(grid.1)) {
+                bail!("Invalid hit_fx grid of {kind}");
+            }
+        }
+        if self.animations.hold.is_some() {
+            bail!("Hold notes can't be animated");
+        }
+        for (kind, animation) in self.animations.iter() {
+            if animation.frames == 0 || animation.frames > 256 || !(animation.fps > 0.) {
+                bail!("Invalid animation of {kind}");
+            }
+        }
         Ok(())
     }
     pub fn color_perfect(&self) -> Color {
@@ -139,11 +238,111 @@ impl ResPackInfo {
     }
 }
 
-pub struct NoteStyle {
+/// Textures drawn over notes by the accessibility options, independent of the resource pack.
+pub struct NoteMarkers {
+    /// Plain white, for outlines
+    pub blank: SafeTexture,
     pub click: SafeTexture,
-    pub hold: SafeTexture,
-    pub flick: SafeTexture,
     pub drag: SafeTexture,
+    pub flick: SafeTexture,
+    pub hold: SafeTexture,
+}
+
+impl NoteMarkers {
+    const SIZE: u16 = 64;
+    const SAMPLES: u16 = 4;
+
+    /// Renders a white shape with a dark border. `inside` tells whether a point in `-1..1` (y
+    /// pointing up) is inside the shape.
+    fn shape(inside: impl Fn(f32, f32) -> bool) -> SafeTexture {
+        let (size, samples) = (Self::SIZE, Self::SAMPLES);
+        let mut image = Image::gen_image_color(size, size, Color::new(0., 0., 0., 0.));
+        for y in 0..size {
+            for x in 0..size {
+                let (mut fill, mut cover) = (0., 0.);
+                for sy in 0..samples {
+                    for sx in 0..samples {
+                        let px = (x as f32 + (sx as f32 + 0.5) / samples as f32) / size as f32 * 2. - 1.;
+                        let py = 1. - (y as f32 + (sy as f32 + 0.5) / samples as f32) / size as f32 * 2.;
+                        if inside(px, py) {
+                            cover += 1.;
+                            if inside(px / 0.7, py / 0.7) {
+                                fill += 1.;
+                            }
+                        }
+                    }
+                }
+                if cover > 0. {
+                    let v = fill / cover;
+                    image.set_pixel(x as _, y as _, Color::new(v, v, v, cover / (samples * samples) as f32));
+                }
+            }
+        }
+        Texture2D::from_image(&image).into()
+    }
+
+    fn new() -> Self {
+        Self {
+            blank: Texture2D::from_rgba8(1, 1, &[255; 4]).into(),
+            click: Self::shape(|x, y| x * x + y * y <= 0.81),
+            drag: Self::shape(|x, y| x.abs() + y.abs() <= 0.9),
+            flick: Self::shape(|x, y| y >= -0.8 && x.abs() <= (0.9 - y) * 0.53),
+            hold: Self::shape(|x, y| x.abs() <= 0.75 && y.abs() <= 0.75),
+        }
+    }
+}
+
+/// A note texture, which may be animated. Dereferences to its first frame.
+#[derive(Clone)]
+pub struct NoteTexture {
+    frames: Vec<SafeTexture>,
+    fps: f32,
+}
+
+impl NoteTexture {
+    fn new(image: DynamicImage, animation: Option<NoteAnimation>) -> Result<Self> {
+        let Some(animation) = animation.filter(|it| it.frames > 1) else {
+            return Ok(Self {
+                frames: vec![SafeTexture::from(image).with_filter(GL_LINEAR)],
+                fps: 0.,
+            });
+        };
+        let width = image.width() / animation.frames;
+        if width == 0 || width * animation.frames != image.width() {
+            bail!("Width {} can't be split into {} frames", image.width(), animation.frames);
+        }
+        Ok(Self {
+            frames: (0..animation.frames)
+                .map(|index| SafeTexture::from(image.crop_imm(index * width, 0, width, image.height())).with_filter(GL_LINEAR))
+                .collect(),
+            fps: animation.fps,
+        })
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// The frame shown at `time`, in seconds.
+    pub fn at(&self, time: f64) -> Texture2D {
+        let index = (time.max(0.) * self.fps as f64) as usize % self.frames.len();
+        *self.frames[index]
+    }
+}
+
+impl Deref for NoteTexture {
+    type Target = Texture2D;
+
+    fn deref(&self) -> &Self::Target {
+        &self.frames[0]
+    }
+}
+
+pub struct NoteStyle {
+    pub click: NoteTexture,
+    pub hold: SafeTexture,
+    pub flick: NoteTexture,
+    pub drag: NoteTexture,
     pub hold_body: Option<SafeTexture>,
     pub hold_atlas: (u16, u16),
 }
@@ -190,7 +389,14 @@ pub struct ResourcePack {
     pub sfx_drag: AudioClip,
     pub sfx_flick: AudioClip,
     pub ending: AudioClip,
+    /// Ending music for each grade, indexed like the grade icons
+    pub ending_grades: [Option<AudioClip>; 8],
     pub hit_fx: SafeTexture,
+    /// Hit effects of each note kind, for those that differ from `hit_fx`
+    pub hit_fx_kinds: PerKind<SafeTexture>,
+    pub font: Option<FontArc>,
+    /// Particles emitted along with hit effects, replacing the default squares
+    pub particles: Option<(ParticlePreset, Option<SafeTexture>)>,
 }
 
 impl ResourcePack {
@@ -206,29 +412,61 @@ impl ResourcePack {
         .await
     }
 
+    /// The latest pack format version supported.
+    pub const MAX_VERSION: u32 = 2;
+    /// Names of the grades ending music can be given for, in the order of the grade icons
+    pub const ENDING_GRADES: [&'static str; 8] = ["f", "c", "b", "a", "s", "v", "fc", "phi"];
+    /// The [`ParticlePreset`] of hit particles
+    pub const PARTICLES: &'static str = "particles.yml";
+
+    /// Loads the first of `name` with a supported extension, if any.
+    async fn load_optional_clip(fs: &mut dyn FileSystem, name: &str) -> Result<Option<AudioClip>> {
+        for format in Self::SOUND_FORMATS {
+            let file = format!("{name}.{format}");
+            if let Ok(bytes) = fs.load_file(&file).await {
+                return Ok(Some(AudioClip::new(bytes).with_context(|| format!("Invalid {file}"))?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Ending music for the grade whose icon is at `index`.
+    pub fn ending_for(&self, index: usize) -> AudioClip {
+        self.ending_grades.get(index).cloned().flatten().unwrap_or_else(|| self.ending.clone())
+    }
+
     pub async fn load(fs: &mut dyn FileSystem) -> Result<Self> {
+        macro_rules! load_image {
+            ($path:expr) => {
+                image::load_from_memory(&fs.load_file($path).await.with_context(|| format!("Missing {}", $path))?)?
+            };
+        }
         macro_rules! load_tex {
             ($path:literal) => {
-                SafeTexture::from(image::load_from_memory(&fs.load_file($path).await.with_context(|| format!("Missing {}", $path))?)?)
-                    .with_filter(GL_LINEAR)
+                SafeTexture::from(load_image!($path)).with_filter(GL_LINEAR)
             };
         }
         let info: ResPackInfo = serde_yaml::from_str(&String::from_utf8(fs.load_file("info.yml").await.context("Missing info.yml")?)?)?;
         info.verify()?;
+        macro_rules! load_note {
+            ($path:literal, $kind:ident) => {
+                NoteTexture::new(load_image!($path), info.animations.$kind).with_context(|| format!("Invalid {}", $path))?
+            };
+        }
         let mut note_style = NoteStyle {
-            click: load_tex!("click.png"),
+            click: load_note!("click.png", click),
             hold: load_tex!("hold.png"),
-            flick: load_tex!("flick.png"),
-            drag: load_tex!("drag.png"),
+            flick: load_note!("flick.png", flick),
+            drag: load_note!("drag.png", drag),
             hold_body: None,
             hold_atlas: info.hold_atlas,
         };
         note_style.verify()?;
         let mut note_style_mh = NoteStyle {
-            click: load_tex!("click_mh.png"),
+            click: load_note!("click_mh.png", click),
             hold: load_tex!("hold_mh.png"),
-            flick: load_tex!("flick_mh.png"),
-            drag: load_tex!("drag_mh.png"),
+            flick: load_note!("flick_mh.png", flick),
+            drag: load_note!("drag_mh.png", drag),
             hold_body: None,
             hold_atlas: info.hold_atlas_mh,
         };
@@ -253,6 +491,37 @@ impl ResourcePack {
             get_body(&mut note_style_mh);
         }
         let hit_fx = image::load_from_memory(&fs.load_file("hit_fx.png").await.context("Missing hit_fx.png")?)?.into();
+        let mut hit_fx_kinds = PerKind::default();
+        for (kind, slot) in [
+            ("click", &mut hit_fx_kinds.click),
+            ("drag", &mut hit_fx_kinds.drag),
+            ("flick", &mut hit_fx_kinds.flick),
+            ("hold", &mut hit_fx_kinds.hold),
+        ] {
+            let file = format!("hit_fx_{kind}.png");
+            if let Ok(bytes) = fs.load_file(&file).await {
+                *slot = Some(SafeTexture::from(image::load_from_memory(&bytes).with_context(|| format!("Invalid {file}"))?));
+            }
+        }
+        let mut font = None;
+        for file in ["font.ttf", "font.otf"] {
+            if let Ok(bytes) = fs.load_file(file).await {
+                font = Some(FontArc::try_from_vec(bytes).with_context(|| format!("Invalid {file}"))?);
+                break;
+            }
+        }
+        let particles = match fs.load_file(Self::PARTICLES).await {
+            Ok(bytes) => {
+                let preset = ParticlePreset::parse(&String::from_utf8(bytes)?).with_context(|| format!("Invalid {}", Self::PARTICLES))?;
+                let texture = preset.load_texture(fs).await?;
+                Some((preset, texture))
+            }
+            Err(_) => None,
+        };
+        let mut ending_grades: [Option<AudioClip>; 8] = Default::default();
+        for (grade, slot) in Self::ENDING_GRADES.iter().zip(&mut ending_grades) {
+            *slot = Self::load_optional_clip(fs, &format!("ending_{grade}")).await?;
+        }
 
         macro_rules! load_clip {
             ($path:literal) => {
@@ -294,16 +563,238 @@ impl ResourcePack {
             sfx_drag: load_clip!("drag"),
             sfx_flick: load_clip!("flick"),
             ending: load_clip!("ending"),
+            ending_grades,
             hit_fx,
+            hit_fx_kinds,
+            font,
+            particles,
         })
     }
 }
 
+/// A problem found in a resource pack by [`ResourcePack::validate`].
+#[derive(Clone, Debug)]
+pub enum ResPackProblem {
+    MissingFile(String),
+    /// `info.yml` can't be parsed or has invalid values
+    InvalidInfo(String),
+    InvalidImage {
+        file: String,
+        error: String,
+    },
+    /// The head and tail of `hold_atlas` don't leave room for the body
+    AtlasTooLarge {
+        file: String,
+        atlas: (u16, u16),
+        height: u32,
+    },
+    /// The size of a hit effect can't be split into its grid
+    HitFxGrid {
+        file: String,
+        size: (u32, u32),
+        grid: (u32, u32),
+    },
+    /// The width of an animated note texture can't be split into its frames
+    AnimationFrames {
+        file: String,
+        width: u32,
+        frames: u32,
+    },
+    InvalidFont {
+        file: String,
+        error: String,
+    },
+    /// The particle preset can't be parsed, or its texture can't be loaded
+    InvalidParticles(String),
+    UnsupportedAudio {
+        file: String,
+        error: String,
+    },
+    /// A sound in a format that is never loaded, so the default sound is used instead
+    IgnoredAudio(String),
+}
+
+impl ResourcePack {
+    pub const NOTE_TEXTURES: [&'static str; 8] = [
+        "click.png",
+        "hold.png",
+        "flick.png",
+        "drag.png",
+        "click_mh.png",
+        "hold_mh.png",
+        "flick_mh.png",
+        "drag_mh.png",
+    ];
+    pub const SOUNDS: [&'static str; 4] = ["click", "drag", "flick", "ending"];
+    pub const SOUND_FORMATS: [&'static str; 3] = ["ogg", "wav", "mp3"];
+
+    /// Checks everything [`ResourcePack::load`] would, but reports every problem found instead of
+    /// stopping at the first one. Doesn't need a graphics context.
+    pub async fn validate(fs: &mut dyn FileSystem) -> Vec<ResPackProblem> {
+        let mut problems = Vec::new();
+        let info = match fs.load_file("info.yml").await {
+            Err(_) => {
+                problems.push(ResPackProblem::MissingFile("info.yml".to_owned()));
+                None
+            }
+            Ok(bytes) => match String::from_utf8(bytes)
+                .map_err(anyhow::Error::new)
+                .and_then(|it| Ok(serde_yaml::from_str::<ResPackInfo>(&it)?))
+            {
+                Err(err) => {
+                    problems.push(ResPackProblem::InvalidInfo(err.to_string()));
+                    None
+                }
+                Ok(info) => {
+                    if let Err(err) = info.verify() {
+                        problems.push(ResPackProblem::InvalidInfo(err.to_string()));
+                    }
+                    Some(info)
+                }
+            },
+        };
+
+        let kind_hit_fx = ["click", "drag", "flick", "hold"].map(|kind| format!("hit_fx_{kind}.png"));
+        let mut sizes = HashMap::new();
+        for (file, optional) in Self::NOTE_TEXTURES
+            .into_iter()
+            .chain(["hit_fx.png"])
+            .map(|it| (it, false))
+            .chain(kind_hit_fx.iter().map(|it| (it.as_str(), true)))
+        {
+            let Ok(bytes) = fs.load_file(file).await else {
+                if !optional {
+                    problems.push(ResPackProblem::MissingFile(file.to_owned()));
+                }
+                continue;
+            };
+            match image::load_from_memory(&bytes) {
+                Err(err) => problems.push(ResPackProblem::InvalidImage {
+                    file: file.to_owned(),
+                    error: err.to_string(),
+                }),
+                Ok(image) => {
+                    sizes.insert(file, (image.width(), image.height()));
+                }
+            }
+        }
+        if let Some(info) = &info {
+            for (file, atlas) in [("hold.png", info.hold_atlas), ("hold_mh.png", info.hold_atlas_mh)] {
+                let Some(&(_, height)) = sizes.get(file) else { continue };
+                if atlas.0 as u32 + atlas.1 as u32 >= height {
+                    problems.push(ResPackProblem::AtlasTooLarge {
+                        file: file.to_owned(),
+                        atlas,
+                        height,
+                    });
+                }
+            }
+            let grids = &info.hit_fx_grids;
+            let kind_grids = [&grids.click, &grids.drag, &grids.flick, &grids.hold].map(|it| it.unwrap_or(info.hit_fx));
+            for (file, grid) in std::iter::once(("hit_fx.png", info.hit_fx)).chain(kind_hit_fx.iter().map(String::as_str).zip(kind_grids)) {
+                let Some(&size) = sizes.get(file) else { continue };
+                if grid.0 == 0 || grid.1 == 0 || size.0 % grid.0 != 0 || size.1 % grid.1 != 0 {
+                    problems.push(ResPackProblem::HitFxGrid {
+                        file: file.to_owned(),
+                        size,
+                        grid,
+                    });
+                }
+            }
+            let animations = &info.animations;
+            for file in Self::NOTE_TEXTURES {
+                let animation = match file.trim_end_matches(".png").trim_end_matches("_mh") {
+                    "click" => animations.click,
+                    "drag" => animations.drag,
+                    "flick" => animations.flick,
+                    _ => None,
+                };
+                let (Some(animation), Some(&(width, _))) = (animation, sizes.get(file)) else {
+                    continue;
+                };
+                if animation.frames == 0 || width % animation.frames != 0 {
+                    problems.push(ResPackProblem::AnimationFrames {
+                        file: file.to_owned(),
+                        width,
+                        frames: animation.frames,
+                    });
+                }
+            }
+        }
+        if let Ok(bytes) = fs.load_file(Self::PARTICLES).await {
+            let result = match String::from_utf8(bytes)
+                .map_err(anyhow::Error::new)
+                .and_then(|it| ParticlePreset::parse(&it))
+            {
+                Err(err) => Err(err.context(format!("Invalid {}", Self::PARTICLES))),
+                // the texture is only decoded, since there may be no graphics context
+                Ok(ParticlePreset { texture: Some(path), .. }) => match fs.load_file(&path).await {
+                    Err(err) => Err(err.context(format!("Missing {path}"))),
+                    Ok(bytes) => image::load_from_memory(&bytes).map(drop).with_context(|| format!("Invalid {path}")),
+                },
+                Ok(_) => Ok(()),
+            };
+            if let Err(err) = result {
+                problems.push(ResPackProblem::InvalidParticles(format!("{err:#}")));
+            }
+        }
+        for file in ["font.ttf", "font.otf"] {
+            let Ok(bytes) = fs.load_file(file).await else { continue };
+            if let Err(err) = FontArc::try_from_vec(bytes) {
+                problems.push(ResPackProblem::InvalidFont {
+                    file: file.to_owned(),
+                    error: err.to_string(),
+                });
+            }
+            break;
+        }
+
+        let files: Vec<String> = fs
+            .list_root()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|it| it.rsplit('/').next().unwrap_or_default().to_owned())
+            .collect();
+        let sounds = Self::SOUNDS
+            .map(str::to_owned)
+            .into_iter()
+            .chain(Self::ENDING_GRADES.map(|grade| format!("ending_{grade}")));
+        for name in sounds {
+            // the first format found is the one loaded
+            for format in Self::SOUND_FORMATS {
+                let file = format!("{name}.{format}");
+                let Ok(bytes) = fs.load_file(&file).await else { continue };
+                if let Err(err) = AudioClip::new(bytes) {
+                    problems.push(ResPackProblem::UnsupportedAudio {
+                        file,
+                        error: err.to_string(),
+                    });
+                }
+                break;
+            }
+            for file in &files {
+                if file
+                    .split_once('.')
+                    .is_some_and(|(stem, ext)| stem == name && !Self::SOUND_FORMATS.contains(&ext))
+                {
+                    problems.push(ResPackProblem::IgnoredAudio(file.clone()));
+                }
+            }
+        }
+        problems
+    }
+}
+
 pub struct ParticleEmitter {
     pub scale: f32,
     pub emitter: Emitter,
+    /// Emitters of the hit effects of each note kind, for those that differ
+    pub kind_emitters: PerKind<Emitter>,
     pub emitter_square: Emitter,
     pub hide_particles: bool,
+    square_count: usize,
+    /// Size of the particles at a scale of 1
+    square_size: f32,
 }
 
 impl ParticleEmitter {
@@ -316,35 +807,68 @@ impl ParticleEmitter {
             end.a = 0.;
             ColorCurve { start, mid, end }
         };
-        let mut res = Self {
-            scale: res_pack.info.hit_fx_scale,
-            emitter: Emitter::new(EmitterConfig {
+        let info = &res_pack.info;
+        let hit_fx = |texture: &SafeTexture, grid: (u32, u32)| {
+            Emitter::new(EmitterConfig {
                 local_coords: false,
-                texture: Some(*res_pack.hit_fx),
-                lifetime: res_pack.info.hit_fx_duration,
+                texture: Some(**texture),
+                lifetime: info.hit_fx_duration,
                 lifetime_randomness: 0.0,
                 initial_rotation_randomness: 0.0,
                 initial_direction_spread: 0.0,
                 initial_velocity: 0.0,
-                atlas: Some(AtlasConfig::new(res_pack.info.hit_fx.0 as _, res_pack.info.hit_fx.1 as _, ..)),
-                emitting: false,
-                colors_curve,
-                ..Default::default()
-            }),
-            emitter_square: Emitter::new(EmitterConfig {
-                local_coords: false,
-                lifetime: res_pack.info.hit_fx_duration,
-                lifetime_randomness: 0.0,
-                initial_direction_spread: 2. * std::f32::consts::PI,
-                size_randomness: 0.3,
+                atlas: Some(AtlasConfig::new(grid.0 as _, grid.1 as _, ..)),
                 emitting: false,
-                initial_velocity: 2.5 * scale,
-                initial_velocity_randomness: 1. / 10.,
-                linear_accel: -6. / 1.,
                 colors_curve,
                 ..Default::default()
-            }),
+            })
+        };
+        let kind_grid = |grid: Option<&(u32, u32)>| grid.copied().unwrap_or(info.hit_fx);
+        let kinds = &res_pack.hit_fx_kinds;
+        let grids = &info.hit_fx_grids;
+        let particles = &info.particles;
+        let (emitter_square, square_count, square_size) = match &res_pack.particles {
+            // lengths of presets are relative to the screen width, at a scale of 1
+            Some((preset, texture)) => {
+                let mut config = preset.build(texture.as_ref());
+                config.local_coords = false;
+                config.emitting = false;
+                config.initial_velocity *= scale;
+                config.gravity *= scale;
+                let size = config.size;
+                (Emitter::new(config), preset.config.amount as usize, size)
+            }
+            None => (
+                Emitter::new(EmitterConfig {
+                    local_coords: false,
+                    lifetime: particles.lifetime.unwrap_or(info.hit_fx_duration),
+                    lifetime_randomness: 0.0,
+                    initial_direction_spread: 2. * std::f32::consts::PI,
+                    size_randomness: particles.size_randomness.unwrap_or(0.3),
+                    emitting: false,
+                    initial_velocity: 2.5 * particles.velocity.unwrap_or(1.) * scale,
+                    initial_velocity_randomness: particles.velocity_randomness.unwrap_or(1. / 10.),
+                    linear_accel: -6. * particles.acceleration.unwrap_or(1.),
+                    colors_curve,
+                    ..Default::default()
+                }),
+                particles.count.unwrap_or(4),
+                particles.size.unwrap_or(1.) / 44.,
+            ),
+        };
+        let mut res = Self {
+            scale: info.hit_fx_scale,
+            emitter: hit_fx(&res_pack.hit_fx, info.hit_fx),
+            kind_emitters: PerKind {
+                click: kinds.click.as_ref().map(|it| hit_fx(it, kind_grid(grids.click.as_ref()))),
+                drag: kinds.drag.as_ref().map(|it| hit_fx(it, kind_grid(grids.drag.as_ref()))),
+                flick: kinds.flick.as_ref().map(|it| hit_fx(it, kind_grid(grids.flick.as_ref()))),
+                hold: kinds.hold.as_ref().map(|it| hit_fx(it, kind_grid(grids.hold.as_ref()))),
+            },
+            emitter_square,
             hide_particles,
+            square_count,
+            square_size,
         };
         res.set_scale(scale);
         Ok(res)
@@ -354,20 +878,56 @@ impl ParticleEmitter {
         self.emitter.config.initial_rotation = rotation;
         self.emitter.config.base_color = color;
         self.emitter.emit(pt, 1);
+        self.emit_particles(pt, color);
+    }
+
+    /// Emits the hit effect of a note of `kind`.
+    pub fn emit_note_at(&mut self, pt: Vec2, rotation: f32, color: Color, kind: &NoteKind) {
+        let emitter = match kind {
+            NoteKind::Click => self.kind_emitters.click.as_mut(),
+            NoteKind::Drag => self.kind_emitters.drag.as_mut(),
+            NoteKind::Flick => self.kind_emitters.flick.as_mut(),
+            NoteKind::Hold { .. } => self.kind_emitters.hold.as_mut(),
+        };
+        let Some(emitter) = emitter else {
+            self.emit_at(pt, rotation, color);
+            return;
+        };
+        emitter.config.initial_rotation = rotation;
+        emitter.config.base_color = color;
+        emitter.emit(pt, 1);
+        self.emit_particles(pt, color);
+    }
+
+    fn emit_particles(&mut self, pt: Vec2, color: Color) {
         if !self.hide_particles {
             self.emitter_square.config.base_color = color;
-            self.emitter_square.emit(pt, 4);
+            self.emitter_square.emit(pt, self.square_count);
         }
     }
 
+    fn kind_emitters_mut(&mut self) -> impl Iterator<Item = &mut Emitter> {
+        let kinds = &mut self.kind_emitters;
+        [kinds.click.as_mut(), kinds.drag.as_mut(), kinds.flick.as_mut(), kinds.hold.as_mut()]
+            .into_iter()
+            .flatten()
+    }
+
     pub fn draw(&mut self, dt: f32) {
         self.emitter.draw(vec2(0., 0.), dt);
+        for emitter in self.kind_emitters_mut() {
+            emitter.draw(vec2(0., 0.), dt);
+        }
         self.emitter_square.draw(vec2(0., 0.), dt);
     }
 
     pub fn set_scale(&mut self, scale: f32) {
-        self.emitter.config.size = self.scale * scale / 5.;
-        self.emitter_square.config.size = self.scale * scale / 44.;
+        let size = self.scale * scale / 5.;
+        self.emitter.config.size = size;
+        for emitter in self.kind_emitters_mut() {
+            emitter.config.size = size;
+        }
+        self.emitter_square.config.size = self.scale * self.square_size * scale;
     }
 }
 
@@ -389,6 +949,7 @@ impl NoteBuffer {
     }
 
     pub fn draw_all(&mut self) {
+        let _span = profile::span(Subsystem::Notes);
         let mut gl = unsafe { get_internal_gl() };
         gl.flush();
         let gl = gl.quad_gl;
@@ -434,17 +995,26 @@ pub struct Resource {
 
     pub audio: AudioManager,
     pub music: AudioClip,
+    /// `music` time-stretched for a speed, see [`Resource::music_for_speed`]
+    stretched_music: Option<(f32, AudioClip)>,
+    /// Stretching of `music` going on in the background, with the speed it's for
+    stretch_task: Option<(f32, Task<Result<AudioClip>>)>,
     pub track_length: f64,
     pub sfx_click: Sfx,
     pub sfx_drag: Sfx,
     pub sfx_flick: Sfx,
 
     pub extra_sfxs: SfxMap,
+    /// How far ahead of notes hitsounds are played when they are scheduled, in seconds
+    pub hitsound_lead: f32,
 
     pub chart_target: Option<MSRenderTarget>,
+    /// Where lines with effects of their own are drawn, created when first needed
+    pub effect_layer: Option<EffectLayer>,
     pub no_effect: bool,
 
     pub note_buffer: RefCell<NoteBuffer>,
+    pub note_markers: NoteMarkers,
 
     pub model_stack: Vec<Matrix>,
 }
@@ -502,6 +1072,7 @@ impl Resource {
         let res_pack = ResourcePack::from_path(config.res_pack_path.as_ref())
             .await
             .context("Failed to load resource pack")?;
+        PACK_FONT.with(|it| *it.borrow_mut() = res_pack.font.clone().map(|font| TextPainter::new(font, None)));
         let camera = Camera2D {
             target: vec2(0., 0.),
             zoom: vec2(1., -config.aspect_ratio.unwrap_or(info.aspect_ratio)),
@@ -522,7 +1093,16 @@ impl Resource {
 
         let emitter = ParticleEmitter::new(&res_pack, note_scale, res_pack.info.hide_particles)?;
 
+        let stretched_music = if config.keeps_pitch() && (config.speed - 1.).abs() >= 1e-3 {
+            let (music, speed) = (music.clone(), config.speed);
+            Some((speed, spawn_task(move || Ok(stretch::keep_pitch(&music, speed))).await?))
+        } else {
+            None
+        };
+
         let no_effect = config.disable_effect || has_no_effect;
+        let hitsound_lead = audio_buffer_latency(&config) + config.hitsound_latency;
+        let judge_line_color = config.fx_palette.colors().map_or_else(|| res_pack.info.fx_perfect(), |it| it.0);
 
         macroquad::window::gl_set_drawcall_buffer_capacity(MAX_SIZE * 4, MAX_SIZE * 6);
         Ok(Self {
@@ -536,7 +1116,7 @@ impl Resource {
             time: 0.,
 
             alpha: 1.,
-            judge_line_color: res_pack.info.fx_perfect(),
+            judge_line_color,
 
             camera,
 
@@ -555,37 +1135,123 @@ impl Resource {
 
             audio,
             music,
+            stretched_music,
+            stretch_task: None,
             track_length,
             sfx_click,
             sfx_drag,
             sfx_flick,
             extra_sfxs: SfxMap::new(),
+            hitsound_lead,
 
             chart_target: None,
+            effect_layer: None,
             no_effect,
 
             note_buffer: RefCell::new(NoteBuffer::default()),
+            note_markers: NoteMarkers::new(),
 
             model_stack: vec![Matrix::identity()],
         })
     }
 
+    /// Line color when all notes are perfect, from the palette in use.
+    pub fn color_perfect(&self) -> Color {
+        self.config
+            .fx_palette
+            .colors()
+            .map_or_else(|| self.res_pack.info.color_perfect(), |it| it.0)
+    }
+
+    /// Line color when all notes are at least good, from the palette in use.
+    pub fn color_good(&self) -> Color {
+        self.config.fx_palette.colors().map_or_else(|| self.res_pack.info.color_good(), |it| it.1)
+    }
+
+    pub fn fx_perfect(&self) -> Color {
+        self.config.fx_palette.colors().map_or_else(|| self.res_pack.info.fx_perfect(), |it| it.0)
+    }
+
+    pub fn fx_good(&self) -> Color {
+        self.config.fx_palette.colors().map_or_else(|| self.res_pack.info.fx_good(), |it| it.1)
+    }
+
+    /// The music to play at the configured speed, time-stretched unless its pitch should follow
+    /// the speed. Stretching takes a while, so it's done in the background: until it's finished,
+    /// this returns `None` and should be asked again. The result is kept for the speed last asked
+    /// for.
+    pub fn music_for_speed(&mut self) -> Result<Option<AudioClip>> {
+        let speed = self.config.speed;
+        if !self.config.keeps_pitch() || (speed - 1.).abs() < 1e-3 {
+            return Ok(Some(self.music.clone()));
+        }
+        if let Some((stretched_speed, clip)) = &self.stretched_music {
+            if *stretched_speed == speed {
+                return Ok(Some(clip.clone()));
+            }
+        }
+        match &mut self.stretch_task {
+            Some((task_speed, task)) if *task_speed == speed => {
+                let Some(clip) = task.take() else { return Ok(None) };
+                self.stretch_task = None;
+                let clip = clip.context("Failed to stretch music")?;
+                self.stretched_music = Some((speed, clip.clone()));
+                Ok(Some(clip))
+            }
+            _ => {
+                let music = self.music.clone();
+                self.stretch_task = Some((speed, Task::new(spawn_task(move || Ok(stretch::keep_pitch(&music, speed))))));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Whether the music is being stretched for the configured speed.
+    pub fn is_stretching(&self) -> bool {
+        self.stretch_task
+            .as_ref()
+            .is_some_and(|(speed, task)| *speed == self.config.speed && !task.ok())
+    }
+
     pub fn create_sfx(&mut self, clip: AudioClip) -> Result<Sfx> {
         self.audio.create_sfx(clip, Some(BUFFER_SIZE))
     }
 
-    pub fn emit_at_origin(&mut self, rotation: f32, color: Color) {
+    pub fn emit_at_origin(&mut self, rotation: f32, color: Color, kind: &NoteKind) {
         if !self.config.particle {
             return;
         }
         let pt = self.world_to_screen(Point::default());
-        self.emitter.emit_at(
+        self.emitter.emit_note_at(
             vec2(if self.config.flip_x() { -pt.x } else { pt.x }, -pt.y),
             if self.res_pack.info.hit_fx_rotate { rotation.to_radians() } else { 0. },
             color,
+            kind,
         );
     }
 
+    /// Lowers the quality by the next step, dropping MSAA first, then particles and then shader
+    /// effects. Returns `None` when there's nothing left to lower.
+    pub fn downgrade(&mut self) -> Option<Downgrade> {
+        let step = if self.config.sample_count > 1 {
+            self.config.sample_count = 1;
+            Downgrade::Msaa
+        } else if self.config.particle {
+            self.config.particle = false;
+            Downgrade::Particles
+        } else if !self.no_effect {
+            self.no_effect = true;
+            Downgrade::Effects
+        } else {
+            return None;
+        };
+        // the chart target is made anew for the next frame, if it's still needed
+        self.chart_target = None;
+        self.effect_layer = None;
+        self.last_vp = (0, 0, 0, 0);
+        Some(step)
+    }
+
     pub fn update_size(&mut self, vp: (i32, i32, i32, i32)) -> bool {
         if self.last_vp == vp {
             return false;
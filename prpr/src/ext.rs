@@ -441,6 +441,32 @@ pub fn create_audio_manger(config: &Config) -> Result<AudioManager> {
     }
 }
 
+/// Returns the name of the current audio output device, where the platform can tell.
+pub fn audio_output_device() -> Option<String> {
+    #[cfg(not(any(target_os = "android", target_os = "ios", target_env = "ohos")))]
+    {
+        use cpal::traits::{DeviceTrait, HostTrait};
+        Some(cpal::default_host().default_output_device()?.description().ok()?.name().to_owned())
+    }
+    #[cfg(any(target_os = "android", target_os = "ios", target_env = "ohos"))]
+    {
+        None
+    }
+}
+
+/// Estimates the latency added by the audio output buffer, in seconds.
+pub fn audio_buffer_latency(config: &Config) -> f32 {
+    #[cfg(not(any(target_os = "android", target_os = "ios", target_env = "ohos")))]
+    let device_rate = || {
+        use cpal::traits::{DeviceTrait, HostTrait};
+        Some(cpal::default_host().default_output_device()?.default_output_config().ok()?.sample_rate())
+    };
+    #[cfg(any(target_os = "android", target_os = "ios", target_env = "ohos"))]
+    let device_rate = || None;
+    let sample_rate = config.preferred_sample_rate.or_else(device_rate).unwrap_or(48000);
+    config.audio_buffer_size.unwrap_or(256) as f32 / sample_rate as f32
+}
+
 pub fn make_pipeline(write_color: bool, pass_op: StencilOp, test_func: CompareFunc, test_ref: i32) -> GlPipeline {
     let InternalGlContext {
         quad_gl: gl,
@@ -1,6 +1,9 @@
 //! File system abstraction
 
-use crate::{ext::spawn_task, info::ChartInfo};
+use crate::{
+    ext::spawn_task,
+    info::{ChartInfo, ChartPack},
+};
 use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
 use chardetng::EncodingDetector;
@@ -135,6 +138,11 @@ impl ZipFileSystem {
         let root = if root_dirs.len() == 1 { root_dirs[0].to_owned() } else { String::new() };
         Ok(Self(Arc::new(Mutex::new(zip)), root))
     }
+
+    /// Opens the folder `dir` of the archive as a file system of its own.
+    pub fn open_dir(&self, dir: &str) -> Self {
+        Self(Arc::clone(&self.0), concat_string!(self.1, dir, "/"))
+    }
 }
 
 #[async_trait]
@@ -208,6 +216,47 @@ impl FileSystem for PatchedFileSystem {
     }
 }
 
+/// A chart inside a chart pack, either unpacked or still in its archive.
+///
+/// Files are looked up in the chart's own folder first, then in the pack folder that holds the
+/// shared music and illustration.
+#[derive(Clone)]
+pub struct PackFileSystem<F = ExternalFileSystem> {
+    pub chart: F,
+    pub shared: F,
+}
+
+#[async_trait]
+impl<F: FileSystem + Clone + 'static> FileSystem for PackFileSystem<F> {
+    async fn load_file(&mut self, path: &str) -> Result<Vec<u8>> {
+        if self.chart.exists(path).await? {
+            self.chart.load_file(path).await
+        } else {
+            self.shared.load_file(path).await
+        }
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool> {
+        Ok(self.chart.exists(path).await? || self.shared.exists(path).await?)
+    }
+
+    fn list_root(&self) -> Result<Vec<String>> {
+        let mut res = self.chart.list_root()?;
+        res.extend(self.shared.list_root()?.into_iter().filter(|it| it != ChartPack::MANIFEST));
+        res.sort();
+        res.dedup();
+        Ok(res)
+    }
+
+    fn clone_box(&self) -> Box<dyn FileSystem> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 fn infer_diff(info: &mut ChartInfo, level: &str) {
     if let Ok(val) = level
         .chars()
@@ -399,6 +448,9 @@ pub async fn load_info(fs: &mut dyn FileSystem) -> Result<ChartInfo> {
         info_from_txt(&bytes_to_text_auto(&bytes))?
     } else if let Ok(bytes) = fs.load_file("info.csv").await {
         info_from_csv(&bytes_to_text_auto(&bytes))?
+    } else if let Ok(bytes) = fs.load_file(ChartPack::MANIFEST).await {
+        let pack: ChartPack = serde_yaml::from_str(&bytes_to_text_auto(&bytes))?;
+        pack.chart_info(0).ok_or_else(|| anyhow!("empty chart pack"))?
     } else {
         warn!("none of info.yml, info.txt and info.csv is found, inferring");
         let mut info = ChartInfo::default();
@@ -413,6 +465,11 @@ pub fn fs_from_file(path: &Path) -> Result<Box<dyn FileSystem + Send + Sync + 's
     Ok(if meta.is_file() {
         let bytes = fs::read(path).with_context(|| format!("failed to read from {}", path.display()))?;
         Box::new(ZipFileSystem::new(bytes).with_context(|| format!("cannot open {} as zip archive", path.display()))?)
+    } else if let Some(parent) = path.parent().filter(|it| it.join(ChartPack::MANIFEST).is_file()) {
+        Box::new(PackFileSystem {
+            chart: ExternalFileSystem(Arc::new(crate::dir::Dir::new(path)?)),
+            shared: ExternalFileSystem(Arc::new(crate::dir::Dir::new(parent)?)),
+        })
     } else {
         Box::new(ExternalFileSystem(Arc::new(crate::dir::Dir::new(path)?)))
     })
@@ -0,0 +1,224 @@
+//! Player-side layout of the in-game HUD.
+//!
+//! Every element has a natural place chosen by the game (which charts may still move through
+//! `attach_ui` lines). The layout can hide an element, or move, scale and fade it relative to that
+//! place or to an anchor on the screen.
+
+use crate::{
+    core::{Matrix, Point, Vector},
+    ui::Ui,
+};
+use macroquad::prelude::Rect;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::HashMap};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HudElement {
+    Pause,
+    ComboNumber,
+    Combo,
+    Score,
+    Bar,
+    Name,
+    Level,
+    Accuracy,
+    HitErrorBar,
+    JudgeCounts,
+    RemainingTime,
+}
+
+impl HudElement {
+    pub const ALL: [Self; 11] = [
+        Self::Pause,
+        Self::ComboNumber,
+        Self::Combo,
+        Self::Score,
+        Self::Bar,
+        Self::Name,
+        Self::Level,
+        Self::Accuracy,
+        Self::HitErrorBar,
+        Self::JudgeCounts,
+        Self::RemainingTime,
+    ];
+
+    /// Extra elements are not part of the original HUD, and are hidden unless enabled.
+    pub fn is_extra(self) -> bool {
+        matches!(self, Self::Accuracy | Self::HitErrorBar | Self::JudgeCounts | Self::RemainingTime)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HudAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl HudAnchor {
+    pub const ALL: [Self; 9] = [
+        Self::TopLeft,
+        Self::Top,
+        Self::TopRight,
+        Self::Left,
+        Self::Center,
+        Self::Right,
+        Self::BottomLeft,
+        Self::Bottom,
+        Self::BottomRight,
+    ];
+
+    /// Position of the anchor across the screen, from `(0, 0)` at the top left to `(1, 1)` at the
+    /// bottom right.
+    pub fn factor(self) -> (f32, f32) {
+        let index = self as usize;
+        ((index % 3) as f32 / 2., (index / 3) as f32 / 2.)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HudElementLayout {
+    pub visible: bool,
+    /// The screen anchor the element is aligned to, or `None` to keep its natural place
+    pub anchor: Option<HudAnchor>,
+    pub offset: (f32, f32),
+    pub scale: f32,
+    pub opacity: f32,
+}
+
+impl Default for HudElementLayout {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            anchor: None,
+            offset: (0., 0.),
+            scale: 1.,
+            opacity: 1.,
+        }
+    }
+}
+
+impl HudElementLayout {
+    pub fn default_for(element: HudElement) -> Self {
+        Self {
+            visible: !element.is_extra(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HudLayout {
+    /// Only elements that differ from their default are stored.
+    elements: HashMap<HudElement, HudElementLayout>,
+}
+
+impl HudLayout {
+    const MARGIN: f32 = 0.03;
+
+    pub fn get(&self, element: HudElement) -> HudElementLayout {
+        self.elements
+            .get(&element)
+            .cloned()
+            .unwrap_or_else(|| HudElementLayout::default_for(element))
+    }
+
+    pub fn get_mut(&mut self, element: HudElement) -> &mut HudElementLayout {
+        self.elements.entry(element).or_insert_with(|| HudElementLayout::default_for(element))
+    }
+
+    pub fn reset(&mut self, element: HudElement) {
+        self.elements.remove(&element);
+    }
+
+    /// Drops entries that are the same as the defaults.
+    pub fn normalize(&mut self) {
+        self.elements.retain(|element, layout| *layout != HudElementLayout::default_for(*element));
+    }
+
+    /// Returns the transform that moves an element whose natural bounds are `rect` to its place in
+    /// the layout. `top` is the y coordinate of the top of the screen.
+    pub fn transform(&self, element: HudElement, rect: Rect, top: f32) -> Matrix {
+        let layout = self.get(element);
+        let (ax, ay) = layout.anchor.map_or((0.5, 0.5), HudAnchor::factor);
+        let pivot = Vector::new(rect.x + rect.w * ax, rect.y + rect.h * ay);
+        let target = if layout.anchor.is_some() {
+            let (l, r) = (-1. + Self::MARGIN, 1. - Self::MARGIN);
+            let (t, b) = (top + Self::MARGIN, -top - Self::MARGIN);
+            Vector::new(l + (r - l) * ax, t + (b - t) * ay)
+        } else {
+            pivot
+        };
+        let target = target + Vector::new(layout.offset.0, layout.offset.1);
+        Matrix::new_translation(&-pivot).append_scaling(layout.scale).append_translation(&target)
+    }
+}
+
+/// Draws HUD elements of a frame according to a [`HudLayout`].
+pub(crate) struct HudPass<'a> {
+    layout: &'a HudLayout,
+    top: f32,
+    editing: bool,
+    /// Bounds of the elements drawn, after layout. Only collected while editing.
+    pub bounds: Vec<(HudElement, Rect)>,
+}
+
+impl<'a> HudPass<'a> {
+    pub fn new(layout: &'a HudLayout, top: f32, editing: bool) -> Self {
+        Self {
+            layout,
+            top,
+            editing,
+            bounds: Vec::new(),
+        }
+    }
+
+    /// Draws `element`, whose natural bounds are `rect`, with `f`. Hidden elements are only drawn
+    /// (faintly) while editing.
+    pub fn draw<R>(&mut self, ui: &mut Ui, element: HudElement, rect: Rect, f: impl FnOnce(&mut Ui) -> R) -> Option<R> {
+        let layout = self.layout.get(element);
+        if !layout.visible && !self.editing {
+            return None;
+        }
+        let tr = self.layout.transform(element, rect, self.top);
+        if self.editing {
+            let corners = [
+                (rect.x, rect.y),
+                (rect.right(), rect.y),
+                (rect.x, rect.bottom()),
+                (rect.right(), rect.bottom()),
+            ]
+            .map(|(x, y)| tr.transform_point(&Point::new(x, y)));
+            let (mut l, mut t, mut r, mut b) = (f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+            for p in corners {
+                (l, t, r, b) = (l.min(p.x), t.min(p.y), r.max(p.x), b.max(p.y));
+            }
+            self.bounds.push((element, Rect::new(l, t, r - l, b - t)));
+        }
+        let alpha = if layout.visible { layout.opacity } else { 0.25 };
+        Some(ui.with(tr, |ui| ui.alpha(alpha, f)))
+    }
+}
+
+thread_local! {
+    static EDITED: RefCell<Option<HudLayout>> = RefCell::default();
+}
+
+/// Takes the layout saved by the in-game editor since the last call, if any.
+pub fn take_edited_layout() -> Option<HudLayout> {
+    EDITED.with(|it| it.borrow_mut().take())
+}
+
+pub(crate) fn set_edited_layout(layout: HudLayout) {
+    EDITED.with(|it| *it.borrow_mut() = Some(layout));
+}
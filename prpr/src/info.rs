@@ -49,6 +49,8 @@ pub struct ChartInfo {
     pub force_aspect_ratio: bool,
     pub use_rpe_170_speed: Option<bool>,
     pub use_attach_ui_fix: Option<bool>,
+    /// The RPE version an RPE chart was made with, as found in its metadata
+    pub rpe_version: Option<i32>,
 
     pub created: Option<DateTime<Utc>>,
     pub updated: Option<DateTime<Utc>>,
@@ -90,6 +92,7 @@ impl Default for ChartInfo {
             force_aspect_ratio: false,
             use_rpe_170_speed: None,
             use_attach_ui_fix: None,
+            rpe_version: None,
 
             created: None,
             updated: None,
@@ -97,3 +100,37 @@ impl Default for ChartInfo {
         }
     }
 }
+
+/// Manifest of a chart pack (`pack.yml`): several charts, usually a difficulty set, sharing the
+/// same music and illustration.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChartPack {
+    pub name: String,
+    /// Shared music file. Overrides `music` of every chart when present.
+    #[serde(default)]
+    pub music: Option<String>,
+    /// Shared illustration file. Overrides `illustration` of every chart when present.
+    #[serde(default)]
+    pub illustration: Option<String>,
+    pub charts: Vec<ChartInfo>,
+}
+
+impl ChartPack {
+    pub const MANIFEST: &'static str = "pack.yml";
+
+    /// Returns the info of the `index`-th chart, with the shared fields filled in.
+    pub fn chart_info(&self, index: usize) -> Option<ChartInfo> {
+        let mut info = self.charts.get(index)?.clone();
+        if info.name.is_empty() || info.name == ChartInfo::default().name {
+            info.name.clone_from(&self.name);
+        }
+        if let Some(music) = &self.music {
+            info.music.clone_from(music);
+        }
+        if let Some(illustration) = &self.illustration {
+            info.illustration.clone_from(illustration);
+        }
+        Some(info)
+    }
+}
@@ -0,0 +1,285 @@
+//! Keyboard and gamepad input profiles.
+//!
+//! A profile maps keys and gamepad buttons to judge actions. Bindings can be restricted to a
+//! horizontal zone of the judge line, so that e.g. four keys cover the line from left to right.
+
+use macroquad::prelude::KeyCode;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+macro_rules! key_names {
+    ($($name:ident),* $(,)?) => {
+        const KEY_NAMES: &[(KeyCode, &str)] = &[$((KeyCode::$name, stringify!($name))),*];
+    };
+}
+
+key_names![
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Kp0,
+    Kp1,
+    Kp2,
+    Kp3,
+    Kp4,
+    Kp5,
+    Kp6,
+    Kp7,
+    Kp8,
+    Kp9,
+    Space,
+    Enter,
+    Tab,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    LeftShift,
+    RightShift,
+    LeftControl,
+    RightControl,
+    LeftAlt,
+    RightAlt,
+    Comma,
+    Period,
+    Slash,
+    Semicolon,
+    Apostrophe,
+    LeftBracket,
+    RightBracket,
+    Backslash,
+    Minus,
+    Equal,
+    GraveAccent,
+];
+
+fn key_name(key: KeyCode) -> &'static str {
+    KEY_NAMES.iter().find(|it| it.0 == key).map_or("Unknown", |it| it.1)
+}
+
+mod key_serde {
+    use super::{key_name, KEY_NAMES};
+    use macroquad::prelude::KeyCode;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(key: &KeyCode, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(key_name(*key))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyCode, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(KEY_NAMES.iter().find(|it| it.1 == name).map_or(KeyCode::Unknown, |it| it.0))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    Select,
+    Start,
+}
+
+impl GamepadButton {
+    #[cfg(all(feature = "gamepad", not(any(target_os = "android", target_os = "ios", target_env = "ohos"))))]
+    pub(crate) fn from_gilrs(button: gilrs::Button) -> Option<Self> {
+        use gilrs::Button;
+        Some(match button {
+            Button::South => Self::South,
+            Button::East => Self::East,
+            Button::North => Self::North,
+            Button::West => Self::West,
+            Button::LeftTrigger => Self::LeftBumper,
+            Button::RightTrigger => Self::RightBumper,
+            Button::LeftTrigger2 => Self::LeftTrigger,
+            Button::RightTrigger2 => Self::RightTrigger,
+            Button::LeftThumb => Self::LeftThumb,
+            Button::RightThumb => Self::RightThumb,
+            Button::DPadUp => Self::DPadUp,
+            Button::DPadDown => Self::DPadDown,
+            Button::DPadLeft => Self::DPadLeft,
+            Button::DPadRight => Self::DPadRight,
+            Button::Select => Self::Select,
+            Button::Start => Self::Start,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InputSource {
+    Key(#[serde(with = "key_serde")] KeyCode),
+    Button(GamepadButton),
+}
+
+impl InputSource {
+    pub fn name(&self) -> Cow<'static, str> {
+        match self {
+            Self::Key(key) => key_name(*key).into(),
+            Self::Button(button) => format!("Pad {button:?}").into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FlickDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum InputAction {
+    /// Hits click notes and the head of hold notes; while held, it also sustains holds and catches
+    /// drags.
+    Tap,
+    /// Only sustains holds and catches drags, like a finger resting on the screen.
+    Hold,
+    /// Hits flick notes. Flick notes accept any direction, so this only tells the bindings apart.
+    Flick { direction: FlickDirection },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputBinding {
+    pub input: InputSource,
+    pub action: InputAction,
+    /// The zone of the judge line this binding covers, or `None` for the whole line.
+    #[serde(default)]
+    pub zone: Option<u8>,
+}
+
+impl InputBinding {
+    fn new(input: InputSource, action: InputAction, zone: Option<u8>) -> Self {
+        Self { input, action, zone }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputProfile {
+    pub name: String,
+    /// How many zones each judge line is split into, from left to right.
+    pub zones: u8,
+    /// Bindings of this profile. A profile without bindings treats every key as a tap that also
+    /// catches flicks while held.
+    pub bindings: Vec<InputBinding>,
+}
+
+impl InputProfile {
+    pub fn presets() -> Vec<Self> {
+        use FlickDirection::*;
+        use InputAction::*;
+        use InputSource::*;
+        let flick = |direction| Flick { direction };
+        vec![
+            Self {
+                name: "Any key".to_owned(),
+                zones: 1,
+                bindings: Vec::new(),
+            },
+            Self {
+                name: "Keyboard".to_owned(),
+                zones: 4,
+                bindings: vec![
+                    InputBinding::new(Key(KeyCode::D), Tap, Some(0)),
+                    InputBinding::new(Key(KeyCode::F), Tap, Some(1)),
+                    InputBinding::new(Key(KeyCode::J), Tap, Some(2)),
+                    InputBinding::new(Key(KeyCode::K), Tap, Some(3)),
+                    InputBinding::new(Key(KeyCode::Space), Hold, None),
+                    InputBinding::new(Key(KeyCode::Up), flick(Up), None),
+                    InputBinding::new(Key(KeyCode::Down), flick(Down), None),
+                    InputBinding::new(Key(KeyCode::Left), flick(Left), None),
+                    InputBinding::new(Key(KeyCode::Right), flick(Right), None),
+                ],
+            },
+            Self {
+                name: "Gamepad".to_owned(),
+                zones: 2,
+                bindings: vec![
+                    InputBinding::new(Button(GamepadButton::LeftBumper), Tap, Some(0)),
+                    InputBinding::new(Button(GamepadButton::RightBumper), Tap, Some(1)),
+                    InputBinding::new(Button(GamepadButton::South), Tap, None),
+                    InputBinding::new(Button(GamepadButton::LeftTrigger), Hold, Some(0)),
+                    InputBinding::new(Button(GamepadButton::RightTrigger), Hold, Some(1)),
+                    InputBinding::new(Button(GamepadButton::DPadUp), flick(Up), None),
+                    InputBinding::new(Button(GamepadButton::DPadDown), flick(Down), None),
+                    InputBinding::new(Button(GamepadButton::DPadLeft), flick(Left), None),
+                    InputBinding::new(Button(GamepadButton::DPadRight), flick(Right), None),
+                ],
+            },
+        ]
+    }
+
+    #[inline]
+    pub fn is_any_key(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    pub fn binding(&self, input: InputSource) -> Option<&InputBinding> {
+        self.bindings.iter().find(|it| it.input == input)
+    }
+
+    pub fn binds_key(&self, key: KeyCode) -> bool {
+        self.binding(InputSource::Key(key)).is_some()
+    }
+
+    /// Returns whether a binding of `zone` covers the line-local x coordinate `x`.
+    pub fn covers(&self, zone: Option<u8>, x: f32) -> bool {
+        let Some(zone) = zone else {
+            return true;
+        };
+        let zones = self.zones.max(1);
+        let at = ((x + 1.) / 2. * zones as f32).floor().clamp(0., (zones - 1) as f32) as u8;
+        at == zone
+    }
+}
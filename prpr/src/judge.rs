@@ -2,8 +2,9 @@
 
 use crate::{
     config::Config,
-    core::{BadNote, Chart, NoteKind, Point, Resource, Vector, NOTE_WIDTH_RATIO_BASE},
+    core::{BadNote, Chart, Note, NoteKind, Point, Resource, Vector, NOTE_WIDTH_RATIO_BASE},
     ext::{get_viewport, NotNanExt},
+    input::{InputAction, InputBinding, InputProfile, InputSource},
 };
 use macroquad::prelude::{
     utils::{register_input_subscriber, repeat_all_miniquad_input},
@@ -247,6 +248,7 @@ impl JudgeInner {
             std: 0.,
             early_kind: self.early_kind,
             late_kind: self.late_kind,
+            input_profile: None,
         }
     }
 
@@ -279,13 +281,21 @@ pub struct Judge {
 
     pub(crate) inner: JudgeInner,
     pub judgements: RefCell<Judgements>,
+
+    input_profile: Option<InputProfile>,
+    /// Tap and hold bindings that are currently held down
+    held: Vec<InputBinding>,
+    timings: Vec<HitTiming>,
+    /// Hitsounds of notes hit ahead of time, waiting for their notes to be within the output
+    /// latency
+    pending_hitsounds: Vec<(f64, HitSound)>,
 }
 
 #[derive(Default)]
 struct TouchStatus {
     touches: Vec<Touch>,
-    key_delta: i32,
-    keys_down: u32,
+    /// Keys and gamepad buttons pressed (`true`) or released (`false`) during this frame
+    inputs: Vec<(InputSource, bool)>,
 }
 
 static SUBSCRIBER_ID: Lazy<usize> = Lazy::new(register_input_subscriber);
@@ -299,7 +309,7 @@ pub fn take_wheel() -> (f32, f32) {
 }
 
 impl Judge {
-    pub fn new(chart: &Chart) -> Self {
+    pub fn new(chart: &Chart, input_profile: Option<InputProfile>) -> Self {
         let notes = chart
             .lines
             .iter()
@@ -318,12 +328,21 @@ impl Judge {
 
             inner: JudgeInner::new(chart.lines.iter().map(|it| it.notes.iter().filter(|it| !it.fake).count() as u32).sum()),
             judgements: RefCell::new(Vec::new()),
+
+            input_profile,
+            held: Vec::new(),
+            timings: Vec::new(),
+            pending_hitsounds: Vec::new(),
         }
     }
 
     pub fn reset(&mut self) {
         self.notes.iter_mut().for_each(|it| it.1 = 0);
         self.trackers.clear();
+        self.key_down_count = 0;
+        self.held.clear();
+        self.timings.clear();
+        self.pending_hitsounds.clear();
         self.inner.reset();
         self.judgements.borrow_mut().clear();
     }
@@ -344,6 +363,30 @@ impl Judge {
         self.last_time = t;
     }
 
+    /// Plays the hitsound of a note being hit. With hitsound scheduling, notes hit earlier than the
+    /// output latency have their hitsounds held back, so that they are heard when the notes reach
+    /// the line.
+    fn play_hitsound(pending: &mut Vec<(f64, HitSound)>, res: &mut Resource, note: &Note) {
+        if res.config.hitsound_scheduling && note.time > res.time + res.hitsound_lead as f64 * res.config.speed as f64 {
+            pending.push((note.time, note.hitsound.clone()));
+        } else {
+            note.hitsound.play(res);
+        }
+    }
+
+    /// Plays the hitsounds held back by [`Self::play_hitsound`] once their notes are within the
+    /// output latency.
+    pub fn schedule_hitsounds(&mut self, res: &mut Resource) {
+        let t = res.time + res.hitsound_lead as f64 * res.config.speed as f64;
+        self.pending_hitsounds.retain(|(time, hitsound)| {
+            if *time > t {
+                return true;
+            }
+            hitsound.play(res);
+            false
+        });
+    }
+
     pub fn commit(&mut self, t: f64, what: Judgement, line_id: u32, note_id: u32, diff: f64) {
         self.judgements.borrow_mut().push((t, line_id, note_id, Ok(what)));
         self.inner.commit(what, diff);
@@ -371,6 +414,8 @@ impl Judge {
         };
         repeat_all_miniquad_input(&mut handler, *SUBSCRIBER_ID);
         handler.finalize();
+        #[cfg(all(feature = "gamepad", not(any(target_os = "android", target_os = "ios", target_env = "ohos"))))]
+        poll_gamepad(&mut handler.status.inputs);
         TOUCHES.with(|it| {
             *it.borrow_mut() = handler.status;
         });
@@ -409,6 +454,48 @@ impl Judge {
         })
     }
 
+    /// Returns the keys and gamepad buttons pressed or released during this frame.
+    pub fn get_inputs() -> Vec<(InputSource, bool)> {
+        TOUCHES.with(|it| it.borrow().inputs.clone())
+    }
+
+    /// Finds the earliest note not judged yet that satisfies `filter`, only considering notes in
+    /// `zone` of their line if given.
+    fn earliest_note(
+        chart: &mut Chart,
+        notes: &[(Vec<u32>, usize)],
+        t: f64,
+        zone: Option<(&InputProfile, Option<u8>)>,
+        filter: impl Fn(&Note) -> bool,
+    ) -> Option<(usize, u32)> {
+        let candidates: Vec<_> = chart
+            .lines
+            .iter_mut()
+            .zip(notes.iter())
+            .enumerate()
+            .filter_map(|(line_id, (line, (idx, st)))| {
+                idx[*st..]
+                    .iter()
+                    .cloned()
+                    .find(|id| {
+                        let note = &mut line.notes[*id as usize];
+                        if !matches!(note.judge, JudgeStatus::NotJudged) || !filter(note) {
+                            return false;
+                        }
+                        zone.is_none_or(|(profile, zone)| {
+                            let x = &mut note.object.translation.0;
+                            x.set_time(t);
+                            profile.covers(zone, x.now())
+                        })
+                    })
+                    .map(|id| (line_id, id))
+            })
+            .collect();
+        candidates
+            .into_iter()
+            .min_by_key(|(line_id, id)| chart.lines[*line_id].notes[*id as usize].time.not_nan())
+    }
+
     pub fn update(&mut self, res: &mut Resource, chart: &mut Chart, bad_notes: &mut Vec<BadNote>) {
         if res.config.autoplay() {
             self.auto_play_update(res, chart);
@@ -459,16 +546,46 @@ impl Judge {
                 })
                 .collect()
         };
-        let (events, keys_down, key_delta) = TOUCHES.with(|it| {
+        let (events, inputs) = TOUCHES.with(|it| {
             let guard = it.borrow();
             let events = guard.touches.clone();
-            if res.config.use_keyboard {
-                (events, guard.keys_down, guard.key_delta)
+            if self.input_profile.is_some() {
+                (events, guard.inputs.clone())
             } else {
-                (events, 0, 0)
+                (events, Vec::new())
             }
         });
-        self.key_down_count = self.key_down_count.saturating_add_signed(key_delta);
+        // zones hit by taps and flicks during this frame; `None` means anywhere
+        let mut taps = Vec::new();
+        let mut flicks = Vec::new();
+        if let Some(profile) = &self.input_profile {
+            for (input, down) in inputs {
+                if profile.is_any_key() {
+                    if down {
+                        self.key_down_count += 1;
+                        taps.push(None);
+                    } else {
+                        self.key_down_count = self.key_down_count.saturating_sub(1);
+                    }
+                    continue;
+                }
+                let Some(binding) = profile.binding(input) else {
+                    continue;
+                };
+                if !down {
+                    self.held.retain(|it| it.input != input);
+                    continue;
+                }
+                match binding.action {
+                    InputAction::Tap => {
+                        taps.push(binding.zone);
+                        self.held.push(binding.clone());
+                    }
+                    InputAction::Hold => self.held.push(binding.clone()),
+                    InputAction::Flick { .. } => flicks.push(binding.zone),
+                }
+            }
+        }
         {
             fn to_local(Vec2 { x, y }: Vec2) -> Point {
                 Point::new(x / screen_width() * 2. - 1., y / screen_height() * 2. - 1.)
@@ -623,7 +740,7 @@ impl Judge {
                                 judgements.push((if dt <= LIMIT_PERFECT { Judgement::Perfect } else { Judgement::Good }, line_id, id, Some(t)));
                             }
                             NoteKind::Hold { .. } => {
-                                note.hitsound.play(res);
+                                Self::play_hitsound(&mut self.pending_hitsounds, res, note);
                                 self.judgements.borrow_mut().push((t, line_id as _, id, Err(dt <= LIMIT_PERFECT)));
                                 note.judge = JudgeStatus::Hold(dt <= LIMIT_PERFECT, t, t, false, f64::INFINITY);
                             }
@@ -646,25 +763,12 @@ impl Judge {
                 }
             }
         }
-        for _ in 0..keys_down {
+        let profile = self.input_profile.as_ref();
+        for zone in taps {
             // find the earliest not judged click / hold note
-            if let Some((line_id, id)) = chart
-                .lines
-                .iter()
-                .zip(self.notes.iter())
-                .enumerate()
-                .filter_map(|(line_id, (line, (idx, st)))| {
-                    idx[*st..]
-                        .iter()
-                        .cloned()
-                        .find(|id| {
-                            let note = &line.notes[*id as usize];
-                            matches!(note.judge, JudgeStatus::NotJudged) && matches!(note.kind, NoteKind::Click | NoteKind::Hold { .. })
-                        })
-                        .map(|id| (line_id, id))
-                })
-                .min_by_key(|(line_id, id)| chart.lines[*line_id].notes[*id as usize].time.not_nan())
-            {
+            if let Some((line_id, id)) = Self::earliest_note(chart, &self.notes, t, profile.map(|it| (it, zone)), |note| {
+                matches!(note.kind, NoteKind::Click | NoteKind::Hold { .. })
+            }) {
                 let note = &mut chart.lines[line_id].notes[id as usize];
                 let dt = (t - note.time).abs() / spd;
                 if dt <= if matches!(note.kind, NoteKind::Click) { LIMIT_BAD } else { LIMIT_GOOD } {
@@ -685,17 +789,27 @@ impl Judge {
                             ));
                         }
                         NoteKind::Hold { .. } => {
-                            note.hitsound.play(res);
+                            Self::play_hitsound(&mut self.pending_hitsounds, res, note);
                             self.judgements.borrow_mut().push((t, line_id as _, id, Err(dt <= LIMIT_PERFECT)));
                             note.judge = JudgeStatus::Hold(dt <= LIMIT_PERFECT, t, t, false, f64::INFINITY);
                         }
                         _ => unreachable!(),
                     };
                 }
-            } else {
-                break;
             }
         }
+        for zone in flicks {
+            if let Some((line_id, id)) =
+                Self::earliest_note(chart, &self.notes, t, profile.map(|it| (it, zone)), |note| matches!(note.kind, NoteKind::Flick))
+            {
+                let note = &mut chart.lines[line_id].notes[id as usize];
+                if (t - note.time).abs() / spd <= LIMIT_GOOD {
+                    note.judge = JudgeStatus::PreJudge;
+                }
+            }
+        }
+        // whether a held key or button covers the line-local x coordinate
+        let held = |x: f32| self.key_down_count != 0 || profile.is_some_and(|profile| self.held.iter().any(|it| profile.covers(it.zone, x)));
         for (line_id, ((line, pos), (idx, st))) in chart.lines.iter_mut().zip(pos.iter()).zip(self.notes.iter()).enumerate() {
             line.object.set_time(t);
             for id in &idx[*st..] {
@@ -709,7 +823,7 @@ impl Judge {
                         let x = &mut note.object.translation.0;
                         x.set_time(t);
                         let x = x.now();
-                        if self.key_down_count == 0
+                        if !held(x)
                             && !pos
                                 .iter()
                                 .any(|it| it.is_some_and(|it| (it.x - x).abs() as f64 / note.judge_area as f64 <= X_DIFF_MAX))
@@ -739,14 +853,17 @@ impl Judge {
                 if -dt > LIMIT_BAD {
                     break;
                 }
-                if !matches!(note.kind, NoteKind::Drag) && (self.key_down_count == 0 || !matches!(note.kind, NoteKind::Flick)) {
+                // with the "any key" profile, holding a key also catches flicks
+                let key_flick = self.key_down_count != 0 && matches!(note.kind, NoteKind::Flick);
+                if !matches!(note.kind, NoteKind::Drag) && !key_flick {
                     continue;
                 }
                 let dt = dt.abs();
                 let x = &mut note.object.translation.0;
                 x.set_time(t);
                 let x = x.now();
-                if self.key_down_count != 0
+                if key_flick
+                    || held(x)
                     || pos.iter().any(|it| {
                         it.is_some_and(|it| {
                             let dx = (it.x - x).abs() as f64 / note.judge_area as f64;
@@ -802,32 +919,36 @@ impl Judge {
             let line = &chart.lines[line_id];
             let note = &line.notes[id as usize];
             let line_tr = line.now_transform(res, &chart.lines);
-            self.commit(
-                t,
-                judgement,
-                line_id as _,
-                id,
-                if matches!(judgement, Judgement::Miss) {
-                    0.25
-                } else if matches!(note.kind, NoteKind::Drag | NoteKind::Flick) {
-                    0.
-                } else {
-                    (diff.unwrap_or(t) - note.time) / spd
-                },
-            );
+            let diff = if matches!(judgement, Judgement::Miss) {
+                0.25
+            } else if matches!(note.kind, NoteKind::Drag | NoteKind::Flick) {
+                0.
+            } else {
+                (diff.unwrap_or(t) - note.time) / spd
+            };
+            self.commit(t, judgement, line_id as _, id, diff);
+            if !matches!(judgement, Judgement::Miss) {
+                if let Some(kind) = HitKind::of(&note.kind) {
+                    self.timings.push(HitTiming {
+                        time: note.time as f32,
+                        diff: diff as f32,
+                        kind,
+                    });
+                }
+            }
             if matches!(note.kind, NoteKind::Hold { .. }) {
                 continue;
             }
             if match judgement {
                 Judgement::Perfect => {
                     res.with_model(line_tr * note.object.now(res), |res| {
-                        res.emit_at_origin(note.rotation(line), note.fx_color.unwrap_or_else(|| res.res_pack.info.fx_perfect()))
+                        res.emit_at_origin(note.rotation(line), note.fx_color.unwrap_or_else(|| res.fx_perfect()), &note.kind)
                     });
                     true
                 }
                 Judgement::Good => {
                     res.with_model(line_tr * note.object.now(res), |res| {
-                        res.emit_at_origin(note.rotation(line), note.fx_color.unwrap_or_else(|| res.res_pack.info.fx_good()))
+                        res.emit_at_origin(note.rotation(line), note.fx_color.unwrap_or_else(|| res.fx_good()), &note.kind)
                     });
                     true
                 }
@@ -856,7 +977,7 @@ impl Judge {
                 }
                 _ => false,
             } {
-                note.hitsound.play(res);
+                Self::play_hitsound(&mut self.pending_hitsounds, res, note);
             }
         }
         for (line, (idx, st)) in chart.lines.iter().zip(self.notes.iter_mut()) {
@@ -893,7 +1014,7 @@ impl Judge {
                     break;
                 }
                 note.judge = if matches!(note.kind, NoteKind::Hold { .. }) {
-                    note.hitsound.play(res);
+                    Self::play_hitsound(&mut self.pending_hitsounds, res, note);
                     self.judgements.borrow_mut().push((t, line_id as _, *id, Err(true)));
                     JudgeStatus::Hold(true, t, (t - note.time) / spd, false, f64::INFINITY)
                 } else {
@@ -920,7 +1041,8 @@ impl Judge {
             };
             let line = &chart.lines[line_id];
             res.with_model(line.now_transform(res, &chart.lines) * note_transform, |res| {
-                res.emit_at_origin(line.notes[id as usize].rotation(line), res.res_pack.info.fx_perfect())
+                let note = &line.notes[id as usize];
+                res.emit_at_origin(note.rotation(line), res.fx_perfect(), &note.kind)
             });
             if !matches!(chart.lines[line_id].notes[id as usize].kind, NoteKind::Hold { .. }) {
                 note_hitsound.play(res);
@@ -928,9 +1050,22 @@ impl Judge {
         }
     }
 
-    #[inline]
     pub fn result(&self) -> PlayResult {
-        self.inner.result()
+        let stats = |kind: Option<HitKind>| {
+            TimingStats::new(
+                self.timings
+                    .iter()
+                    .filter(|it| kind.map_or(true, |kind| it.kind == kind))
+                    .map(|it| it.diff),
+            )
+        };
+        PlayResult {
+            input_profile: self.input_profile.as_ref().map(|it| it.name.clone()),
+            timing: stats(None),
+            timing_by_kind: [stats(Some(HitKind::Click)), stats(Some(HitKind::Hold))],
+            timings: self.timings.clone(),
+            ..self.inner.result()
+        }
     }
 
     #[inline]
@@ -938,6 +1073,12 @@ impl Judge {
         self.inner.combo()
     }
 
+    /// Timed hits of this run so far, in the order they were judged.
+    #[inline]
+    pub fn timings(&self) -> &[HitTiming] {
+        &self.timings
+    }
+
     #[inline]
     pub fn counts(&self) -> [u32; 4] {
         self.inner.counts()
@@ -1006,16 +1147,47 @@ impl EventHandler for Handler {
         });
     }
 
-    fn key_down_event(&mut self, _ctx: &mut miniquad::Context, _keycode: KeyCode, _keymods: miniquad::KeyMods, repeat: bool) {
+    fn key_down_event(&mut self, _ctx: &mut miniquad::Context, keycode: KeyCode, _keymods: miniquad::KeyMods, repeat: bool) {
         if !repeat {
-            self.status.key_delta += 1;
-            self.status.keys_down += 1;
+            self.status.inputs.push((InputSource::Key(keycode), true));
         }
     }
 
-    fn key_up_event(&mut self, _ctx: &mut miniquad::Context, _keycode: KeyCode, _keymods: miniquad::KeyMods) {
-        self.status.key_delta -= 1;
+    fn key_up_event(&mut self, _ctx: &mut miniquad::Context, keycode: KeyCode, _keymods: miniquad::KeyMods) {
+        self.status.inputs.push((InputSource::Key(keycode), false));
+    }
+}
+
+#[cfg(all(feature = "gamepad", not(any(target_os = "android", target_os = "ios", target_env = "ohos"))))]
+fn poll_gamepad(inputs: &mut Vec<(InputSource, bool)>) {
+    use crate::input::GamepadButton;
+    use gilrs::{EventType, Gilrs};
+
+    thread_local! {
+        static GILRS: RefCell<Option<Gilrs>> = RefCell::new(match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) => {
+                tracing::warn!(?err, "failed to initialize gamepad support");
+                None
+            }
+        });
     }
+    GILRS.with(|it| {
+        let mut guard = it.borrow_mut();
+        let Some(gilrs) = guard.as_mut() else {
+            return;
+        };
+        while let Some(event) = gilrs.next_event() {
+            let (button, down) = match event.event {
+                EventType::ButtonPressed(button, _) => (button, true),
+                EventType::ButtonReleased(button, _) => (button, false),
+                _ => continue,
+            };
+            if let Some(button) = GamepadButton::from_gilrs(button) {
+                inputs.push((InputSource::Button(button), down));
+            }
+        }
+    });
 }
 
 #[derive(Default)]
@@ -1030,6 +1202,105 @@ pub struct PlayResult {
     pub std: f32,
     pub early_kind: [u32; 4],
     pub late_kind: [u32; 4],
+    /// Name of the keyboard or gamepad input profile used, if any
+    pub input_profile: Option<String>,
+    /// Every timed hit, in the order they were judged
+    pub timings: Vec<HitTiming>,
+    pub timing: TimingStats,
+    /// Timing statistics of each [`HitKind`], in declaration order
+    pub timing_by_kind: [TimingStats; 2],
+}
+
+impl PlayResult {
+    /// Counts timed hits into `bins` equal buckets spanning the whole judge window, from early
+    /// to late.
+    pub fn hit_error_histogram(&self, bins: usize) -> Vec<u32> {
+        let mut res = vec![0; bins];
+        if bins == 0 {
+            return res;
+        }
+        for timing in &self.timings {
+            let pos = (timing.diff as f64 + LIMIT_BAD) / (LIMIT_BAD * 2.) * bins as f64;
+            res[(pos.max(0.) as usize).min(bins - 1)] += 1;
+        }
+        res
+    }
+}
+
+/// Note kinds whose judgement depends on timing. Drag and flick notes are judged as soon as they
+/// are touched, so they carry no timing error.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HitKind {
+    Click,
+    Hold,
+}
+
+impl HitKind {
+    pub fn of(kind: &NoteKind) -> Option<Self> {
+        match kind {
+            NoteKind::Click => Some(Self::Click),
+            NoteKind::Hold { .. } => Some(Self::Hold),
+            NoteKind::Drag | NoteKind::Flick => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct HitTiming {
+    /// Time of the note in the chart
+    pub time: f32,
+    /// Timing error in seconds, negative for early hits
+    pub diff: f32,
+    pub kind: HitKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct TimingStats {
+    pub count: u32,
+    /// Mean timing error in seconds, negative when hitting early on average
+    pub mean: f32,
+    /// Standard deviation of timing errors in seconds
+    pub std: f32,
+}
+
+impl TimingStats {
+    pub fn new(diffs: impl IntoIterator<Item = f32>) -> Self {
+        let (mut count, mut sum, mut sum_sq) = (0, 0., 0.);
+        for diff in diffs {
+            let diff = diff as f64;
+            count += 1;
+            sum += diff;
+            sum_sq += diff * diff;
+        }
+        if count == 0 {
+            return Self::default();
+        }
+        let mean = sum / count as f64;
+        let var = (sum_sq / count as f64 - mean * mean).max(0.);
+        Self {
+            count,
+            mean: mean as f32,
+            std: var.sqrt() as f32,
+        }
+    }
+
+    /// The standard deviation in milliseconds, multiplied by 10.
+    pub fn unstable_rate(&self) -> f32 {
+        self.std * 10000.
+    }
+}
+
+/// Color of a timing error of `diff` seconds, by the judgement it falls into.
+pub fn timing_color(diff: f32) -> Color {
+    let diff = diff.abs() as f64;
+    if diff <= LIMIT_PERFECT {
+        Color::from_hex_rgb(0xfff59d)
+    } else if diff <= LIMIT_GOOD {
+        Color::from_hex_rgb(0x81d4fa)
+    } else {
+        Color::from_hex_rgb(0xffab91)
+    }
 }
 
 pub fn icon_index(score: u32, full_combo: bool) -> usize {
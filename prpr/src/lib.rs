@@ -4,11 +4,16 @@ pub mod core;
 pub mod dir;
 pub mod ext;
 pub mod fs;
+pub mod hud;
 pub mod info;
+pub mod input;
 pub mod judge;
 pub mod parse;
 pub mod particle;
+pub mod profile;
+pub mod quality;
 pub mod scene;
+pub mod stretch;
 pub mod task;
 pub mod time;
 pub mod ui;
@@ -2,7 +2,7 @@
 prpr_l10n::tl_file!("parser" ptl);
 
 mod extra;
-pub use extra::parse_extra;
+pub use extra::{check_extra_lines, lint_effects, parse_extra};
 
 mod pec;
 pub use pec::parse_pec;
@@ -11,12 +11,18 @@ mod pgr;
 pub use pgr::parse_phigros;
 
 mod rpe;
-pub use rpe::{lint, parse_rpe, RPE_HEIGHT, RPE_WIDTH};
+pub use rpe::{check_rpe_fields, lint, parse_rpe, RpeFieldIssue, RpeFieldStatus, RpeScope, RPE_HEIGHT, RPE_WIDTH};
 
 #[derive(Debug, Default)]
 pub struct ParseWarnings {
     pub has_new_speed_events: bool,
     pub has_attach_ui: bool,
+    /// Version found in the metadata of an RPE chart
+    pub rpe_version: Option<i32>,
+    /// Fields of an RPE chart that are unknown or not supported
+    pub rpe_fields: Vec<RpeFieldIssue>,
+    /// Problems found in the shaders of effects, by effect
+    pub shader_problems: Vec<String>,
 }
 
 pub(crate) fn process_lines(v: &mut [crate::core::JudgeLine]) {
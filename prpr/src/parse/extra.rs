@@ -2,15 +2,19 @@ use super::RPE_TWEEN_MAP;
 use anyhow::{Context, Result};
 use macroquad::prelude::{Color, Vec2};
 use serde::Deserialize;
-use std::{collections::HashMap, rc::Rc};
+use std::{collections::HashMap, rc::Rc, sync::Arc};
 
 use super::L10N_LOCAL;
 #[cfg(feature = "video")]
 use crate::core::Video;
 use crate::{
-    core::{Anim, BpmList, ChartExtra, ClampedTween, Effect, Keyframe, StaticTween, Triple, Tweenable, Uniform, EPS},
-    ext::ScaleType,
+    core::{
+        effect_package, validate_shader, Anim, BpmList, ChartExtra, ChartParticles, ClampedTween, Effect, EffectPackage, Keyframe, StaticTween,
+        Triple, Tweenable, Uniform, EPS,
+    },
+    ext::{SafeTexture, ScaleType},
     fs::FileSystem,
+    particle::{Emitter, ParticlePreset},
 };
 
 // serde is weird...
@@ -112,6 +116,13 @@ enum Variable {
     Color(ExtAnim<[u8; 4]>),
 }
 
+#[derive(Deserialize)]
+struct ExtPass {
+    shader: String,
+    #[serde(default)]
+    vars: HashMap<String, Variable>,
+}
+
 #[derive(Deserialize)]
 struct ExtEffect {
     start: Triple,
@@ -119,10 +130,42 @@ struct ExtEffect {
     shader: String,
     #[serde(default)]
     vars: HashMap<String, Variable>,
+    /// Further passes, each reading the output of the one before
+    #[serde(default)]
+    passes: Vec<ExtPass>,
+    /// Extra textures from the chart, by the name of their uniform
+    #[serde(default)]
+    textures: HashMap<String, String>,
+    /// Effects are applied from low to high order, and in the order they're listed if equal
+    #[serde(default)]
+    order: i32,
+    /// Judge lines the effect is limited to
+    #[serde(default)]
+    lines: Option<Vec<usize>>,
     #[serde(default)]
     global: bool,
 }
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExtPreset {
+    /// Path of a preset file in the chart
+    Path(String),
+    Inline(ParticlePreset),
+}
+
+#[derive(Deserialize)]
+struct ExtParticles {
+    start: Triple,
+    end: Triple,
+    preset: ExtPreset,
+    #[serde(default)]
+    position: ExtAnim<(f32, f32)>,
+    /// Judge line the position is relative to
+    #[serde(default)]
+    line: Option<usize>,
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize)]
 struct ExtVideo {
@@ -147,46 +190,139 @@ struct Extra {
     #[serde(default)]
     effects: Vec<ExtEffect>,
     #[serde(default)]
+    particles: Vec<ExtParticles>,
+    #[serde(default)]
     videos: Vec<ExtVideo>,
 }
 
+async fn load_shader(name: &str, package: Option<&EffectPackage>, fs: &mut dyn FileSystem) -> Result<String> {
+    Ok(if let Some(path) = name.strip_prefix('/') {
+        String::from_utf8(fs.load_file(path).await?).with_context(|| ptl!("shader-load-failed", "path" => path))?
+    } else if let Some(package) = package {
+        package.shader.clone()
+    } else {
+        Effect::get_preset(name)
+            .ok_or_else(|| ptl!(err "shader-not-found", "shader" => name))?
+            .to_owned()
+    })
+}
+
+/// Finds the effect package a pass refers to, unless it's a shader of the chart or a preset.
+fn pass_package(shader: &str) -> Option<Arc<EffectPackage>> {
+    if shader.starts_with('/') || Effect::get_preset(shader).is_some() {
+        None
+    } else {
+        effect_package(shader)
+    }
+}
+
+fn parse_vars(r: &mut BpmList, vars: HashMap<String, Variable>, package: Option<&EffectPackage>) -> Vec<Box<dyn Uniform>> {
+    // defaults of the package, unless the chart sets them
+    let mut result: Vec<Box<dyn Uniform>> = package
+        .iter()
+        .flat_map(|it| it.vars.iter())
+        .filter(|(name, _)| !vars.contains_key(*name))
+        .map(|(name, value)| value.uniform(name.clone()))
+        .collect();
+    result.extend(vars.into_iter().map(|(name, var)| -> Box<dyn Uniform> {
+        match var {
+            Variable::Float(events) => Box::new((name, events.into::<f32>(r, None))),
+            Variable::Vec2(events) => Box::new((name, events.into::<Vec2>(r, None))),
+            Variable::Color(events) => Box::new((name, events.into::<Color>(r, None))),
+        }
+    }));
+    result
+}
+
 async fn parse_effect(r: &mut BpmList, rpe: ExtEffect, fs: &mut dyn FileSystem) -> Result<Effect> {
+    if rpe.global && rpe.lines.is_some() {
+        ptl!(bail "effect-global-lines");
+    }
     let range = r.time(&rpe.start)..r.time(&rpe.end);
-    let vars = rpe
-        .vars
-        .into_iter()
-        .map(|(name, var)| -> Result<Box<dyn Uniform>> {
-            Ok(match var {
-                Variable::Float(events) => Box::new((name, events.into::<f32>(r, None))),
-                Variable::Vec2(events) => Box::new((name, events.into::<Vec2>(r, None))),
-                Variable::Color(events) => Box::new((name, events.into::<Color>(r, None))),
-            })
-        })
-        .collect::<Result<_>>()?;
-    let string;
-    Effect::new(
-        range,
-        if let Some(path) = rpe.shader.strip_prefix('/') {
-            string = String::from_utf8(fs.load_file(path).await?).with_context(|| ptl!("shader-load-failed", "path" => path))?;
-            &string
-        } else {
-            Effect::get_preset(&rpe.shader).ok_or_else(|| ptl!(err "shader-not-found", "shader" => rpe.shader))?
-        },
-        vars,
-        rpe.global,
-    )
+    let mut textures = Vec::new();
+    for (name, path) in rpe.textures {
+        let bytes = fs
+            .load_file(&path)
+            .await
+            .with_context(|| ptl!("texture-load-failed", "path" => path.clone()))?;
+        let image = image::load_from_memory(&bytes).with_context(|| ptl!("texture-load-failed", "path" => path))?;
+        textures.push((name, SafeTexture::from(image)));
+    }
+    let mut shaders = Vec::new();
+    let mut uniforms = Vec::new();
+    for pass in std::iter::once(ExtPass {
+        shader: rpe.shader,
+        vars: rpe.vars,
+    })
+    .chain(rpe.passes)
+    {
+        let package = pass_package(&pass.shader);
+        let shader = load_shader(&pass.shader, package.as_deref(), fs).await?;
+        uniforms.push(parse_vars(r, pass.vars, package.as_deref()));
+        shaders.push((pass.shader, shader));
+    }
+    let names = shaders.iter().map(|it| it.0.as_str()).collect::<Vec<_>>().join(" > ");
+    let passes = shaders.iter().map(|it| it.1.as_str()).zip(uniforms).collect();
+    let mut effect = Effect::chain(range, passes, textures, rpe.global).with_context(|| ptl!("shader-invalid", "shader" => names))?;
+    effect.lines = rpe.lines.map(|mut lines| {
+        lines.sort_unstable();
+        lines.dedup();
+        lines
+    });
+    Ok(effect)
+}
+
+async fn parse_particles(r: &mut BpmList, ext: ExtParticles, fs: &mut dyn FileSystem) -> Result<ChartParticles> {
+    let preset = match ext.preset {
+        ExtPreset::Path(path) => {
+            let source = String::from_utf8(
+                fs.load_file(&path)
+                    .await
+                    .with_context(|| ptl!("particles-load-failed", "path" => path.clone()))?,
+            )?;
+            ParticlePreset::parse(&source).with_context(|| ptl!("particles-load-failed", "path" => path))?
+        }
+        ExtPreset::Inline(preset) => {
+            preset.verify()?;
+            preset
+        }
+    };
+    let texture = preset.load_texture(fs).await?;
+    let emitter = Emitter::new(preset.build(texture.as_ref()));
+    Ok(ChartParticles::new(r.time(&ext.start)..r.time(&ext.end), emitter, texture, ext.position.into(r, None), ext.line))
 }
 
 pub async fn parse_extra(source: &str, fs: &mut dyn FileSystem) -> Result<ChartExtra> {
     let ext: Extra = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
     let mut r: BpmList = ext.bpm.into();
-    let mut effects = Vec::new();
+    let mut effects: Vec<Effect> = Vec::new();
     let mut global_effects = Vec::new();
-    for (id, effect) in ext.effects.into_iter().enumerate() {
-        (if effect.global { &mut global_effects } else { &mut effects }).push(
-            parse_effect(&mut r, effect, fs)
+    let mut ext_effects: Vec<_> = ext.effects.into_iter().enumerate().collect();
+    ext_effects.sort_by_key(|it| it.1.order);
+    for (id, effect) in ext_effects {
+        let effect = parse_effect(&mut r, effect, fs)
+            .await
+            .with_context(|| ptl!("effect-location", "id" => id))?;
+        if let Some(lines) = &effect.lines {
+            // a line can only be drawn onto one layer
+            let overlap = effects
+                .iter()
+                .filter_map(|it| it.lines.as_ref())
+                .filter(|it| *it != lines)
+                .find_map(|it| it.iter().find(|line| lines.contains(line)));
+            if let Some(line) = overlap {
+                let context = ptl!("effect-location", "id" => id);
+                return Err(ptl!(err "effect-lines-overlap", "line" => *line).context(context));
+            }
+        }
+        (if effect.global { &mut global_effects } else { &mut effects }).push(effect);
+    }
+    let mut particles = Vec::new();
+    for (id, ext) in ext.particles.into_iter().enumerate() {
+        particles.push(
+            parse_particles(&mut r, ext, fs)
                 .await
-                .with_context(|| ptl!("effect-location", "id" => id))?,
+                .with_context(|| ptl!("particles-location", "id" => id))?,
         );
     }
     #[cfg(feature = "video")]
@@ -214,7 +350,64 @@ pub async fn parse_extra(source: &str, fs: &mut dyn FileSystem) -> Result<ChartE
     Ok(ChartExtra {
         effects,
         global_effects,
+        particles,
         #[cfg(feature = "video")]
         videos,
     })
 }
+
+/// Checks the shaders of the effects in `source` with [`validate_shader`], returning the problems
+/// found in each effect.
+///
+/// Effects with such problems still load, as they may play fine on some devices, so these are only
+/// meant to be reported. Shaders that can't be loaded at all are left to [`parse_extra`].
+pub async fn lint_effects(source: &str, fs: &mut dyn FileSystem) -> Result<Vec<String>> {
+    let ext: Extra = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
+    let mut r: BpmList = ext.bpm.into();
+    let mut problems = Vec::new();
+    for (id, effect) in ext.effects.into_iter().enumerate() {
+        let textures: Vec<_> = effect.textures.into_keys().collect();
+        let passes = std::iter::once(ExtPass {
+            shader: effect.shader,
+            vars: effect.vars,
+        })
+        .chain(effect.passes);
+        for (index, pass) in passes.enumerate() {
+            let package = pass_package(&pass.shader);
+            let Ok(shader) = load_shader(&pass.shader, package.as_deref(), fs).await else {
+                continue;
+            };
+            let vars: Vec<_> = parse_vars(&mut r, pass.vars, package.as_deref())
+                .iter()
+                .map(|it| it.uniform_pair())
+                .collect();
+            if let Err(err) = validate_shader(&shader, &vars, &textures) {
+                let err = err
+                    .context(format!("In pass #{}", index + 1))
+                    .context(ptl!("effect-location", "id" => id));
+                problems.push(format!("{err:#}"));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Checks that the judge lines `extra` refers to exist, as the chart holding `lines` lines is only
+/// parsed after it.
+pub fn check_extra_lines(extra: &ChartExtra, lines: usize) -> Result<()> {
+    let missing = |line: usize| ptl!(err "extra-line-missing", "line" => line, "count" => lines);
+    // effects are reordered while parsed, so the missing line is all there is to point at
+    if let Some(line) = extra.effects.iter().filter_map(|it| it.lines.as_ref()?.last()).find(|it| **it >= lines) {
+        return Err(missing(*line));
+    }
+    for (id, particles) in extra.particles.iter().enumerate() {
+        if let Some(line) = particles.line.filter(|it| *it >= lines) {
+            return Err(missing(line).context(ptl!("particles-location", "id" => id)));
+        }
+    }
+    #[cfg(feature = "video")]
+    if let Some(attach) = extra.videos.iter().filter_map(|it| it.1.as_ref()).find(|it| it.line >= lines) {
+        return Err(missing(attach.line));
+    }
+    Ok(())
+}
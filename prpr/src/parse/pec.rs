@@ -1,4 +1,4 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use macroquad::color::WHITE;
 use std::{cell::RefCell, collections::HashMap};
 use tracing::warn;
@@ -13,14 +13,42 @@ use crate::{
     judge::{HitSound, JudgeStatus},
 };
 
-trait Take {
-    fn take_f32(&mut self) -> Result<f32>;
-    fn take_usize(&mut self) -> Result<usize>;
-    fn take_tween(&mut self) -> Result<TweenId>;
-    fn take_time(&mut self, r: &mut BpmList) -> Result<f64>;
+/// Errors listed at most in the report of a chart.
+const MAX_ERRORS: usize = 20;
+
+/// Whitespace-separated tokens of a line, keeping track of where the last one starts.
+struct Tokens<'a> {
+    line: &'a str,
+    rest: &'a str,
+    last: usize,
 }
 
-impl<'a, T: Iterator<Item = &'a str>> Take for T {
+impl<'a> Tokens<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { line, rest: line, last: 0 }
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.rest = self.rest.trim_start();
+        self.last = self.line.len() - self.rest.len();
+        if self.rest.is_empty() {
+            return None;
+        }
+        let end = self.rest.find(char::is_whitespace).unwrap_or(self.rest.len());
+        let (token, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        Some(token)
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.rest.split_whitespace().next()
+    }
+
+    /// The 1-based column of the last token taken, or of the end of the line if there was none.
+    fn column(&self) -> usize {
+        self.line[..self.last].chars().count() + 1
+    }
+
     fn take_f32(&mut self) -> Result<f32> {
         self.next()
             .ok_or_else(|| ptl!(err "unexpected-eol"))
@@ -45,6 +73,15 @@ impl<'a, T: Iterator<Item = &'a str>> Take for T {
             .with_context(|| ptl!("expected-tween"))
     }
 
+    /// Some editors leave out the easing of motion events at the end of a line.
+    fn take_tween_or(&mut self, default: TweenId) -> Result<TweenId> {
+        if self.peek().is_none() {
+            Ok(default)
+        } else {
+            self.take_tween()
+        }
+    }
+
     fn take_time(&mut self, r: &mut BpmList) -> Result<f64> {
         self.take_f32().map(|it| r.time_beats(it as f64))
     }
@@ -181,11 +218,69 @@ fn parse_judge_line(mut pec: PECJudgeLine, id: usize, max_time: f64) -> Result<J
     })
 }
 
+/// Applies a `#` (speed) or `&` (size) modifier to `note`.
+fn apply_modifier(note: &mut Note, modifier: &str, it: &mut Tokens) -> Result<()> {
+    let value = it.take_f32()?;
+    if modifier == "#" {
+        note.speed = value as f64;
+    } else if (value - 1.0).abs() >= EPS as f32 {
+        note.object.scale.0 = AnimFloat::fixed(value);
+    }
+    Ok(())
+}
+
+/// Combines errors found on several lines, given as `(line, column, error)`, into one.
+fn report(mut errors: Vec<(usize, usize, anyhow::Error)>) -> anyhow::Error {
+    errors.sort_by_key(|it| (it.0, it.1));
+    let count = errors.len();
+    let mut lines: Vec<String> = errors
+        .iter()
+        .take(MAX_ERRORS)
+        .map(|(lid, col, err)| {
+            let location = ptl!("line-location", "lid" => *lid, "col" => *col);
+            format!("{location} {err:#}")
+        })
+        .collect();
+    if count > MAX_ERRORS {
+        lines.push(ptl!("more-errors", "count" => count - MAX_ERRORS));
+    }
+    anyhow!(lines.join("\n")).context(ptl!("pec-errors", "count" => count))
+}
+
 pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
-    let mut offset = None;
-    let mut r = None;
-    let mut lines = Vec::new();
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+    let mut errors = Vec::new();
+    // bpm events can appear anywhere in the file, yet every other command needs them to tell time
     let mut bpm_list = Vec::new();
+    for (id, line) in source.lines().enumerate() {
+        let mut it = Tokens::new(line);
+        if it.next() != Some("bp") {
+            continue;
+        }
+        let mut parse_bp = || -> Result<(f64, f64)> {
+            let bp = (it.take_f32()? as f64, it.take_f32()? as f64);
+            if let Some(next) = it.next() {
+                ptl!(bail "unexpected-extra", "next" => next);
+            }
+            Ok(bp)
+        };
+        match parse_bp() {
+            Ok(bp) => bpm_list.push(bp),
+            Err(err) => errors.push((id + 1, it.column(), err)),
+        }
+    }
+    if bpm_list.is_empty() {
+        if errors.is_empty() {
+            ptl!(bail "no-bpm");
+        }
+        return Err(report(errors));
+    }
+    bpm_list.sort_by_key(|it| it.0.not_nan());
+    let mut r = BpmList::new(bpm_list);
+
+    let mut offset = 0.;
+    let mut has_offset = false;
+    let mut lines = Vec::new();
     let mut last_line = None;
     fn get_line(lines: &mut Vec<PECJudgeLine>, id: usize) -> &mut PECJudgeLine {
         if lines.len() <= id {
@@ -196,17 +291,6 @@ pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
         }
         &mut lines[id]
     }
-    fn ensure_bpm<'a>(r: &'a mut Option<BpmList>, bpm_list: &mut Vec<(f64, f64)>) -> &'a mut BpmList {
-        if r.is_none() {
-            *r = Some(BpmList::new(std::mem::take(bpm_list)));
-        }
-        r.as_mut().unwrap()
-    }
-    macro_rules! bpm {
-        () => {
-            ensure_bpm(&mut r, &mut bpm_list)
-        };
-    }
     macro_rules! last_note {
         () => {{
             let Some(last_line) = last_line else {
@@ -215,30 +299,26 @@ pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
             lines[last_line].notes.last_mut().unwrap()
         }};
     }
-    let mut inner = |line: &str| -> Result<()> {
-        let mut it = line.split_whitespace();
-        if offset.is_none() {
-            offset = Some(it.take_f32()? / 1000. - 0.15);
+    let mut inner = |it: &mut Tokens| -> Result<()> {
+        if it.peek().is_none() {
+            return Ok(());
+        }
+        if !has_offset {
+            // the first line that is not blank holds the offset
+            has_offset = true;
+            offset = it.take_f32()? / 1000. - 0.15;
         } else {
-            let Some(cmd) = it.next() else {
-                return Ok(());
-            };
+            let cmd = it.next().unwrap();
             let cs: Vec<_> = cmd.chars().collect();
             if cs.len() > 2 {
                 ptl!(bail "unknown-command", "cmd" => cmd);
             }
+            let r = &mut r;
             match cs[0] {
-                'b' if cmd == "bp" => {
-                    if r.is_some() {
-                        ptl!(bail "bp-error");
-                    }
-                    bpm_list.push((it.take_f32()? as f64, it.take_f32()? as f64));
-                }
+                // already read before
+                'b' if cmd == "bp" => return Ok(()),
                 'n' if cs.len() == 2 && ('1'..='4').contains(&cs[1]) => {
-                    let r = bpm!();
                     let line = it.take_usize()?;
-                    last_line = Some(line);
-                    let line = get_line(&mut lines, line);
                     let time = it.take_time(r)?;
                     let kind = match cs[1] {
                         '1' => NoteKind::Click,
@@ -251,15 +331,20 @@ pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
                         _ => unreachable!(),
                     };
                     let position_x = it.take_f32()? / 1024.;
-                    // TODO we don't understand..
-                    let above = it.take_usize()? == 1;
+                    // PhiEditer writes 1 for notes falling from above and 2 for those from below,
+                    // some older charts use 0 for the latter
+                    let above = match it.take_usize()? {
+                        1 => true,
+                        0 | 2 => false,
+                        _ => ptl!(bail "expected-12"),
+                    };
                     let fake = match it.take_usize()? {
                         0 => false,
                         1 => true,
                         _ => ptl!(bail "expected-01"),
                     };
                     let hitsound = HitSound::default_from_kind(&kind);
-                    line.notes.push(Note {
+                    let mut note = Note {
                         object: Object {
                             translation: AnimVector(AnimFloat::fixed(position_x), AnimFloat::default()),
                             ..Default::default()
@@ -277,30 +362,19 @@ pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
                         color: WHITE,
                         fx_color: None,
                         judge_area: 1.,
-                    });
-                    if it.next() == Some("#") {
-                        last_note!().speed = it.take_f32()? as f64;
-                    }
-                    if it.next() == Some("&") {
-                        let note = last_note!();
-                        let size = it.take_f32()?;
-                        if (size - 1.0).abs() >= EPS as f32 {
-                            note.object.scale.0 = AnimFloat::fixed(size);
-                        }
+                    };
+                    // speed and size may follow on the same line, in any order
+                    while let Some(modifier @ ("#" | "&")) = it.peek() {
+                        it.next();
+                        apply_modifier(&mut note, modifier, it)?;
                     }
+                    get_line(&mut lines, line).notes.push(note);
+                    last_line = Some(line);
                 }
-                '#' if cs.len() == 1 => {
-                    last_note!().speed = it.take_f32()? as f64;
-                }
-                '&' if cs.len() == 1 => {
-                    let note = last_note!();
-                    let size = it.take_f32()?;
-                    if (size - 1.0).abs() >= EPS as f32 {
-                        note.object.scale.0 = AnimFloat::fixed(size);
-                    }
+                '#' | '&' if cs.len() == 1 => {
+                    apply_modifier(last_note!(), cmd, it)?;
                 }
                 'c' if cs.len() == 2 => {
-                    let r = bpm!();
                     let line = get_line(&mut lines, it.take_usize()?);
                     let time = it.take_time(r)?;
                     match cs[1] {
@@ -323,13 +397,13 @@ pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
                             let end_time = it.take_time(r)?;
                             let x = it.take_f32()?;
                             let y = it.take_f32()?;
-                            let t = it.take_tween()?;
+                            let t = it.take_tween_or(2)?;
                             line.move_events.0.push(PECEvent::new(time, end_time, x, t));
                             line.move_events.1.push(PECEvent::new(time, end_time, y, t));
                         }
                         'r' => {
                             line.rotate_events
-                                .push(PECEvent::new(time, it.take_time(r)?, -it.take_f32()?, it.take_tween()?));
+                                .push(PECEvent::new(time, it.take_time(r)?, -it.take_f32()?, it.take_tween_or(2)?));
                         }
                         'f' => {
                             line.alpha_events.push(PECEvent::new(time, it.take_time(r)?, it.take_f32()?, 2));
@@ -346,7 +420,13 @@ pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
         Ok(())
     };
     for (id, line) in source.lines().enumerate() {
-        inner(line).with_context(|| ptl!("line-location", "lid" => id + 1))?;
+        let mut it = Tokens::new(line);
+        if let Err(err) = inner(&mut it) {
+            errors.push((id + 1, it.column(), err));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(report(errors));
     }
     let max_time = *lines
         .iter()
@@ -371,11 +451,10 @@ pub fn parse_pec(source: &str, extra: ChartExtra) -> Result<Chart> {
         .map(|(id, line)| parse_judge_line(line, id, max_time).with_context(|| ptl!("judge-line-location", "jlid" => id)))
         .collect::<Result<Vec<_>>>()?;
     process_lines(&mut lines);
-    ensure_bpm(&mut r, &mut bpm_list);
     Ok(Chart::new(
-        offset.unwrap(),
+        offset,
         lines,
-        r.unwrap(),
+        r,
         ChartSettings {
             pe_alpha_extension: true,
             ..Default::default()
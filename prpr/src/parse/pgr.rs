@@ -44,7 +44,9 @@ pub struct PgrNote {
     position_x: f32,
     hold_time: f64,
     speed: f32,
+    // absent from some version 1 charts
     #[allow(unused)]
+    #[serde(default)]
     floor_position: f32,
 }
 
@@ -67,7 +69,9 @@ struct PgrJudgeLine {
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct PgrChart {
-    format_version: u32,
+    /// Missing from some early charts, see [`infer_format_version`]
+    #[serde(default)]
+    format_version: Option<u32>,
     offset: f32,
     judge_line_list: Vec<PgrJudgeLine>,
 }
@@ -87,6 +91,14 @@ macro_rules! validate_events {
 
 fn parse_speed_events(r: f64, mut pgr: Vec<PgrSpeedEvent>, max_time: f64) -> Result<(AnimFloat, AnimFloat)> {
     validate_events!(pgr);
+    if pgr.is_empty() {
+        // lines without notes often come without speed events as well
+        pgr.push(PgrSpeedEvent {
+            start_time: 0.,
+            end_time: max_time / r,
+            value: 1.,
+        });
+    }
     //assert_eq!(pgr[0].start_time, 0.0);
     if pgr[0].start_time != 0. {
         pgr[0].start_time = 0.
@@ -244,7 +256,7 @@ fn parse_judge_line(pgr: PgrJudgeLine, max_time: f64, format_version: u32) -> Re
                 match format_version {
                     1 => parse_move_events_fv1(r, pgr.move_events).with_context(|| ptl!("move-events-parse-failed"))?,
                     3 => parse_move_events(r, pgr.move_events).with_context(|| ptl!("move-events-parse-failed"))?,
-                    _ => ptl!(bail "unknown-format-version"),
+                    _ => ptl!(bail "unknown-format-version", "version" => format_version),
                 }
             },
             ..Default::default()
@@ -265,9 +277,26 @@ fn parse_judge_line(pgr: PgrJudgeLine, max_time: f64, format_version: u32) -> Re
     })
 }
 
+/// Guesses the format version of a chart that doesn't state it.
+///
+/// Version 1 packs both coordinates of a move event into `start` and `end` (`x * 1000 + y`), while
+/// version 3 keeps y in `start2` and `end2`. Version 3 is assumed unless the values are clearly
+/// packed, as a version 3 chart may well keep every line at y = 0.
+fn infer_format_version(pgr: &PgrChart) -> u32 {
+    let events = || pgr.judge_line_list.iter().flat_map(|line| line.move_events.iter());
+    let separate_y = events().any(|e| e.start2 != 0. || e.end2 != 0.);
+    // x is at least 1 for all but the leftmost positions, making packed values large
+    let packed = events().any(|e| e.start.abs() >= 1000. || e.end.abs() >= 1000.);
+    if packed && !separate_y {
+        1
+    } else {
+        3
+    }
+}
+
 pub fn parse_phigros(source: &str, extra: ChartExtra) -> Result<Chart> {
     let pgr: PgrChart = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
-    let format_version = pgr.format_version;
+    let format_version = pgr.format_version.unwrap_or_else(|| infer_format_version(&pgr));
     let max_time = *pgr
         .judge_line_list
         .iter()
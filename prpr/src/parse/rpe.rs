@@ -43,6 +43,14 @@ fn i32_one() -> i32 {
     1
 }
 
+fn u16_255() -> u16 {
+    255
+}
+
+fn f64_inf() -> f64 {
+    f64::INFINITY
+}
+
 fn rpe_version_default() -> i32 {
     160
 }
@@ -149,12 +157,19 @@ struct RPENote {
     start_time: Triple,
     end_time: Triple,
     position_x: f32,
+    // charts exported by other editors tend to leave out the fields below
+    #[serde(default)]
     y_offset: f32,
-    alpha: u16,               // some alpha has 256...
-    hitsound: Option<String>, // TODO implement this feature
+    #[serde(default = "u16_255")]
+    alpha: u16, // some alpha has 256...
+    hitsound: Option<String>,
+    #[serde(default = "f32_one")]
     size: f32,
+    #[serde(default = "f32_one")]
     speed: f32,
+    #[serde(default)]
     is_fake: u8,
+    #[serde(default = "f64_inf")]
     visible_time: f64,
     #[serde(default)]
     tint: Option<[u8; 3]>,
@@ -167,8 +182,6 @@ struct RPENote {
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct RPEJudgeLine {
-    // TODO group
-    // TODO bpmfactor
     #[serde(rename = "Name")]
     name: String,
     #[serde(rename = "Texture")]
@@ -184,6 +197,9 @@ struct RPEJudgeLine {
     z_order: i32,
     #[serde(rename = "attachUI")]
     attach_ui: Option<UIElement>,
+    /// The BPM of this line is the chart's divided by this factor
+    #[serde(rename = "bpmfactor", default = "f32_one")]
+    bpm_factor: f32,
 
     #[serde(default)]
     pos_control: Vec<RPECtrlEvent>,
@@ -864,7 +880,17 @@ pub async fn parse_rpe(source: &str, fs: &mut dyn FileSystem, extra: ChartExtra,
         SpeedEasingMode::Legacy
     };
     let bezier_map = get_bezier_map(&rpe);
-    let mut r = BpmList::new(rpe.bpm_list.into_iter().map(|it| (it.start_time.beats(), it.bpm)).collect());
+    let bpms: Vec<_> = rpe.bpm_list.iter().map(|it| (it.start_time.beats(), it.bpm)).collect();
+    let mut r = BpmList::new(bpms.clone());
+    // lines with a BPM factor count their beats at a BPM of their own
+    let mut line_bpms: Vec<Option<BpmList>> = rpe
+        .judge_line_list
+        .iter()
+        .map(|line| {
+            (line.bpm_factor > 0. && (line.bpm_factor - 1.).abs() >= EPS as f32)
+                .then(|| BpmList::new(bpms.iter().map(|(beats, bpm)| (*beats, bpm / line.bpm_factor as f64)).collect()))
+        })
+        .collect();
     fn vec<T>(v: &Option<Vec<T>>) -> impl Iterator<Item = &T> {
         v.iter().flat_map(|it| it.iter())
     }
@@ -873,7 +899,9 @@ pub async fn parse_rpe(source: &str, fs: &mut dyn FileSystem, extra: ChartExtra,
     let max_time = *rpe
         .judge_line_list
         .iter()
-        .map(|line| {
+        .zip(line_bpms.iter_mut())
+        .map(|(line, line_r)| {
+            let r = line_r.as_mut().unwrap_or(&mut r);
             line.notes.as_ref().map(|notes| {
                 notes
                     .iter()
@@ -903,12 +931,22 @@ pub async fn parse_rpe(source: &str, fs: &mut dyn FileSystem, extra: ChartExtra,
     // don't want to add a whole crate for a mere join_all...
     let mut lines = Vec::new();
     let mut line_texture_map = HashMap::new();
-    for (id, rpe) in rpe.judge_line_list.into_iter().enumerate() {
+    for ((id, rpe), line_r) in rpe.judge_line_list.into_iter().enumerate().zip(line_bpms.iter_mut()) {
         let name = rpe.name.clone();
         lines.push(
-            parse_judge_line(&mut r, rpe, max_time, speed_mode, fs, use_rpe_170_speed, &bezier_map, &mut hitsounds, &mut line_texture_map)
-                .await
-                .with_context(move || ptl!("judge-line-location-name", "jlid" => id, "name" => name))?,
+            parse_judge_line(
+                line_r.as_mut().unwrap_or(&mut r),
+                rpe,
+                max_time,
+                speed_mode,
+                fs,
+                use_rpe_170_speed,
+                &bezier_map,
+                &mut hitsounds,
+                &mut line_texture_map,
+            )
+            .await
+            .with_context(move || ptl!("judge-line-location-name", "jlid" => id, "name" => name))?,
         );
     }
     fn has_cycle(line: &JudgeLine, lines: &[JudgeLine], visited: &mut Vec<usize>) -> Option<usize> {
@@ -932,6 +970,205 @@ pub async fn parse_rpe(source: &str, fs: &mut dyn FileSystem, extra: ChartExtra,
     Ok(Chart::new(rpe.meta.offset as f32 / 1000.0, lines, r, ChartSettings::default(), extra, hitsounds))
 }
 
+/// Where a field of an RPE chart was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpeScope {
+    Chart,
+    Meta,
+    Line(usize),
+    /// Events of a judge line
+    Events(usize),
+    /// Notes of a judge line
+    Notes(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpeFieldStatus {
+    /// Not a field of any RPE version we know of
+    Unknown,
+    /// A field RPE renders but we don't, so the chart may look different
+    Unsupported,
+}
+
+#[derive(Clone, Debug)]
+pub struct RpeFieldIssue {
+    pub scope: RpeScope,
+    pub field: String,
+    pub status: RpeFieldStatus,
+    /// How many notes or events have the field, or 1 for other scopes
+    pub count: usize,
+    /// Index of the first note or event (counted across layers) that has the field
+    pub first: usize,
+}
+
+struct RpeFields {
+    supported: &'static [&'static str],
+    /// Only used by RPE itself while editing
+    ignored: &'static [&'static str],
+    unsupported: &'static [&'static str],
+}
+
+const CHART_FIELDS: RpeFields = RpeFields {
+    supported: &["META", "BPMList", "judgeLineList"],
+    ignored: &["judgeLineGroup", "multiLineString", "multiScale", "chartTime", "xybind"],
+    unsupported: &[],
+};
+
+const META_FIELDS: RpeFields = RpeFields {
+    supported: &["offset", "RPEVersion"],
+    ignored: &[
+        "background",
+        "charter",
+        "composer",
+        "id",
+        "illustration",
+        "level",
+        "name",
+        "song",
+        "duration",
+    ],
+    unsupported: &[],
+};
+
+const LINE_FIELDS: RpeFields = RpeFields {
+    supported: &[
+        "Name",
+        "Texture",
+        "father",
+        "rotateWithFather",
+        "eventLayers",
+        "extended",
+        "notes",
+        "isCover",
+        "zOrder",
+        "attachUI",
+        "bpmfactor",
+        "posControl",
+        "sizeControl",
+        "alphaControl",
+        "yControl",
+    ],
+    ignored: &["Group", "numOfNotes", "isGif"],
+    unsupported: &["skewControl", "anchor"],
+};
+
+const LAYER_FIELDS: RpeFields = RpeFields {
+    supported: &["alphaEvents", "moveXEvents", "moveYEvents", "rotateEvents", "speedEvents"],
+    ignored: &[],
+    unsupported: &[],
+};
+
+const EXTENDED_FIELDS: RpeFields = RpeFields {
+    supported: &[
+        "colorEvents",
+        "textEvents",
+        "scaleXEvents",
+        "scaleYEvents",
+        "inclineEvents",
+        "paintEvents",
+        "gifEvents",
+    ],
+    ignored: &[],
+    unsupported: &[],
+};
+
+const EVENT_FIELDS: RpeFields = RpeFields {
+    supported: &[
+        "easingLeft",
+        "easingRight",
+        "bezier",
+        "bezierPoints",
+        "easingType",
+        "start",
+        "end",
+        "startTime",
+        "endTime",
+    ],
+    ignored: &["linkgroup"],
+    unsupported: &[],
+};
+
+const NOTE_FIELDS: RpeFields = RpeFields {
+    supported: &[
+        "type",
+        "above",
+        "startTime",
+        "endTime",
+        "positionX",
+        "yOffset",
+        "alpha",
+        "hitsound",
+        "size",
+        "speed",
+        "isFake",
+        "visibleTime",
+        "tint",
+        "tintHitEffects",
+        "judgeArea",
+    ],
+    ignored: &[],
+    unsupported: &[],
+};
+
+fn check_fields(value: &serde_json::Value, fields: &RpeFields, scope: RpeScope, index: usize, issues: &mut Vec<RpeFieldIssue>) {
+    let Some(object) = value.as_object() else {
+        return;
+    };
+    for key in object.keys().map(String::as_str) {
+        let status = if fields.unsupported.contains(&key) {
+            RpeFieldStatus::Unsupported
+        } else if fields.supported.contains(&key) || fields.ignored.contains(&key) {
+            continue;
+        } else {
+            RpeFieldStatus::Unknown
+        };
+        if let Some(issue) = issues.iter_mut().find(|it| it.scope == scope && it.field == key) {
+            issue.count += 1;
+        } else {
+            issues.push(RpeFieldIssue {
+                scope,
+                field: key.to_owned(),
+                status,
+                count: 1,
+                first: index,
+            });
+        }
+    }
+}
+
+/// Checks an RPE chart strictly, reporting every field that [`parse_rpe`] would ignore apart from
+/// those only RPE's editor cares about.
+pub fn check_rpe_fields(source: &str) -> Result<Vec<RpeFieldIssue>> {
+    let chart: serde_json::Value = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
+    let mut issues = Vec::new();
+    check_fields(&chart, &CHART_FIELDS, RpeScope::Chart, 0, &mut issues);
+    check_fields(&chart["META"], &META_FIELDS, RpeScope::Meta, 0, &mut issues);
+    let lines = chart["judgeLineList"].as_array().map_or(&[][..], Vec::as_slice);
+    for (id, line) in lines.iter().enumerate() {
+        check_fields(line, &LINE_FIELDS, RpeScope::Line(id), 0, &mut issues);
+        let scope = RpeScope::Events(id);
+        let layers = line["eventLayers"].as_array().into_iter().flatten().map(|it| (it, &LAYER_FIELDS));
+        let mut index = 0;
+        for (layer, fields) in layers.chain(std::iter::once((&line["extended"], &EXTENDED_FIELDS))) {
+            check_fields(layer, fields, scope, 0, &mut issues);
+            for event in layer
+                .as_object()
+                .into_iter()
+                .flat_map(|it| it.values())
+                .filter_map(|it| it.as_array())
+                .flatten()
+            {
+                check_fields(event, &EVENT_FIELDS, scope, index, &mut issues);
+                index += 1;
+            }
+        }
+        for (index, note) in line["notes"].as_array().into_iter().flatten().enumerate() {
+            check_fields(note, &NOTE_FIELDS, RpeScope::Notes(id), index, &mut issues);
+        }
+    }
+    Ok(issues)
+}
+
 pub async fn lint(source: &str) -> Result<ParseWarnings> {
     let rpe: RPEChart = serde_json::from_str(source).with_context(|| ptl!("json-parse-failed"))?;
     let has_new_speed_events = rpe
@@ -946,5 +1183,67 @@ pub async fn lint(source: &str) -> Result<ParseWarnings> {
     Ok(ParseWarnings {
         has_new_speed_events,
         has_attach_ui,
+        rpe_version: Some(rpe.meta.rpe_version),
+        rpe_fields: check_rpe_fields(source)?,
+        ..Default::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::{self, Visitor};
+    use std::collections::HashSet;
+
+    /// Takes note of the fields a struct asks for instead of deserializing anything.
+    struct FieldNames(&'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for &mut FieldNames {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> std::result::Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            _visitor: V,
+        ) -> std::result::Result<V::Value, Self::Error> {
+            self.0 = fields;
+            Err(de::Error::custom("fields taken"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+            unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    fn serde_fields<T: for<'de> Deserialize<'de>>() -> HashSet<&'static str> {
+        let mut names = FieldNames(&[]);
+        let _ = T::deserialize(&mut names);
+        names.0.iter().copied().collect()
+    }
+
+    #[test]
+    fn fields_match_serde() {
+        for (name, fields, serde) in [
+            ("chart", &CHART_FIELDS, serde_fields::<RPEChart>()),
+            ("meta", &META_FIELDS, serde_fields::<RPEMetadata>()),
+            ("line", &LINE_FIELDS, serde_fields::<RPEJudgeLine>()),
+            ("layer", &LAYER_FIELDS, serde_fields::<RPEEventLayer>()),
+            ("extended", &EXTENDED_FIELDS, serde_fields::<RPEExtendedEvents>()),
+            ("event", &EVENT_FIELDS, serde_fields::<RPEEvent>()),
+            ("note", &NOTE_FIELDS, serde_fields::<RPENote>()),
+        ] {
+            assert!(!serde.is_empty(), "{name}: no fields found");
+            let supported: HashSet<_> = fields.supported.iter().copied().collect();
+            assert_eq!(supported, serde, "{name}: supported fields differ from what is parsed");
+            for field in fields.ignored.iter().chain(fields.unsupported) {
+                assert!(!serde.contains(field), "{name}: `{field}` is parsed, but listed as not supported");
+            }
+        }
+    }
+}
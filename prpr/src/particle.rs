@@ -8,17 +8,53 @@
 //! 3. clippy
 //! 4. time can be customized by input argument
 //! 5. Remove EmittersCache
+//! 6. serde support, and presets loaded from files
+//! 7. Emitter::clear
 
+use crate::{ext::SafeTexture, fs::FileSystem};
+use anyhow::{bail, Context as _, Result};
 use macroquad::prelude::*;
 use macroquad::window::miniquad::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Vectors as `[x, y]`.
+mod vec2_serde {
+    use macroquad::prelude::Vec2;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Vec2, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_array().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec2, D::Error> {
+        Ok(Vec2::from_array(<[f32; 2]>::deserialize(deserializer)?))
+    }
+}
+
+/// Colors as `[r, g, b, a]`, each from 0 to 1.
+mod color_serde {
+    use macroquad::prelude::Color;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [value.r, value.g, value.b, value.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Color::new(r, g, b, a))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum Interpolation {
     Linear,
     Bezier,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Curve {
     /// Key points for building a curve
     pub points: Vec<(f32, f32)>,
@@ -81,17 +117,22 @@ impl Default for Curve {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
 pub enum EmissionShape {
     Point,
     Rect { width: f32, height: f32 },
     Sphere { radius: f32 },
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ColorCurve {
+    #[serde(with = "color_serde")]
     pub start: Color,
+    #[serde(with = "color_serde")]
     pub mid: Color,
+    #[serde(with = "color_serde")]
     pub end: Color,
 }
 
@@ -105,7 +146,8 @@ impl Default for ColorCurve {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct EmitterConfig {
     /// If false - particles spawns at position supplied to .draw(), but afterwards lives in current camera coordinate system.
     /// If false particles use coordinate system originated to the emitter draw position
@@ -131,6 +173,7 @@ pub struct EmitterConfig {
     /// If its a "one-shot" emitter, emitting will switch to false after active emission cycle.
     pub emitting: bool,
     /// Unit vector specifying emission direction.
+    #[serde(with = "vec2_serde")]
     pub initial_direction: Vec2,
     /// Angle from 0 to "2 * Pi" for random fluctuation for direction vector.
     pub initial_direction_spread: f32,
@@ -168,14 +211,17 @@ pub struct EmitterConfig {
     /// Particles rendering mode.
     pub blend_mode: BlendMode,
 
+    #[serde(with = "color_serde")]
     pub base_color: Color,
     /// How particles should change base color along the lifetime.
     pub colors_curve: ColorCurve,
 
     /// Gravity applied to each individual particle.
+    #[serde(with = "vec2_serde")]
     pub gravity: Vec2,
 
     /// Particle texture. If none particles going to be white squares.
+    #[serde(skip)]
     pub texture: Option<Texture2D>,
 
     /// For animated texture specify spritesheet layout.
@@ -183,6 +229,7 @@ pub struct EmitterConfig {
     pub atlas: Option<AtlasConfig>,
 
     /// Custom material used to shade each particle.
+    #[serde(skip)]
     pub material: Option<ParticleMaterial>,
 
     /// If none particles will be rendered directly to the screen.
@@ -190,6 +237,7 @@ pub struct EmitterConfig {
     /// will be rendered to the screen.
     /// This will allows some effects affecting particles as a whole.
     /// NOTE: this is not really implemented and now Some will just make hardcoded downscaling
+    #[serde(skip)]
     pub post_processing: Option<PostProcessing>,
 }
 
@@ -211,7 +259,8 @@ impl EmissionShape {
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct PostProcessing;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
 pub enum ParticleShape {
     Rectangle { aspect_ratio: f32 },
     Circle { subdivisions: u32 },
@@ -293,7 +342,8 @@ impl ParticleMaterial {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum BlendMode {
     /// Colors of overlapped particles will be blended by alpha channel.
     Alpha,
@@ -312,11 +362,15 @@ impl BlendMode {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AtlasConfig {
+    #[serde(rename = "columns")]
     n: u16,
+    #[serde(rename = "rows")]
     m: u16,
+    #[serde(rename = "start")]
     start_index: u16,
+    #[serde(rename = "end")]
     end_index: u16,
 }
 
@@ -380,6 +434,82 @@ impl Default for EmitterConfig {
     }
 }
 
+/// An [`EmitterConfig`] stored as YAML or JSON, like the hit particles of a resource pack or the
+/// particles placed by a chart. The texture is given by its path.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ParticlePreset {
+    #[serde(flatten)]
+    pub config: EmitterConfig,
+    /// Path of the particle texture, relative to the pack or chart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<String>,
+}
+
+impl ParticlePreset {
+    pub fn parse(source: &str) -> Result<Self> {
+        let preset: Self = serde_yaml::from_str(source)?;
+        preset.verify()?;
+        Ok(preset)
+    }
+
+    /// Rejects configs the emitter can't handle.
+    pub fn verify(&self) -> Result<()> {
+        let config = &self.config;
+        if !(config.lifetime > 0.) {
+            bail!("lifetime must be positive");
+        }
+        if config.amount as usize > Emitter::MAX_PARTICLES {
+            bail!("amount must be at most {}", Emitter::MAX_PARTICLES);
+        }
+        if let Some(curve) = &config.size_curve {
+            if curve.interpolation != Interpolation::Linear {
+                bail!("only linear size curves are supported");
+            }
+            if curve.resolution == 0 || curve.points.len() < 2 || curve.points[0].0 > 0. || curve.points.windows(2).any(|it| it[0].0 >= it[1].0) {
+                bail!("size curve must start at 0, increase and have a positive resolution");
+            }
+        }
+        match &config.shape {
+            ParticleShape::Rectangle { .. } => {}
+            ParticleShape::Circle { subdivisions } => {
+                if !(3..=256).contains(subdivisions) {
+                    bail!("circles must have 3 to 256 subdivisions");
+                }
+            }
+            ParticleShape::CustomMesh { vertices, indices } => {
+                // every vertex has a position, uv and color
+                let count = vertices.len() / 9;
+                if count == 0 || vertices.len() % 9 != 0 || indices.len() % 3 != 0 || indices.iter().any(|it| *it as usize >= count) {
+                    bail!("invalid custom mesh");
+                }
+            }
+        }
+        if let Some(atlas) = &config.atlas {
+            if atlas.n == 0 || atlas.m == 0 || atlas.start_index >= atlas.end_index || atlas.end_index as u32 > atlas.n as u32 * atlas.m as u32 {
+                bail!("invalid atlas");
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads the texture of the preset from `fs`, if it has one.
+    pub async fn load_texture(&self, fs: &mut dyn FileSystem) -> Result<Option<SafeTexture>> {
+        let Some(path) = &self.texture else {
+            return Ok(None);
+        };
+        let bytes = fs.load_file(path).await.with_context(|| format!("Missing {path}"))?;
+        Ok(Some(image::load_from_memory(&bytes).with_context(|| format!("Invalid {path}"))?.into()))
+    }
+
+    /// Builds the config of an emitter, given the texture loaded by [`Self::load_texture`].
+    pub fn build(&self, texture: Option<&SafeTexture>) -> EmitterConfig {
+        EmitterConfig {
+            texture: texture.map(|it| **it),
+            ..self.config.clone()
+        }
+    }
+}
+
 #[repr(C)]
 struct GpuParticle {
     pos: Vec4,
@@ -717,6 +847,15 @@ impl Emitter {
         self.bindings.vertex_buffers[1].update(ctx, &self.gpu_particles[..]);
     }
 
+    /// Removes all particles, and restarts the emission cycle.
+    pub fn clear(&mut self) {
+        self.gpu_particles.clear();
+        self.cpu_counterpart.clear();
+        self.particles_spawned = 0;
+        self.last_emit_time = 0.0;
+        self.time_passed = 0.0;
+    }
+
     /// Immediately emit N particles, ignoring "emitting" and "amount" params of EmitterConfig
     pub fn emit(&mut self, pos: Vec2, n: usize) {
         for _ in 0..n {
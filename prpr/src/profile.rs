@@ -0,0 +1,313 @@
+//! Frame-time profiler of the game.
+//!
+//! While enabled, a [`span`] times the code running until it's dropped and files it under a
+//! [`Subsystem`]. Draws are batched and only submitted to the GPU when flushed, so a span of a
+//! render phase measures building its geometry on the CPU, not the GPU work.
+//!
+//! The times of the last frames feed the overlay drawn by [`render_overlay`], and every span is
+//! kept for a trace in the Chrome trace event format, which `chrome://tracing` and Perfetto open.
+
+use crate::ui::Ui;
+use anyhow::Result;
+use macroquad::prelude::*;
+use serde_json::json;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Frames shown in the graph
+const HISTORY: usize = 240;
+/// Spans kept for the trace, older ones are dropped
+const MAX_EVENTS: usize = 1 << 18;
+/// Frame time filling the height of the graph
+const GRAPH_SCALE: f32 = 1. / 30.;
+
+static TRACE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Subsystem {
+    Judge,
+    /// Updating lines, notes and their animations
+    Chart,
+    /// Drawing lines and notes
+    Render,
+    /// Submitting the batched notes
+    Notes,
+    Effects,
+    Video,
+    Particles,
+    /// Laying out and uploading glyphs
+    Text,
+    Hud,
+}
+
+impl Subsystem {
+    pub const ALL: [Self; 9] = [
+        Self::Judge,
+        Self::Chart,
+        Self::Render,
+        Self::Notes,
+        Self::Effects,
+        Self::Video,
+        Self::Particles,
+        Self::Text,
+        Self::Hud,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Judge => "judge",
+            Self::Chart => "chart",
+            Self::Render => "render",
+            Self::Notes => "notes",
+            Self::Effects => "effects",
+            Self::Video => "video",
+            Self::Particles => "particles",
+            Self::Text => "text",
+            Self::Hud => "hud",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Judge => Color::from_rgba(239, 83, 80, 255),
+            Self::Chart => Color::from_rgba(255, 167, 38, 255),
+            Self::Render => Color::from_rgba(255, 238, 88, 255),
+            Self::Notes => Color::from_rgba(156, 204, 101, 255),
+            Self::Effects => Color::from_rgba(38, 198, 218, 255),
+            Self::Video => Color::from_rgba(66, 165, 245, 255),
+            Self::Particles => Color::from_rgba(171, 71, 188, 255),
+            Self::Text => Color::from_rgba(236, 64, 122, 255),
+            Self::Hud => Color::from_rgba(141, 110, 99, 255),
+        }
+    }
+}
+
+struct Event {
+    name: &'static str,
+    /// Seconds since the profiler was enabled
+    start: f64,
+    duration: f64,
+}
+
+/// Time of a frame, and the part of it spent in each subsystem excluding nested spans.
+#[derive(Clone)]
+struct FrameTimes {
+    total: f32,
+    spans: [f32; Subsystem::ALL.len()],
+}
+
+#[derive(Default)]
+struct Profiler {
+    enabled: bool,
+    epoch: f64,
+    frame_start: Option<f64>,
+    current: [f32; Subsystem::ALL.len()],
+    /// Open spans, with the time spent in the ones nested in them
+    stack: Vec<(Subsystem, f64, f64)>,
+    frames: VecDeque<FrameTimes>,
+    events: VecDeque<Event>,
+}
+
+impl Profiler {
+    fn record(&mut self, event: Event) {
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+}
+
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::default();
+}
+
+fn now() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        web_sys::window().unwrap().performance().unwrap().now() / 1000.
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::{sync::OnceLock, time::Instant};
+        static START: OnceLock<Instant> = OnceLock::new();
+        START.get_or_init(Instant::now).elapsed().as_secs_f64()
+    }
+}
+
+/// Enables or disables the profiler, starting over with no frames recorded.
+pub fn set_enabled(enabled: bool) {
+    PROFILER.with(|it| {
+        *it.borrow_mut() = Profiler {
+            enabled,
+            epoch: now(),
+            ..Default::default()
+        };
+    });
+}
+
+pub fn enabled() -> bool {
+    PROFILER.with(|it| it.borrow().enabled)
+}
+
+/// Ends the frame going on and starts the next one.
+pub fn next_frame() {
+    PROFILER.with(|it| {
+        let mut profiler = it.borrow_mut();
+        if !profiler.enabled {
+            return;
+        }
+        let t = now() - profiler.epoch;
+        if let Some(start) = profiler.frame_start {
+            if profiler.frames.len() == HISTORY {
+                profiler.frames.pop_front();
+            }
+            let spans = std::mem::take(&mut profiler.current);
+            profiler.frames.push_back(FrameTimes {
+                total: (t - start) as f32,
+                spans,
+            });
+            profiler.record(Event {
+                name: "frame",
+                start,
+                duration: t - start,
+            });
+        }
+        profiler.frame_start = Some(t);
+    });
+}
+
+#[must_use]
+pub struct Span(bool);
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if !self.0 {
+            return;
+        }
+        PROFILER.with(|it| {
+            let mut profiler = it.borrow_mut();
+            let Some((subsystem, start, nested)) = profiler.stack.pop() else {
+                return;
+            };
+            let duration = now() - profiler.epoch - start;
+            profiler.current[subsystem as usize] += (duration - nested) as f32;
+            if let Some(parent) = profiler.stack.last_mut() {
+                parent.2 += duration;
+            }
+            profiler.record(Event {
+                name: subsystem.name(),
+                start,
+                duration,
+            });
+        });
+    }
+}
+
+/// Times `subsystem` until the returned span is dropped.
+pub fn span(subsystem: Subsystem) -> Span {
+    PROFILER.with(|it| {
+        let mut profiler = it.borrow_mut();
+        if !profiler.enabled {
+            return Span(false);
+        }
+        let start = now() - profiler.epoch;
+        profiler.stack.push((subsystem, start, 0.));
+        Span(true)
+    })
+}
+
+/// Draws the time of the last frames, split by subsystem, at the bottom left of the screen.
+pub fn render_overlay(ui: &mut Ui) {
+    // drawing text opens spans of its own, so the profiler can't stay borrowed
+    let Some(frames) = PROFILER.with(|it| {
+        let profiler = it.borrow();
+        profiler.enabled.then(|| profiler.frames.iter().cloned().collect::<Vec<_>>())
+    }) else {
+        return;
+    };
+    let (w, h) = (0.7, 0.25);
+    let graph = Rect::new(-0.98, ui.top - 0.03 - h, w, h);
+    ui.fill_rect(Rect::new(graph.x - 0.01, graph.y - 0.01, w + 0.32, h + 0.02), Color::new(0., 0., 0., 0.6));
+
+    let bar = w / HISTORY as f32;
+    let scale = h / GRAPH_SCALE;
+    let other = Color::new(1., 1., 1., 0.3);
+    for (i, frame) in frames.iter().enumerate() {
+        let x = graph.x + bar * (HISTORY - frames.len() + i) as f32;
+        let mut y = graph.bottom();
+        for (subsystem, time) in Subsystem::ALL.iter().zip(frame.spans) {
+            let len = (time * scale).min(y - graph.y);
+            ui.fill_rect(Rect::new(x, y - len, bar, len), subsystem.color());
+            y -= len;
+        }
+        let len = ((frame.total * scale).min(h) - (graph.bottom() - y)).max(0.);
+        ui.fill_rect(Rect::new(x, y - len, bar, len), other);
+    }
+    for budget in [1. / 60., 1. / 30.] {
+        let y = graph.bottom() - budget * scale;
+        ui.fill_rect(Rect::new(graph.x, y, w, 0.002), Color::new(1., 1., 1., 0.5));
+        ui.text(format!("{:.1}ms", budget * 1000.))
+            .pos(graph.x + 0.005, y + 0.005)
+            .size(0.25)
+            .color(Color::new(1., 1., 1., 0.7))
+            .draw();
+    }
+
+    // average over the frames shown
+    let count = frames.len().max(1) as f32;
+    let x = graph.right() + 0.02;
+    let line = h / (Subsystem::ALL.len() + 1) as f32;
+    let total: f32 = frames.iter().map(|it| it.total).sum::<f32>() / count;
+    ui.text(format!("frame {:.2}ms", total * 1000.))
+        .pos(x, graph.y)
+        .size(0.25)
+        .color(WHITE)
+        .draw();
+    for (i, subsystem) in Subsystem::ALL.iter().enumerate() {
+        let avg = frames.iter().map(|it| it.spans[i]).sum::<f32>() / count;
+        ui.text(format!("{} {:.2}ms", subsystem.name(), avg * 1000.))
+            .pos(x, graph.y + line * (i + 1) as f32)
+            .size(0.25)
+            .color(subsystem.color())
+            .draw();
+    }
+}
+
+/// Sets the directory traces are saved to by [`save_trace`], the temporary directory by default.
+pub fn set_trace_dir(dir: impl Into<PathBuf>) {
+    *TRACE_DIR.lock().unwrap() = Some(dir.into());
+}
+
+/// Writes the spans recorded so far as a Chrome trace, and forgets them.
+pub fn write_trace(w: impl Write) -> Result<()> {
+    let events = PROFILER.with(|it| std::mem::take(&mut it.borrow_mut().events));
+    let mut trace = vec![json!({ "name": "thread_name", "ph": "M", "pid": 0, "tid": 0, "args": { "name": "main" } })];
+    trace.extend(events.into_iter().map(|event| {
+        json!({
+            "name": event.name,
+            "cat": if event.name == "frame" { "frame" } else { "span" },
+            "ph": "X",
+            "ts": event.start * 1e6,
+            "dur": event.duration * 1e6,
+            "pid": 0,
+            "tid": 0,
+        })
+    }));
+    serde_json::to_writer(w, &json!({ "traceEvents": trace, "displayTimeUnit": "ms" }))?;
+    Ok(())
+}
+
+/// Saves the spans recorded so far as a trace named after the current time, and returns its path.
+pub fn save_trace() -> Result<PathBuf> {
+    let dir = TRACE_DIR.lock().unwrap().clone().unwrap_or_else(std::env::temp_dir);
+    std::fs::create_dir_all(&dir)?;
+    let path = Path::new(&dir).join(format!("trace-{}.json", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    write_trace(std::io::BufWriter::new(std::fs::File::create(&path)?))?;
+    Ok(path)
+}
@@ -0,0 +1,172 @@
+//! Keeping the game smooth on slow devices.
+//!
+//! [`benchmark`] picks the quality tier to start with by measuring the device, and a [`Governor`]
+//! lowers the quality during play when frames keep missing their budget.
+
+use crate::{
+    config::Quality,
+    core::MSRenderTarget,
+    ext::{screen_aspect, SafeTexture},
+    ui::{TextPainter, Ui},
+};
+use macroquad::prelude::*;
+
+/// Frame time above which a frame counts as slow
+const BUDGET: f32 = 1. / 45.;
+/// How long frames are looked at before deciding, in seconds
+const WINDOW: f64 = 2.;
+/// Ratio of slow frames in a window that lowers the quality
+const SLOW_RATIO: f32 = 0.5;
+/// Time after lowering the quality during which frames aren't looked at, so that the change can
+/// take effect
+const SETTLE: f64 = 1.;
+
+/// A step the [`Governor`] lowers the quality by, in the order they're taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Downgrade {
+    Msaa,
+    Particles,
+    Effects,
+}
+
+/// Watches the frame time during play and tells when the quality should go down a step.
+pub struct Governor {
+    last: Option<f64>,
+    window_start: f64,
+    frames: u32,
+    slow: u32,
+    steps: Vec<Downgrade>,
+}
+
+impl Default for Governor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Governor {
+    pub fn new() -> Self {
+        Self {
+            last: None,
+            window_start: f64::NEG_INFINITY,
+            frames: 0,
+            slow: 0,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Starts looking at frames afresh, e.g. after a pause.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.window_start = f64::NEG_INFINITY;
+        self.frames = 0;
+        self.slow = 0;
+    }
+
+    /// Feeds a frame starting at `t`, in real time. Returns `true` when most frames of the last
+    /// window were slow.
+    pub fn frame(&mut self, t: f64) -> bool {
+        let Some(last) = self.last.replace(t) else {
+            self.window_start = t;
+            return false;
+        };
+        if t < self.window_start {
+            return false;
+        }
+        self.frames += 1;
+        if (t - last) as f32 > BUDGET {
+            self.slow += 1;
+        }
+        if t - self.window_start < WINDOW {
+            return false;
+        }
+        let slow = self.slow as f32 > self.frames as f32 * SLOW_RATIO;
+        self.frames = 0;
+        self.slow = 0;
+        self.window_start = if slow { t + SETTLE } else { t };
+        slow
+    }
+
+    /// Records that the quality was lowered by `step`.
+    pub fn push(&mut self, step: Downgrade) {
+        self.steps.push(step);
+    }
+
+    /// The steps the quality was lowered by so far.
+    pub fn steps(&self) -> &[Downgrade] {
+        &self.steps
+    }
+}
+
+/// Frames drawn before measuring, while shaders compile and caches fill up
+const WARMUP: usize = 20;
+/// Frames measured
+const FRAMES: usize = 90;
+/// Quads drawn each frame, more than the notes on screen in most charts
+const QUADS: usize = 3000;
+
+/// Draws a scene heavier than most charts with 4x MSAA for a while, and returns the tier the
+/// device can keep smooth. `message` is shown meanwhile.
+pub async fn benchmark(painter: &mut TextPainter, message: &str) -> Quality {
+    let (w, h) = (screen_width().max(1.) as u32, screen_height().max(1.) as u32);
+    let target = MSRenderTarget::new((w, h), 4);
+    let texture = SafeTexture::from(Texture2D::from_rgba8(1, 1, &[255, 255, 255, 255]));
+    let mut total = 0.;
+    for frame in 0..WARMUP + FRAMES {
+        let asp = screen_aspect();
+        let top = 1. / asp;
+        set_camera(&Camera2D {
+            zoom: vec2(1., -asp),
+            render_target: Some(target.input()),
+            ..Default::default()
+        });
+        clear_background(BLACK);
+        for i in 0..QUADS {
+            let phase = i as f32 * 0.618 + frame as f32 * 0.02;
+            let (x, y) = ((phase * 1.7).sin(), (phase * 2.3).cos() * top);
+            draw_texture_ex(
+                *texture,
+                x - 0.1,
+                y - 0.01,
+                Color::new(phase.sin().abs(), 0.8, (phase * 0.5).cos().abs(), 0.5),
+                DrawTextureParams {
+                    dest_size: Some(vec2(0.2, 0.02)),
+                    rotation: phase,
+                    ..Default::default()
+                },
+            );
+        }
+        unsafe { get_internal_gl() }.flush();
+        target.blit();
+
+        set_camera(&Camera2D {
+            zoom: vec2(1., -asp),
+            ..Default::default()
+        });
+        draw_texture_ex(
+            target.output().texture,
+            -1.,
+            -top,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(2., top * 2.)),
+                ..Default::default()
+            },
+        );
+        draw_rectangle(-1., -top, 2., top * 2., Color::new(0., 0., 0., 0.8));
+        let mut ui = Ui::new(painter, None);
+        ui.text(message).anchor(0.5, 0.5).size(0.6).draw();
+        next_frame().await;
+        if frame >= WARMUP {
+            total += get_frame_time();
+        }
+    }
+    let average = total / FRAMES as f32;
+    if average <= 1. / 55. {
+        Quality::High
+    } else if average <= BUDGET {
+        Quality::Medium
+    } else {
+        Quality::Low
+    }
+}
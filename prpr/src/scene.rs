@@ -324,6 +324,18 @@ pub fn request_file(id: impl Into<String>) {
     }
 }
 
+/// Asks the user to pick a folder. Platforms without a folder picker fall back to [`request_file`].
+#[cfg(not(target_arch = "wasm32"))]
+pub fn request_folder(id: impl Into<String>) {
+    cfg_if! {
+        if #[cfg(any(target_os = "android", target_os = "ios", target_env = "ohos"))] {
+            request_file(id);
+        } else {
+            *CHOSEN_FILE.lock().unwrap() = (Some(id.into()), rfd::FileDialog::new().pick_folder().map(|it| it.display().to_string()));
+        }
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub fn take_file() -> Option<(String, String)> {
     let mut w = CHOSEN_FILE.lock().unwrap();
@@ -2,11 +2,12 @@ prpr_l10n::tl_file!("ending");
 
 use super::{draw_background, game::SimpleRecord, loading::UploadFn, NextScene, Scene};
 use crate::{
-    config::{Config, Mods},
+    config::{Config, Mods, Quality},
     core::{BOLD_FONT, PGR_FONT},
     ext::{create_audio_manger, rect_shadow, semi_black, semi_white, RectExt, SafeTexture, ScaleType},
     info::ChartInfo,
-    judge::{icon_index, PlayResult},
+    judge::{icon_index, timing_color, PlayResult, LIMIT_BAD},
+    quality::Downgrade,
     scene::show_message,
     task::Task,
     time::TimeManager,
@@ -43,7 +44,6 @@ pub struct EndingScene {
     player_name: String,
     player_rks: Option<f32>,
     autoplay: bool,
-    use_keyboard: bool,
     speed: f32,
     mods: Mods,
     next: u8, // 0 -> none, 1 -> pop, 2 -> exit
@@ -63,6 +63,9 @@ pub struct EndingScene {
     tr_start: f32,
 
     avg_fps: Option<f32>,
+    quality: Quality,
+    /// Steps the quality was lowered by during play
+    downgrades: Vec<Downgrade>,
 }
 
 impl EndingScene {
@@ -85,6 +88,7 @@ impl EndingScene {
         record_data: Option<Vec<u8>>,
         best_record: Option<SimpleRecord>,
         avg_fps: Option<f32>,
+        downgrades: Vec<Downgrade>,
     ) -> Result<Self> {
         let mut audio = create_audio_manger(config)?;
         let bgm = audio.create_music(
@@ -131,7 +135,6 @@ impl EndingScene {
             player_name: config.player_name.clone(),
             player_rks,
             autoplay: config.autoplay(),
-            use_keyboard: config.use_keyboard,
             speed: config.speed,
             mods: config.mods,
             next: 0,
@@ -149,8 +152,112 @@ impl EndingScene {
             tr_start: f32::NAN,
 
             avg_fps,
+            quality: config.quality,
+            downgrades,
         })
     }
+
+    fn quality_text(&self) -> String {
+        let tier = match self.quality {
+            Quality::Low => tl!("quality-low"),
+            Quality::Medium => tl!("quality-medium"),
+            Quality::High => tl!("quality-high"),
+            Quality::Custom => tl!("quality-custom"),
+        };
+        if self.downgrades.is_empty() {
+            return tier.into_owned();
+        }
+        let steps = self
+            .downgrades
+            .iter()
+            .map(|it| match it {
+                Downgrade::Msaa => tl!("lowered-msaa"),
+                Downgrade::Particles => tl!("lowered-particles"),
+                Downgrade::Effects => tl!("lowered-effects"),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        tl!("quality-lowered", "tier" => tier, "steps" => steps)
+    }
+}
+
+/// Draws timing statistics, a hit-error histogram and a timeline of errors into `r`.
+fn render_timing(ui: &mut Ui, res: &PlayResult, r: Rect) {
+    let ms = |secs: f32| (secs * 1000.).round() as i32;
+    let s = 0.36;
+    let timing = &res.timing;
+    if timing.count == 0 {
+        ui.text(tl!("no-timing")).pos(r.x, r.y).size(s).color(semi_white(0.6)).draw();
+        return;
+    }
+    let tr = ui
+        .text(
+            tl!("timing-summary", "mean" => format!("{:+}", ms(timing.mean)), "std" => ms(timing.std), "ur" => timing.unstable_rate().round() as i32),
+        )
+        .pos(r.x, r.y)
+        .size(s)
+        .color(semi_white(0.8))
+        .draw();
+    let kinds = [tl!("timing-click"), tl!("timing-hold")]
+        .into_iter()
+        .zip(&res.timing_by_kind)
+        .filter(|(_, stats)| stats.count != 0)
+        .map(|(kind, stats)| tl!("timing-kind", "kind" => kind, "mean" => format!("{:+}", ms(stats.mean)), "std" => ms(stats.std)))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let tr = ui.text(kinds).pos(r.x, tr.bottom() + 0.01).size(s).color(semi_white(0.6)).draw();
+
+    let top = tr.bottom() + 0.02;
+    let h = r.bottom() - top;
+    if h <= 0. {
+        return;
+    }
+    let bg = semi_black(0.3);
+
+    // histogram, early on the left
+    let hr = Rect::new(r.x, top, r.w * 0.45, h);
+    ui.fill_rect(hr, bg);
+    let bins = res.hit_error_histogram(22);
+    let max = bins.iter().copied().max().unwrap_or(0).max(1);
+    let bw = hr.w / bins.len() as f32;
+    for (i, count) in bins.iter().enumerate() {
+        if *count == 0 {
+            continue;
+        }
+        let center = ((i as f32 + 0.5) / bins.len() as f32 * 2. - 1.) * LIMIT_BAD as f32;
+        let bh = *count as f32 / max as f32 * hr.h;
+        ui.fill_rect(Rect::new(hr.x + bw * i as f32, hr.bottom() - bh, bw * 0.8, bh), timing_color(center));
+    }
+    let to_x = |diff: f32| hr.center().x + diff / LIMIT_BAD as f32 * hr.w / 2.;
+    ui.fill_rect(Rect::new(hr.center().x - 0.001, hr.y, 0.002, hr.h), semi_white(0.5));
+    ui.fill_rect(Rect::new(to_x(timing.mean) - 0.002, hr.y, 0.004, hr.h), WHITE);
+    ui.text(tl!("early"))
+        .pos(hr.x + 0.01, hr.y + 0.01)
+        .size(0.3)
+        .color(semi_white(0.5))
+        .draw();
+    ui.text(tl!("late"))
+        .pos(hr.right() - 0.01, hr.y + 0.01)
+        .anchor(1., 0.)
+        .size(0.3)
+        .color(semi_white(0.5))
+        .draw();
+
+    // timeline, early on the top
+    let lr = Rect::new(hr.right() + 0.03, top, r.right() - hr.right() - 0.03, h);
+    ui.fill_rect(lr, bg);
+    ui.fill_rect(Rect::new(lr.x, lr.center().y - 0.001, lr.w, 0.002), semi_white(0.5));
+    let (start, end) = res
+        .timings
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(l, r), it| (l.min(it.time), r.max(it.time)));
+    let len = (end - start).max(1e-3);
+    let d = 0.008;
+    for timing in &res.timings {
+        let x = lr.x + (timing.time - start) / len * (lr.w - d);
+        let y = lr.center().y + (timing.diff / LIMIT_BAD as f32).clamp(-1., 1.) * (lr.h - d) / 2.;
+        ui.fill_rect(Rect::new(x, y - d / 2., d, d), timing_color(timing.diff));
+    }
 }
 
 thread_local! {
@@ -397,6 +504,18 @@ impl Scene for EndingScene {
                     .draw_using(&BOLD_FONT);
             }
 
+            let r = ui
+                .text(tl!("quality"))
+                .pos(lf - 0.017, r.bottom() + 0.015)
+                .color(cl)
+                .size(s * 0.8)
+                .draw_using(&BOLD_FONT);
+            ui.text(self.quality_text())
+                .pos(r.right() + 0.02, r.y)
+                .size(s * 0.8)
+                .color(ct)
+                .draw_using(&BOLD_FONT);
+
             let mut y = -top + 0.4 + ui.top * 0.3;
             let tp = y;
             let mut x = -0.26 + (1.2 - y) / 1.9 * 0.4;
@@ -510,6 +629,12 @@ impl Scene for EndingScene {
             };
             ui.text(text).pos(r.right() + 0.03, y).size(s).draw_using(&BOLD_FONT);
 
+            if self.detail_mode {
+                let y = y + dy + 0.02;
+                let x = x - 0.15;
+                render_timing(ui, res, Rect::new(x, y, 0.96 - x, ui.top - 0.18 - y));
+            }
+
             let mut r = Rect::new(0.96, ui.top - 0.04, 0.25, 0.1);
             r.x -= r.w;
             r.y -= r.h;
@@ -543,7 +668,9 @@ impl Scene for EndingScene {
             } else {
                 format!("{:.2}x", self.speed)
             };
-            let status_text = if !self.rated && !self.autoplay && !self.use_keyboard {
+            let status_text = if let Some(profile) = &self.result.input_profile {
+                format!("{profile} {spd}")
+            } else if !self.rated && !self.autoplay {
                 if spd.is_empty() {
                     "UNRATED".to_string()
                 } else {
@@ -6,17 +6,20 @@ use super::{
     draw_background,
     ending::RecordUpdateState,
     loading::{BasicPlayer, SaveFn, UpdateFn, UploadFn},
-    request_input, return_input, show_message, take_input, EndingScene, NextScene, Scene,
+    request_input, return_input, show_error, show_message, take_input, EndingScene, NextScene, Scene,
 };
 use crate::{
     bin::BinaryReader,
     config::{Config, Mods},
-    core::{copy_fbo, BadNote, Chart, ChartExtra, Effect, Point, Resource, UIElement, Vector, PGR_FONT},
+    core::{copy_fbo, BadNote, Chart, ChartExtra, Effect, JudgeLineKind, NoteKind, Point, Resource, UIElement, Vector, PACK_FONT, PGR_FONT},
     ext::{parse_time, screen_aspect, semi_white, RectExt, SafeTexture, ScaleType},
     fs::FileSystem,
+    hud::{set_edited_layout, HudAnchor, HudElement, HudPass},
     info::{ChartFormat, ChartInfo},
-    judge::Judge,
-    parse::{parse_extra, parse_pec, parse_phigros, parse_rpe},
+    judge::{icon_index, timing_color, Judge, LIMIT_BAD, LIMIT_GOOD, LIMIT_PERFECT},
+    parse::{check_extra_lines, parse_extra, parse_pec, parse_phigros, parse_rpe},
+    profile::{self, Subsystem},
+    quality::Governor,
     task::Task,
     time::TimeManager,
     ui::{RectButton, TextPainter, Ui},
@@ -30,6 +33,7 @@ use sasa::{Music, MusicParams};
 use serde::{Deserialize, Serialize};
 use std::{
     any::Any,
+    borrow::Cow,
     cell::RefCell,
     fs::File,
     io::{Cursor, ErrorKind},
@@ -114,6 +118,56 @@ enum State {
     Ending,
 }
 
+#[derive(Default)]
+struct HudEditor {
+    selected: Option<HudElement>,
+    /// The touch dragging the selected element and its last position
+    drag: Option<(u64, Point)>,
+}
+
+#[derive(Default)]
+struct LineInspector {
+    selected: Option<usize>,
+    /// Whether lines are being picked in place of the pause menu
+    picking: bool,
+}
+
+/// Seconds of keyframes shown before and after the current time
+const INSPECTOR_TIMELINE: (f64, f64) = (1., 7.);
+/// Upcoming notes listed by the inspector
+const INSPECTOR_NOTES: usize = 5;
+
+fn hud_element_name(element: HudElement) -> Cow<'static, str> {
+    match element {
+        HudElement::Pause => tl!("hud-pause"),
+        HudElement::ComboNumber => tl!("hud-combo-number"),
+        HudElement::Combo => tl!("hud-combo"),
+        HudElement::Score => tl!("hud-score"),
+        HudElement::Bar => tl!("hud-bar"),
+        HudElement::Name => tl!("hud-name"),
+        HudElement::Level => tl!("hud-level"),
+        HudElement::Accuracy => tl!("hud-accuracy"),
+        HudElement::HitErrorBar => tl!("hud-hit-error-bar"),
+        HudElement::JudgeCounts => tl!("hud-judge-counts"),
+        HudElement::RemainingTime => tl!("hud-remaining-time"),
+    }
+}
+
+fn hud_anchor_name(anchor: Option<HudAnchor>) -> Cow<'static, str> {
+    match anchor {
+        None => tl!("hud-anchor-none"),
+        Some(HudAnchor::TopLeft) => tl!("hud-anchor-top-left"),
+        Some(HudAnchor::Top) => tl!("hud-anchor-top"),
+        Some(HudAnchor::TopRight) => tl!("hud-anchor-top-right"),
+        Some(HudAnchor::Left) => tl!("hud-anchor-left"),
+        Some(HudAnchor::Center) => tl!("hud-anchor-center"),
+        Some(HudAnchor::Right) => tl!("hud-anchor-right"),
+        Some(HudAnchor::BottomLeft) => tl!("hud-anchor-bottom-left"),
+        Some(HudAnchor::Bottom) => tl!("hud-anchor-bottom"),
+        Some(HudAnchor::BottomRight) => tl!("hud-anchor-bottom-right"),
+    }
+}
+
 pub struct GameScene {
     should_exit: bool,
     next_scene: Option<NextScene>,
@@ -155,6 +209,12 @@ pub struct GameScene {
     fps_last_frame_time: f64,
 
     dead: bool,
+
+    hud_bounds: Vec<(HudElement, Rect)>,
+    hud_editor: Option<HudEditor>,
+    inspector: Option<LineInspector>,
+
+    governor: Governor,
 }
 
 macro_rules! reset {
@@ -162,7 +222,7 @@ macro_rules! reset {
         $self.bad_notes.clear();
         $self.judge.reset();
         $self.chart.reset();
-        $res.judge_line_color = $res.res_pack.info.color_perfect();
+        $res.judge_line_color = $res.color_perfect();
         $self.music.pause()?;
         $self.music.seek_to(0.)?;
         $tm.speed = $res.config.speed as _;
@@ -228,6 +288,7 @@ impl GameScene {
                 r.read()
             }
         }?;
+        check_extra_lines(&chart.extra, chart.lines.len()).context("Failed to parse extra")?;
         chart.load_textures(fs).await?;
         chart.settings.hold_partial_cover = info.hold_partial_cover;
         Ok((chart, bytes, format))
@@ -302,7 +363,7 @@ impl GameScene {
 
         let exercise_range = (chart.offset + info_offset + res.config.offset) as f64..res.track_length;
 
-        let judge = Judge::new(&chart);
+        let judge = Judge::new(&chart, res.config.input_profile().cloned());
 
         let music = Self::new_music(&mut res)?;
         Ok(Self {
@@ -347,12 +408,27 @@ impl GameScene {
             fps_last_frame_time: 0.0,
 
             dead: false,
+
+            hud_bounds: Vec::new(),
+            hud_editor: None,
+            inspector: None,
+
+            governor: Governor::new(),
         })
     }
 
+    /// Creates the music for the configured speed. The stretched music for it should be ready,
+    /// which it is from loading on unless the speed is changed in the pause menu.
     fn new_music(res: &mut Resource) -> Result<Music> {
+        let clip = match res.music_for_speed()? {
+            Some(clip) => clip,
+            None => {
+                warn!("music is still being stretched, playing it unstretched");
+                res.music.clone()
+            }
+        };
         res.audio.create_music(
-            res.music.clone(),
+            clip,
             MusicParams {
                 amplifier: res.config.volume_music as _,
                 playback_rate: res.config.speed as _,
@@ -382,12 +458,20 @@ impl GameScene {
                 1. - (t / (AFTER_TIME + 0.3)).min(1.).powi(2)
             }
         } as f32;
-        let res = &mut self.res;
+        let res = &self.res;
+        let editing = self.hud_editor.is_some();
         let eps = 2e-2 / res.aspect_ratio;
         let top = -1. / res.aspect_ratio;
         let pause_w = 0.015;
         let pause_h = pause_w * 3.2;
         let pause_center = Point::new(pause_w * 4.0 - 1., top + eps * 3.5 - (1. - p) * 0.4 + pause_h / 2.);
+        let pause_rect = Rect::new(pause_center.x - pause_w * 1.5, pause_center.y - pause_h / 2., pause_w * 3., pause_h);
+        // the pause button still works when hidden, at the place it would be drawn
+        let pause_at = res
+            .config
+            .hud
+            .transform(HudElement::Pause, pause_rect, top)
+            .transform_point(&pause_center);
         if res.config.interactive
             && !tm.paused()
             && self.pause_rewind.is_none()
@@ -395,7 +479,7 @@ impl GameScene {
                 touch.phase == TouchPhase::Started && {
                     let p = touch.position;
                     let p = Point::new(p.x, p.y);
-                    (pause_center - p).norm() < 0.05
+                    (pause_at - p).norm() < 0.05
                 }
             })
         {
@@ -412,12 +496,13 @@ impl GameScene {
                 miniquad::native::set_interceptor_state(false);
             }
         }
-        ui.alpha(res.alpha, |ui| {
+        self.hud_bounds = ui.alpha(res.alpha, |ui| {
             ui.text("MAGIC BUGFIX TEXT").color(Color::new(0., 0., 0., 0.)).draw();
             if tm.now() as f32 - self.pause_first_time <= PAUSE_CLICK_INTERVAL {
-                ui.fill_circle(pause_center.x, pause_center.y, 0.05, Color::new(1., 1., 1., 0.5));
+                ui.fill_circle(pause_at.x, pause_at.y, 0.05, Color::new(1., 1., 1., 0.5));
             }
 
+            let mut hud = HudPass::new(&res.config.hud, top, editing);
             let margin = 0.03;
 
             let legacy_aui = !res.info.use_attach_ui_fix.unwrap_or_default();
@@ -432,82 +517,104 @@ impl GameScene {
                 let ct = ui.text(&score).size(0.8).measure_using(&PGR_FONT).center();
                 (score_right - ct.x, score_top + ct.y)
             });
-            self.chart
-                .with_element(ui, res, UIElement::Score, scale_point, (score_right, score_top), |ui, c| {
-                    ui.text(&score)
-                        .pos(score_right, score_top)
-                        .anchor(1., 0.)
-                        .size(0.8)
-                        .color(c)
-                        .draw_using(&PGR_FONT);
-                    if res.config.show_acc {
-                        ui.text(format!("{:05.2}%", self.judge.real_time_accuracy() * 100.))
-                            .pos(1. - margin, score_top + h)
+            let score_rect = ui
+                .text(&score)
+                .pos(score_right, score_top)
+                .anchor(1., 0.)
+                .size(0.8)
+                .measure_using(&PGR_FONT);
+            hud.draw(ui, HudElement::Score, score_rect, |ui| {
+                self.chart
+                    .with_element(ui, res, UIElement::Score, scale_point, (score_right, score_top), |ui, c| {
+                        ui.text(&score)
+                            .pos(score_right, score_top)
                             .anchor(1., 0.)
-                            .size(0.4)
-                            .color(Color { a: c.a * 0.7, ..c })
+                            .size(0.8)
+                            .color(c)
                             .draw_using(&PGR_FONT);
-                    }
-                });
+                        if res.config.show_acc {
+                            ui.text(format!("{:05.2}%", self.judge.real_time_accuracy() * 100.))
+                                .pos(1. - margin, score_top + h)
+                                .anchor(1., 0.)
+                                .size(0.4)
+                                .color(Color { a: c.a * 0.7, ..c })
+                                .draw_using(&PGR_FONT);
+                        }
+                    });
+            });
 
-            self.chart.with_element(
-                ui,
-                res,
-                UIElement::Pause,
-                legacy_aui.then(|| (pause_center.x, pause_center.y)),
-                (pause_center.x - pause_w * 1.5, pause_center.y - pause_h / 2.),
-                |ui, c| {
-                    let mut r = Rect::new(pause_center.x - pause_w * 1.5, pause_center.y - pause_h / 2., pause_w, pause_h);
-                    ui.fill_rect(r, c);
-                    r.x += pause_w * 2.;
-                    ui.fill_rect(r, c);
-                },
-            );
-            if self.judge.combo() >= 3 {
+            hud.draw(ui, HudElement::Pause, pause_rect, |ui| {
+                self.chart.with_element(
+                    ui,
+                    res,
+                    UIElement::Pause,
+                    legacy_aui.then(|| (pause_center.x, pause_center.y)),
+                    (pause_rect.x, pause_rect.y),
+                    |ui, c| {
+                        let mut r = Rect::new(pause_rect.x, pause_rect.y, pause_w, pause_h);
+                        ui.fill_rect(r, c);
+                        r.x += pause_w * 2.;
+                        ui.fill_rect(r, c);
+                    },
+                );
+            });
+            // the resource pack may replace the font of combo and judgement text
+            let combo_font = if PACK_FONT.with(|it| it.borrow().is_some()) {
+                &PACK_FONT
+            } else {
+                &PGR_FONT
+            };
+            if self.judge.combo() >= 3 || editing {
+                let combo = self.judge.combo().to_string();
+                let label = if res.config.autoplay() { "AUTOPLAY" } else { "COMBO" };
                 if legacy_aui {
                     let combo_top = top + eps * 2. - (1. - p) * 0.4;
-                    let btm = self
-                        .chart
-                        .with_element(ui, res, UIElement::ComboNumber, None, (0., combo_top + unit_h / 2.), |ui, c| {
-                            ui.text(self.judge.combo().to_string())
-                                .pos(0., combo_top)
-                                .anchor(0.5, 0.)
+                    let number_rect = ui.text(&combo).pos(0., combo_top).anchor(0.5, 0.).measure_using(combo_font);
+                    hud.draw(ui, HudElement::ComboNumber, number_rect, |ui| {
+                        self.chart
+                            .with_element(ui, res, UIElement::ComboNumber, None, (0., combo_top + unit_h / 2.), |ui, c| {
+                                ui.text(&combo).pos(0., combo_top).anchor(0.5, 0.).color(c).draw_using(combo_font);
+                            });
+                    });
+                    let combo_top = number_rect.bottom() + 0.01;
+                    let label_rect = ui.text(label).pos(0., combo_top).anchor(0.5, 0.).size(0.4).measure_using(combo_font);
+                    hud.draw(ui, HudElement::Combo, label_rect, |ui| {
+                        self.chart
+                            .with_element(ui, res, UIElement::Combo, None, (0., combo_top + unit_h * 0.2), |ui, c| {
+                                ui.text(label)
+                                    .pos(0., combo_top)
+                                    .anchor(0.5, 0.)
+                                    .size(0.4)
+                                    .color(c)
+                                    .draw_using(combo_font);
+                            });
+                    });
+                } else {
+                    let ct = ui.text(&combo).size(1.0).measure().center();
+                    let combo_y = top + eps * 2. - (1. - p) * 0.4 + ct.y;
+                    let number_rect = ui.text(&combo).pos(0., combo_y).anchor(0.5, 0.5).size(1.0).measure_using(combo_font);
+                    hud.draw(ui, HudElement::ComboNumber, number_rect, |ui| {
+                        self.chart.with_element(ui, res, UIElement::ComboNumber, None, (0., combo_y), |ui, c| {
+                            ui.text(&combo)
+                                .pos(0., combo_y)
+                                .anchor(0.5, 0.5)
+                                .size(1.0)
                                 .color(c)
-                                .draw_using(&PGR_FONT)
-                                .bottom()
+                                .draw_using(combo_font);
                         });
-                    let combo_top = btm + 0.01;
-                    self.chart
-                        .with_element(ui, res, UIElement::Combo, None, (0., combo_top + unit_h * 0.2), |ui, c| {
-                            ui.text(if res.config.autoplay() { "AUTOPLAY" } else { "COMBO" })
+                    });
+                    let ct = ui.text("COMBO").size(0.4).measure().center();
+                    let combo_top = number_rect.bottom() + 0.01 + ct.y;
+                    let label_rect = ui.text(label).pos(0., combo_top).anchor(0.5, 0.5).size(0.4).measure_using(combo_font);
+                    hud.draw(ui, HudElement::Combo, label_rect, |ui| {
+                        self.chart.with_element(ui, res, UIElement::Combo, None, (0., combo_top), |ui, c| {
+                            ui.text(label)
                                 .pos(0., combo_top)
-                                .anchor(0.5, 0.)
+                                .anchor(0.5, 0.5)
                                 .size(0.4)
                                 .color(c)
-                                .draw_using(&PGR_FONT);
+                                .draw_using(combo_font);
                         });
-                } else {
-                    let combo = self.judge.combo().to_string();
-                    let ct = ui.text(&combo).size(1.0).measure().center();
-                    let combo_y = top + eps * 2. - (1. - p) * 0.4 + ct.y;
-                    let btm = self.chart.with_element(ui, res, UIElement::ComboNumber, None, (0., combo_y), |ui, c| {
-                        ui.text(&combo)
-                            .pos(0., combo_y)
-                            .anchor(0.5, 0.5)
-                            .size(1.0)
-                            .color(c)
-                            .draw_using(&PGR_FONT)
-                            .bottom()
-                    });
-                    let ct = ui.text("COMBO").size(0.4).measure().center();
-                    let combo_top = btm + 0.01 + ct.y;
-                    self.chart.with_element(ui, res, UIElement::Combo, None, (0., combo_top), |ui, c| {
-                        ui.text(if res.config.autoplay() { "AUTOPLAY" } else { "COMBO" })
-                            .pos(0., combo_top)
-                            .anchor(0.5, 0.5)
-                            .size(0.4)
-                            .color(c)
-                            .draw_using(&PGR_FONT);
                     });
                 }
             }
@@ -519,38 +626,122 @@ impl GameScene {
                 let ct = ui.text(&res.info.name).size(0.5).measure().center();
                 (lf + ct.x, bt - ct.y)
             });
-            self.chart.with_element(ui, res, UIElement::Name, scale_point, (lf, bt), |ui, c| {
-                ui.text(&res.info.name)
-                    .pos(lf, bt)
-                    .anchor(0., 1.)
-                    .size(0.5)
-                    .color(c)
-                    .max_width(0.8)
-                    .draw();
+            let name_rect = ui.text(&res.info.name).pos(lf, bt).anchor(0., 1.).size(0.5).max_width(0.8).measure();
+            hud.draw(ui, HudElement::Name, name_rect, |ui| {
+                self.chart.with_element(ui, res, UIElement::Name, scale_point, (lf, bt), |ui, c| {
+                    ui.text(&res.info.name)
+                        .pos(lf, bt)
+                        .anchor(0., 1.)
+                        .size(0.5)
+                        .color(c)
+                        .max_width(0.8)
+                        .draw();
+                });
             });
 
             let scale_point = legacy_aui.then(|| {
                 let ct = ui.text(&res.info.level).size(0.5).measure().center();
                 (-lf - ct.x, bt - ct.y)
             });
-            self.chart.with_element(ui, res, UIElement::Level, scale_point, (-lf, bt), |ui, c| {
-                ui.text(&res.info.level).pos(-lf, bt).anchor(1., 1.).size(0.5).color(c).draw();
+            let level_rect = ui.text(&res.info.level).pos(-lf, bt).anchor(1., 1.).size(0.5).measure();
+            hud.draw(ui, HudElement::Level, level_rect, |ui| {
+                self.chart.with_element(ui, res, UIElement::Level, scale_point, (-lf, bt), |ui, c| {
+                    ui.text(&res.info.level).pos(-lf, bt).anchor(1., 1.).size(0.5).color(c).draw();
+                });
             });
 
             let hw = 0.003;
             let height = eps * 1.0;
             let dest = (2. * res.time / res.track_length).clamp(0., 2.) as f32;
-            self.chart
-                .with_element(ui, res, UIElement::Bar, Some((-1., top + height / 2.)), (-1., top + height / 2.), |ui, color| {
-                    ui.fill_rect(Rect::new(-1., top, dest, height), semi_white(0.6));
-                    ui.fill_rect(Rect::new(-1. + dest - hw, top, hw * 2., height), WHITE);
-                });
+            hud.draw(ui, HudElement::Bar, Rect::new(-1., top, 2., height), |ui| {
+                self.chart
+                    .with_element(ui, res, UIElement::Bar, Some((-1., top + height / 2.)), (-1., top + height / 2.), |ui, color| {
+                        ui.fill_rect(Rect::new(-1., top, dest, height), semi_white(0.6));
+                        ui.fill_rect(Rect::new(-1. + dest - hw, top, hw * 2., height), WHITE);
+                    });
+            });
+
+            // extra elements, which charts can't attach to lines
+            let acc_top = score_top + h + if res.config.show_acc { 0.05 } else { 0. };
+            let acc = format!("{:.2}%", self.judge.real_time_accuracy() * 100.);
+            let acc_rect = ui.text(&acc).pos(score_right, acc_top).anchor(1., 0.).size(0.5).measure_using(&PGR_FONT);
+            hud.draw(ui, HudElement::Accuracy, acc_rect, |ui| {
+                ui.text(&acc).pos(score_right, acc_top).anchor(1., 0.).size(0.5).draw_using(&PGR_FONT);
+            });
+
+            let remaining = (res.track_length - res.time).max(0.) as u32;
+            let remaining = format!("{}:{:02}", remaining / 60, remaining % 60);
+            let remaining_x = pause_rect.right() + 0.03;
+            let remaining_rect = ui
+                .text(&remaining)
+                .pos(remaining_x, pause_center.y)
+                .anchor(0., 0.5)
+                .size(0.5)
+                .no_baseline()
+                .measure_using(&PGR_FONT);
+            hud.draw(ui, HudElement::RemainingTime, remaining_rect, |ui| {
+                ui.text(&remaining)
+                    .pos(remaining_x, pause_center.y)
+                    .anchor(0., 0.5)
+                    .size(0.5)
+                    .no_baseline()
+                    .draw_using(&PGR_FONT);
+            });
+
+            let [perfect, good, bad, miss] = self.judge.counts();
+            let counts = format!("P {perfect}  G {good}  B {bad}  M {miss}");
+            let counts_rect = ui.text(&counts).pos(lf, 0.).anchor(0., 0.5).size(0.4).measure_using(combo_font);
+            hud.draw(ui, HudElement::JudgeCounts, counts_rect, |ui| {
+                ui.text(&counts).pos(lf, 0.).anchor(0., 0.5).size(0.4).draw_using(combo_font);
+            });
+
+            let bar_w = 0.5;
+            let bar_h = 0.03;
+            let bar_rect = Rect::new(-bar_w / 2., bt - bar_h - 0.02, bar_w, bar_h);
+            hud.draw(ui, HudElement::HitErrorBar, bar_rect, |ui| {
+                let x = |diff: f32| bar_rect.center().x + diff / LIMIT_BAD as f32 * bar_w / 2.;
+                let zone = |ui: &mut Ui, limit: f32, alpha: f32| {
+                    ui.fill_rect(
+                        Rect::new(x(-limit), bar_rect.y + bar_h * 0.3, x(limit) - x(-limit), bar_h * 0.4),
+                        Color {
+                            a: alpha,
+                            ..timing_color(limit)
+                        },
+                    );
+                };
+                zone(ui, LIMIT_BAD as f32, 0.3);
+                zone(ui, LIMIT_GOOD as f32, 0.4);
+                zone(ui, LIMIT_PERFECT as f32, 0.5);
+                ui.fill_rect(Rect::new(x(0.) - 0.001, bar_rect.y, 0.002, bar_h), WHITE);
+                for timing in self.judge.timings().iter().rev().take(30) {
+                    let age = (res.time as f32 - timing.time).max(0.) / 4.;
+                    if age >= 1. {
+                        continue;
+                    }
+                    ui.fill_rect(
+                        Rect::new(x(timing.diff) - 0.002, bar_rect.y, 0.004, bar_h),
+                        Color {
+                            a: 1. - age,
+                            ..timing_color(timing.diff)
+                        },
+                    );
+                }
+            });
+            hud.bounds
         });
         Ok(())
     }
 
     fn overlay_ui(&mut self, ui: &mut Ui, tm: &mut TimeManager) -> Result<()> {
         let c = semi_white(self.res.alpha);
+        if tm.paused() && self.hud_editor.is_some() {
+            self.hud_editor_ui(ui);
+            return Ok(());
+        }
+        if tm.paused() && self.inspector.as_ref().is_some_and(|it| it.picking) {
+            self.line_picker_ui(ui);
+            return Ok(());
+        }
         let res = &mut self.res;
         if tm.paused() {
             let h = 1. / res.aspect_ratio;
@@ -559,6 +750,7 @@ impl GameScene {
             let s = 0.06;
             let w = 0.05;
             let no_retry = self.mode == GameMode::NoRetry;
+            let stretching = res.is_stretching();
             draw_texture_ex(
                 *res.icon_back,
                 -s * 3. - w,
@@ -571,17 +763,26 @@ impl GameScene {
             );
             let r = Rect::new(0., o, 0., 0.).feather(s);
             let disabled_color = semi_white(res.alpha * 0.4);
-            ui.fill_rect(r, (*res.icon_retry, r.feather(0.02), ScaleType::Fit, if no_retry { disabled_color } else { c }));
+            ui.fill_rect(r, (*res.icon_retry, r.feather(0.02), ScaleType::Fit, if no_retry || stretching { disabled_color } else { c }));
             draw_texture_ex(
                 *res.icon_resume,
                 s + w,
                 -s + o,
-                if self.dead { disabled_color } else { c },
+                if self.dead || stretching { disabled_color } else { c },
                 DrawTextureParams {
                     dest_size: Some(vec2(s * 2., s * 2.)),
                     ..Default::default()
                 },
             );
+            if res.config.interactive && self.mode != GameMode::Exercise {
+                let r = Rect::new(-0.12, o + s + 0.08, 0.24, 0.07);
+                if ui.button("hud_edit", r, tl!("hud-edit")) {
+                    self.hud_editor = Some(HudEditor::default());
+                }
+                if res.config.chart_debug && ui.button("inspect_lines", Rect { y: r.bottom() + 0.02, ..r }, tl!("inspect-lines")) {
+                    self.inspector.get_or_insert_with(LineInspector::default).picking = true;
+                }
+            }
             if res.config.interactive {
                 let mut clicked = None;
                 for touch in Judge::get_touches() {
@@ -607,15 +808,13 @@ impl GameScene {
                     pos = tm.now();
                 }
                 if clicked.is_some_and(|it| it != -1) && (tm.speed - res.config.speed as f64).abs() > 0.01 {
-                    debug!("recreating music");
-                    self.music = res.audio.create_music(
-                        res.music.clone(),
-                        MusicParams {
-                            amplifier: res.config.volume_music as _,
-                            playback_rate: res.config.speed as _,
-                            ..Default::default()
-                        },
-                    )?;
+                    if res.music_for_speed()?.is_some() {
+                        debug!("recreating music");
+                        self.music = Self::new_music(res)?;
+                    } else {
+                        // the music is being stretched for the new speed, which can take a while
+                        clicked = None;
+                    }
                 }
                 match clicked {
                     Some(-1) => {
@@ -729,7 +928,7 @@ impl GameScene {
                             self.bad_notes.clear();
                             self.judge.reset();
                             self.chart.reset();
-                            self.res.judge_line_color = self.res.res_pack.info.color_perfect();
+                            self.res.judge_line_color = self.res.color_perfect();
                         } else {
                             *(if *ctrl == -1 {
                                 &mut self.exercise_range.start
@@ -794,10 +993,342 @@ impl GameScene {
         Ok(())
     }
 
+    /// Saves the trace of the play when profiling, as the scene is left.
+    fn save_trace() {
+        if !profile::enabled() {
+            return;
+        }
+        match profile::save_trace() {
+            Ok(path) => {
+                show_message(tl!("trace-saved", "path" => path.display().to_string())).ok();
+            }
+            Err(err) => show_error(err.context(tl!("trace-save-failed"))),
+        }
+    }
+
+    fn hud_editor_ui(&mut self, ui: &mut Ui) {
+        let Some(editor) = &mut self.hud_editor else {
+            return;
+        };
+        let layout = &mut self.res.config.hud;
+        let panel = Rect::new(-0.4, -0.17, 0.8, 0.34);
+        for touch in Judge::get_touches() {
+            let p = Point::new(touch.position.x, touch.position.y);
+            match touch.phase {
+                TouchPhase::Started => {
+                    if panel.contains(touch.position) {
+                        continue;
+                    }
+                    if let Some((element, _)) = self.hud_bounds.iter().rev().find(|(_, r)| r.contains(touch.position)) {
+                        editor.selected = Some(*element);
+                        editor.drag = Some((touch.id, p));
+                    }
+                }
+                TouchPhase::Moved | TouchPhase::Stationary => {
+                    if let (Some(element), Some((id, last))) = (editor.selected, &mut editor.drag) {
+                        if *id == touch.id {
+                            let offset = &mut layout.get_mut(element).offset;
+                            offset.0 += p.x - last.x;
+                            offset.1 += p.y - last.y;
+                            *last = p;
+                        }
+                    }
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    if editor.drag.is_some_and(|(id, _)| id == touch.id) {
+                        editor.drag = None;
+                    }
+                }
+            }
+        }
+
+        for (element, r) in &self.hud_bounds {
+            let chosen = editor.selected == Some(*element);
+            let r = r.feather(0.01);
+            ui.stroke_path(&r.rounded(0.01), if chosen { 0.006 } else { 0.003 }, if chosen { YELLOW } else { semi_white(0.5) });
+        }
+
+        ui.fill_path(&panel.rounded(0.02), Color::new(0., 0., 0., 0.8));
+        let pad = 0.02;
+        let spacing = 0.01;
+        let bh = 0.065;
+        let title = editor.selected.map_or_else(|| tl!("hud-select"), hud_element_name);
+        ui.text(title)
+            .pos(0., panel.y + pad)
+            .anchor(0.5, 0.)
+            .size(0.6)
+            .max_width(panel.w - pad * 2.)
+            .draw();
+        let row = |i: usize| panel.y + 0.09 + (bh + spacing) * i as f32;
+        let half = (panel.w - pad * 2. - spacing) / 2.;
+        let left = |i: usize| Rect::new(panel.x + pad, row(i), half, bh);
+        let right = |i: usize| Rect::new(panel.x + pad + half + spacing, row(i), half, bh);
+
+        if let Some(element) = editor.selected {
+            let it = layout.get_mut(element);
+            if ui.button("hud_visible", left(0), if it.visible { tl!("hud-hide") } else { tl!("hud-show") }) {
+                it.visible = !it.visible;
+            }
+            let anchor = hud_anchor_name(it.anchor);
+            if ui.button("hud_anchor", right(0), tl!("hud-anchor", "anchor" => anchor)) {
+                let next = match it.anchor {
+                    None => Some(0),
+                    Some(anchor) => HudAnchor::ALL.iter().position(|it| *it == anchor).map(|i| i + 1),
+                };
+                it.anchor = next.and_then(|i| HudAnchor::ALL.get(i).copied());
+                it.offset = (0., 0.);
+            }
+            let stepper = |ui: &mut Ui, id: &str, r: Rect, label: String, value: &mut f32, range: (f32, f32)| {
+                let bw = bh;
+                if ui.button(&format!("{id}_sub"), Rect::new(r.x, r.y, bw, r.h), "-") {
+                    *value = ((*value - 0.1) * 10.).round() / 10.;
+                }
+                if ui.button(&format!("{id}_add"), Rect::new(r.right() - bw, r.y, bw, r.h), "+") {
+                    *value = ((*value + 0.1) * 10.).round() / 10.;
+                }
+                *value = value.clamp(range.0, range.1);
+                let ct = r.center();
+                ui.text(label)
+                    .pos(ct.x, ct.y)
+                    .anchor(0.5, 0.5)
+                    .size(0.42)
+                    .max_width(r.w - bw * 2.)
+                    .no_baseline()
+                    .draw();
+            };
+            let label = tl!("hud-size", "size" => format!("{:.1}", it.scale));
+            stepper(ui, "hud_size", left(1), label, &mut it.scale, (0.3, 3.));
+            let label = tl!("hud-opacity", "opacity" => format!("{:.0}%", it.opacity * 100.));
+            stepper(ui, "hud_opacity", right(1), label, &mut it.opacity, (0.1, 1.));
+            if ui.button("hud_reset", left(2), tl!("hud-reset")) {
+                layout.reset(element);
+            }
+        }
+        if ui.button("hud_done", right(2), tl!("hud-done")) {
+            layout.normalize();
+            set_edited_layout(layout.clone());
+            self.hud_editor = None;
+        }
+    }
+
+    fn inspector_panel(ui: &Ui) -> Rect {
+        Rect::new(0.38, -ui.top + 0.04, 0.6, ui.top * 2. - 0.08)
+    }
+
+    /// Picks the line to inspect, by tapping at it or going through the lines in order.
+    fn line_picker_ui(&mut self, ui: &mut Ui) {
+        let Some(inspector) = &mut self.inspector else {
+            return;
+        };
+        let res = &self.res;
+        let lines = &self.chart.lines;
+        let flip = if res.config.flip_x() { -1. } else { 1. };
+        let bar = Rect::new(-0.96, ui.top - 0.13, 1.28, 0.09);
+        let panel = Self::inspector_panel(ui);
+        for touch in Judge::get_touches() {
+            if touch.phase != TouchPhase::Started || bar.contains(touch.position) || inspector.selected.is_some() && panel.contains(touch.position) {
+                continue;
+            }
+            // lines are picked by the point their id is drawn at
+            let nearest = lines
+                .iter()
+                .enumerate()
+                .map(|(id, line)| {
+                    let pos = line.fetch_pos(res, lines);
+                    (id, (vec2(pos.x * flip, -pos.y) - touch.position).length())
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((id, _)) = nearest.filter(|it| it.1 < 0.08) {
+                inspector.selected = Some(id);
+            }
+        }
+
+        ui.fill_path(&bar.rounded(0.02), Color::new(0., 0., 0., 0.8));
+        ui.text(tl!("inspector-pick"))
+            .pos(bar.x + 0.02, bar.y - 0.01)
+            .anchor(0., 1.)
+            .size(0.45)
+            .draw();
+        let pad = 0.015;
+        let w = (bar.w - pad * 5.) / 4.;
+        let cell = |i: usize| Rect::new(bar.x + pad + (w + pad) * i as f32, bar.y + pad, w, bar.h - pad * 2.);
+        let count = lines.len();
+        if ui.button("inspector_prev", cell(0), tl!("inspector-prev")) && count > 0 {
+            inspector.selected = Some(inspector.selected.map_or(count - 1, |it| (it + count - 1) % count));
+        }
+        if ui.button("inspector_next", cell(1), tl!("inspector-next")) && count > 0 {
+            inspector.selected = Some(inspector.selected.map_or(0, |it| (it + 1) % count));
+        }
+        let mut close = ui.button("inspector_close", cell(3), tl!("inspector-close"));
+        if ui.button("inspector_done", cell(2), tl!("inspector-done")) {
+            inspector.picking = false;
+            close |= inspector.selected.is_none();
+        }
+        if close {
+            self.inspector = None;
+        }
+    }
+
+    /// Shows the live state of the inspected line, which stays on screen while playing.
+    fn line_inspector_ui(&mut self, ui: &mut Ui) {
+        let Some(id) = self.inspector.as_ref().and_then(|it| it.selected) else {
+            return;
+        };
+        let res = &self.res;
+        let lines = &self.chart.lines;
+        let line = &lines[id];
+        let obj = &line.object;
+        let flip = if res.config.flip_x() { -1. } else { 1. };
+
+        let pos = line.fetch_pos(res, lines);
+        let rot = line.fetch_rot(lines);
+        let ct = vec2(pos.x * flip, -pos.y);
+        let dir = vec2(rot.to_radians().cos() * flip, -rot.to_radians().sin()) * 0.15;
+        ui.stroke_circle(ct.x, ct.y, 0.03, 0.005, YELLOW);
+        draw_line(ct.x - dir.x, ct.y - dir.y, ct.x + dir.x, ct.y + dir.y, 0.005, YELLOW);
+
+        let panel = Self::inspector_panel(ui);
+        ui.fill_path(&panel.rounded(0.02), Color::new(0., 0., 0., 0.75));
+        let pad = 0.02;
+        let row_h = 0.033;
+        let mut y = panel.y + pad;
+        let row = |ui: &mut Ui, y: &mut f32, text: String, color: Color| {
+            ui.text(text)
+                .pos(panel.x + pad, *y)
+                .size(0.3)
+                .color(color)
+                .max_width(panel.w - pad * 2.)
+                .draw();
+            *y += row_h;
+        };
+        let dim = semi_white(0.7);
+        let opt = |it: Option<f32>| it.map_or_else(|| "-".to_owned(), |it| format!("{it:.2}"));
+
+        let kind = match &line.kind {
+            JudgeLineKind::Normal => tl!("inspector-kind-normal"),
+            JudgeLineKind::Texture(_, path) | JudgeLineKind::TextureGif(_, _, path) => tl!("inspector-kind-texture", "path" => path.as_str()),
+            JudgeLineKind::Text(_) => tl!("inspector-kind-text"),
+            JudgeLineKind::Paint(..) => tl!("inspector-kind-paint"),
+        };
+        row(ui, &mut y, tl!("inspector-line", "id" => id, "kind" => kind, "z" => line.z_index), YELLOW);
+        if let Some(element) = line.attach_ui {
+            row(ui, &mut y, tl!("inspector-attach", "element" => format!("{element:?}")), dim);
+        }
+        let chain = line.ancestors(lines).map(|it| format!("#{it}")).collect::<Vec<_>>();
+        let parents = if chain.is_empty() {
+            tl!("inspector-no-parent").into_owned()
+        } else if line.rot_with_parent {
+            tl!("inspector-parents-rot", "chain" => chain.join(" → "))
+        } else {
+            tl!("inspector-parents", "chain" => chain.join(" → "))
+        };
+        row(ui, &mut y, parents, dim);
+
+        let tr = obj.translation.now();
+        let f = |it: f32| format!("{it:.3}");
+        row(ui, &mut y, tl!("inspector-local", "x" => f(tr.x), "y" => f(tr.y), "rot" => format!("{:.1}", obj.rotation.now())), WHITE);
+        row(ui, &mut y, tl!("inspector-world", "x" => f(pos.x), "y" => f(pos.y * res.aspect_ratio), "rot" => format!("{rot:.1}")), WHITE);
+        let scale = obj.scale.now_with_def(1., 1.);
+        row(
+            ui,
+            &mut y,
+            tl!("inspector-scale", "x" => format!("{:.2}", scale.x), "y" => format!("{:.2}", scale.y), "alpha" => opt(obj.alpha.now_opt())),
+            WHITE,
+        );
+        row(
+            ui,
+            &mut y,
+            tl!("inspector-speed", "speed" => format!("{:.2}", line.height.now_slope()), "height" => format!("{:.2}", line.height.now())),
+            WHITE,
+        );
+        let color = line.color.now_opt().map_or_else(
+            || tl!("inspector-default").into_owned(),
+            |c| format!("#{:02X}{:02X}{:02X}", (c.r * 255.) as u8, (c.g * 255.) as u8, (c.b * 255.) as u8),
+        );
+        row(ui, &mut y, tl!("inspector-color", "color" => color), WHITE);
+        {
+            // notes set the height themselves before using it
+            let mut ctrl = line.ctrl_obj.borrow_mut();
+            ctrl.set_height(0.);
+            let text = tl!("inspector-ctrl", "alpha" => opt(ctrl.alpha.now_opt()), "size" => opt(ctrl.size.now_opt()), "pos" => opt(ctrl.pos.now_opt()), "y" => opt(ctrl.y.now_opt()));
+            row(ui, &mut y, text, WHITE);
+        }
+
+        y += row_h * 0.5;
+        let end = |note: &&crate::core::Note| match note.kind {
+            NoteKind::Hold { end_time, .. } => end_time,
+            _ => note.time,
+        };
+        let mut notes = line.notes.iter().filter(|it| end(it) >= res.time).collect::<Vec<_>>();
+        notes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        row(ui, &mut y, tl!("inspector-notes", "count" => notes.len()), YELLOW);
+        for note in notes.iter().take(INSPECTOR_NOTES) {
+            let kind = match note.kind {
+                NoteKind::Click => tl!("inspector-note-click"),
+                NoteKind::Hold { .. } => tl!("inspector-note-hold"),
+                NoteKind::Flick => tl!("inspector-note-flick"),
+                NoteKind::Drag => tl!("inspector-note-drag"),
+            };
+            let mut text = tl!("inspector-note", "time" => format!("{:.3}", note.time), "kind" => kind, "x" => f(note.object.translation.0.now()));
+            if !note.above {
+                text.push_str(&tl!("inspector-note-below"));
+            }
+            if note.fake {
+                text.push_str(&tl!("inspector-note-fake"));
+            }
+            row(ui, &mut y, text, dim);
+        }
+
+        y += row_h * 0.5;
+        let (from, to) = (res.time - INSPECTOR_TIMELINE.0, res.time + INSPECTOR_TIMELINE.1);
+        row(ui, &mut y, tl!("inspector-keyframes", "from" => format!("{from:.1}"), "to" => format!("{to:.1}")), YELLOW);
+        let tracks = [
+            (tl!("inspector-anim-x"), obj.translation.0.keyframe_times().collect::<Vec<_>>()),
+            (tl!("inspector-anim-y"), obj.translation.1.keyframe_times().collect()),
+            (tl!("inspector-anim-rot"), obj.rotation.keyframe_times().collect()),
+            (tl!("inspector-anim-alpha"), obj.alpha.keyframe_times().collect()),
+            (tl!("inspector-anim-speed"), line.height.keyframe_times().collect()),
+            (tl!("inspector-anim-scale"), obj.scale.0.keyframe_times().chain(obj.scale.1.keyframe_times()).collect()),
+            (tl!("inspector-anim-color"), line.color.keyframe_times().collect()),
+        ];
+        let label_w = 0.1;
+        let track_x = panel.x + pad + label_w;
+        let track_w = panel.w - pad * 2. - label_w;
+        let to_x = |t: f64| track_x + ((t - from) / (to - from)) as f32 * track_w;
+        let track_h = row_h * 0.7;
+        for (i, (label, mut times)) in tracks.into_iter().enumerate() {
+            let y = y + row_h * i as f32;
+            ui.text(label)
+                .pos(panel.x + pad, y)
+                .size(0.26)
+                .color(dim)
+                .max_width(label_w - 0.01)
+                .draw();
+            ui.fill_rect(Rect::new(track_x, y, track_w, track_h), semi_white(0.1));
+            times.sort_by(f64::total_cmp);
+            // the segment being played
+            let current = times.partition_point(|it| *it <= res.time);
+            if current > 0 && current < times.len() {
+                let (l, r) = (to_x(times[current - 1]).max(track_x), to_x(times[current]).min(track_x + track_w));
+                ui.fill_rect(Rect::new(l, y, r - l, track_h), Color::new(1., 1., 0., 0.25));
+            }
+            for t in times.into_iter().filter(|it| (from..=to).contains(it)) {
+                ui.fill_rect(Rect::new(to_x(t) - 0.0015, y, 0.003, track_h), WHITE);
+            }
+        }
+        let now = to_x(res.time);
+        ui.fill_rect(Rect::new(now - 0.001, y, 0.002, row_h * 7.), YELLOW);
+    }
+
     fn interactive(res: &Resource, state: &State) -> bool {
         res.config.interactive && matches!(state, State::Playing)
     }
 
+    /// Time the chart is shown at, behind the time notes are judged at by the visual offset.
+    fn display_time(res: &Resource) -> f64 {
+        (res.time - res.config.visual_offset as f64).max(0.)
+    }
+
     fn offset(&self) -> f32 {
         self.chart.offset + self.res.config.offset + self.info_offset
     }
@@ -879,6 +1410,8 @@ impl Scene for GameScene {
         reset!(self, self.res, tm);
         set_camera(&self.res.camera);
         self.first_in = true;
+        // the profiler is turned off whenever the scene is left, retrying included
+        profile::set_enabled(self.res.config.profiler);
         Ok(())
     }
 
@@ -901,7 +1434,18 @@ impl Scene for GameScene {
     }
 
     fn update(&mut self, tm: &mut TimeManager) -> Result<()> {
+        profile::next_frame();
         self.res.audio.recover_if_needed()?;
+        if self.res.config.auto_quality && matches!(self.state, State::Playing) && !tm.paused() {
+            if self.governor.frame(tm.real_time()) {
+                if let Some(step) = self.res.downgrade() {
+                    warn!("frames are too slow, lowering the quality: {step:?}");
+                    self.governor.push(step);
+                }
+            }
+        } else {
+            self.governor.reset();
+        }
         if matches!(self.state, State::Playing) {
             tm.update(self.music.position());
         }
@@ -1007,7 +1551,7 @@ impl Scene for GameScene {
                             let historic_best = self.player.as_ref().map_or(0, |it| it.historic_best);
                             if let Some(new_rec) = &record {
                                 if let Some(f) = &self.save_fn {
-                                    f(new_rec.clone())?;
+                                    f(new_rec.clone(), &result)?;
                                 }
                                 if let Some(best) = &mut self.best_record {
                                     best.update(new_rec);
@@ -1031,13 +1575,16 @@ impl Scene for GameScene {
                                 self.res.info.clone(),
                                 self.judge.result(),
                                 &self.res.config,
-                                self.res.res_pack.ending.clone(),
+                                self.res
+                                    .res_pack
+                                    .ending_for(icon_index(result.score, result.max_combo == result.num_of_notes)),
                                 self.upload_fn.as_ref().map(Arc::clone),
                                 self.player.as_ref().map(|it| it.rks),
                                 historic_best,
                                 record_data,
                                 self.best_record.clone(),
                                 if self.res.config.show_avg_fps { self.get_avg_fps() } else { None },
+                                self.governor.steps().to_vec(),
                             )?)))
                         }
                         GameMode::TweakOffset => Some(NextScene::PopWithResult(Box::new(None::<f32>))),
@@ -1051,8 +1598,10 @@ impl Scene for GameScene {
         let time = (time - offset as f64).max(0.);
         self.res.time = time;
         if !tm.paused() && self.pause_rewind.is_none() && self.mode != GameMode::View {
+            let _span = profile::span(Subsystem::Judge);
             self.gl.quad_gl.viewport(self.res.camera.viewport);
             self.judge.update(&mut self.res, &mut self.chart, &mut self.bad_notes);
+            self.judge.schedule_hitsounds(&mut self.res);
             self.gl.quad_gl.viewport(None);
         }
         if let Some(update) = &mut self.update_fn {
@@ -1061,9 +1610,9 @@ impl Scene for GameScene {
         let counts = self.judge.counts();
         self.res.judge_line_color = if counts[2] + counts[3] == 0 && self.res.config.ap_fc_indicator {
             if counts[1] == 0 {
-                self.res.res_pack.info.color_perfect()
+                self.res.color_perfect()
             } else {
-                self.res.res_pack.info.color_good()
+                self.res.color_good()
             }
         } else {
             WHITE
@@ -1083,7 +1632,13 @@ impl Scene for GameScene {
             show_message(tl!("game-over")).error();
         }
         self.res.judge_line_color.a *= self.res.alpha;
-        self.chart.update(&mut self.res);
+        {
+            let _span = profile::span(Subsystem::Chart);
+            // notes are judged at the current time, but displayed with the visual offset
+            let time = std::mem::replace(&mut self.res.time, Self::display_time(&self.res));
+            self.chart.update(&mut self.res);
+            self.res.time = time;
+        }
         let res = &mut self.res;
         if res.config.interactive && is_key_pressed(KeyCode::Space) {
             if tm.paused() {
@@ -1099,24 +1654,30 @@ impl Scene for GameScene {
             }
         }
         if Self::interactive(res, &self.state) {
-            if is_key_pressed(KeyCode::Left) && res.config.use_keyboard {
+            // keys bound by the input profile are reserved for playing
+            let bound = |key| res.config.input_profile().is_some_and(|it| it.binds_key(key));
+            let (bound_left, bound_right, bound_q) = (bound(KeyCode::Left), bound(KeyCode::Right), bound(KeyCode::Q));
+            if is_key_pressed(KeyCode::Left) && res.config.use_keyboard && !bound_left {
                 res.time -= 1.;
                 let dst = (self.music.position() - 1.).max(0.);
                 self.music.seek_to(dst)?;
                 tm.seek_to(dst);
             }
-            if is_key_pressed(KeyCode::Right) && res.config.use_keyboard {
+            if is_key_pressed(KeyCode::Right) && res.config.use_keyboard && !bound_right {
                 res.time += 5.;
                 let dst = (self.music.position() + 5.).min(res.track_length);
                 self.music.seek_to(dst)?;
                 tm.seek_to(dst);
             }
-            if is_key_pressed(KeyCode::Q) {
+            if is_key_pressed(KeyCode::Q) && !bound_q {
                 self.should_exit = true;
             }
         }
-        for e in &mut self.effects {
-            e.update(&self.res);
+        {
+            let _span = profile::span(Subsystem::Effects);
+            for e in &mut self.effects {
+                e.update(&self.res);
+            }
         }
         if let Some((id, text)) = take_input() {
             let offset = self.offset().min(0.);
@@ -1216,7 +1777,12 @@ impl Scene for GameScene {
         let h = 1. / res.aspect_ratio;
         draw_rectangle(-1., -h, 2., h * 2., Color::new(0., 0., 0., res.alpha * res.info.background_dim));
 
-        self.chart.render(ui, res);
+        {
+            let _span = profile::span(Subsystem::Render);
+            let time = std::mem::replace(&mut res.time, Self::display_time(res));
+            self.chart.render(ui, res);
+            res.time = time;
+        }
 
         self.gl.quad_gl.render_pass(
             res.chart_target
@@ -1229,10 +1795,17 @@ impl Scene for GameScene {
         let t = tm.real_time();
         let dt = (t - std::mem::replace(&mut self.last_update_time, t)) as f32;
         if res.config.particle {
+            let _span = profile::span(Subsystem::Particles);
             res.emitter.draw(dt);
+            self.chart.render_particles(res);
         }
-        self.ui(ui, tm)?;
-        self.overlay_ui(ui, tm)?;
+        {
+            let _span = profile::span(Subsystem::Hud);
+            self.ui(ui, tm)?;
+            self.overlay_ui(ui, tm)?;
+            self.line_inspector_ui(ui);
+        }
+        profile::render_overlay(ui);
 
         if self.mode == GameMode::TweakOffset {
             push_camera_state();
@@ -1247,6 +1820,7 @@ impl Scene for GameScene {
         }
 
         if !self.res.no_effect && !self.effects.is_empty() {
+            let _span = profile::span(Subsystem::Effects);
             push_camera_state();
             set_camera(&Camera2D {
                 zoom: vec2(1., asp),
@@ -1292,6 +1866,8 @@ impl Scene for GameScene {
             }
             tm.speed = 1.0;
             tm.adjust_time = false;
+            Self::save_trace();
+            profile::set_enabled(false);
             match self.mode {
                 // return result to update score and refresh
                 GameMode::Normal => {
@@ -1309,6 +1885,10 @@ impl Scene for GameScene {
             if !matches!(next_scene, NextScene::None) && tm.paused() {
                 tm.resume();
             }
+            if !matches!(next_scene, NextScene::None) {
+                Self::save_trace();
+                profile::set_enabled(false);
+            }
             tm.speed = 1.0;
             tm.adjust_time = false;
             next_scene
@@ -5,7 +5,7 @@ use crate::{
     ext::{poll_future, semi_black, semi_white, LocalTask, RectExt, SafeTexture, BLACK_TEXTURE},
     fs::FileSystem,
     info::ChartInfo,
-    judge::Judge,
+    judge::{Judge, PlayResult},
     scene::game::SimpleRecord,
     task::Task,
     time::TimeManager,
@@ -23,7 +23,8 @@ const FADE_IN_TIME: f32 = 0.6;
 
 pub type UploadFn = Arc<dyn Fn(Vec<u8>) -> Task<Result<RecordUpdateState>>>;
 pub type UpdateFn = Box<dyn FnMut(f64, &mut Resource, &mut Judge)>;
-pub type SaveFn = Box<dyn Fn(SimpleRecord) -> Result<()>>;
+/// Saves the record of a finished play. The full result is passed along for statistics.
+pub type SaveFn = Box<dyn Fn(SimpleRecord, &PlayResult) -> Result<()>>;
 
 fn transition_time() -> Option<f32> {
     if PREFER_REDUCED_MOTION.load(Ordering::Relaxed) {
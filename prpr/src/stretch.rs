@@ -0,0 +1,147 @@
+//! Pitch-preserving playback at other speeds.
+//!
+//! The music is played at another speed by changing its playback rate, which moves the pitch
+//! along. To keep the pitch, the clip is first shifted in pitch the other way: it's time-stretched
+//! with WSOLA (waveform similarity overlap-add) and resampled back to its original length. The
+//! length staying the same keeps positions in the music matched to the chart.
+
+use sasa::{AudioClip, Frame};
+use std::f64::consts::{PI, TAU};
+
+/// Length of the segments, in seconds
+const WINDOW: f32 = 0.04;
+/// How far a segment may be moved to match the one before, in seconds
+const TOLERANCE: f32 = 0.012;
+/// Decimation of the coarse search for the best match
+const COARSE: usize = 8;
+/// Zero crossings on each side of the low-pass kernel, trading sharpness for speed
+const LOW_PASS_ZEROS: f64 = 8.;
+
+/// Returns `clip` shifted in pitch so that it sounds at its original pitch when played at
+/// `speed`. The result has the same length as `clip`.
+pub fn keep_pitch(clip: &AudioClip, speed: f32) -> AudioClip {
+    let frames = clip.frames();
+    let sample_rate = clip.sample_rate();
+    let window = ((sample_rate as f32 * WINDOW) as usize / 2 * 2).max(COARSE * 2);
+    if (speed - 1.).abs() < 1e-3 || frames.len() < window * 2 {
+        return clip.clone();
+    }
+    let stretched = stretch(frames, window, (sample_rate as f32 * TOLERANCE) as usize, speed as f64);
+    AudioClip::from_raw(resample(&stretched, frames.len()), sample_rate)
+}
+
+/// Time-stretches `frames` to `1 / speed` of their length, keeping the pitch.
+fn stretch(frames: &[Frame], window: usize, tolerance: usize, speed: f64) -> Vec<Frame> {
+    let len = frames.len();
+    let hop = window / 2;
+    let last = len - window;
+    // the mono mix is enough to find similar waveforms
+    let mono: Vec<f32> = frames.iter().map(|it| (it.0 + it.1) / 2.).collect();
+    let coarse: Vec<f32> = mono.chunks_exact(COARSE).map(|it| it.iter().sum::<f32>() / COARSE as f32).collect();
+    let hann: Vec<f32> = (0..window)
+        .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / window as f32).cos())
+        .collect();
+
+    let out_len = (len as f64 / speed) as usize;
+    let mut out = vec![Frame::default(); out_len + window];
+    let mut weights = vec![0f32; out_len + window];
+    let mut prev = 0;
+    for k in 0.. {
+        let out_pos = k * hop;
+        if out_pos >= out_len {
+            break;
+        }
+        let pos = if k == 0 {
+            0
+        } else {
+            let nominal = ((k * hop) as f64 * speed) as usize;
+            // the segment that would naturally follow the previous one
+            let template = (prev + hop).min(last);
+            best_match(&mono, &coarse, template, hop, nominal.saturating_sub(tolerance).min(last), (nominal + tolerance).min(last))
+        };
+        for (i, w) in hann.iter().enumerate() {
+            let frame = frames[pos + i];
+            let out = &mut out[out_pos + i];
+            out.0 += frame.0 * w;
+            out.1 += frame.1 * w;
+            weights[out_pos + i] += w;
+        }
+        prev = pos;
+    }
+    out.truncate(out_len);
+    for (frame, weight) in out.iter_mut().zip(weights) {
+        if weight > 1e-3 {
+            frame.0 /= weight;
+            frame.1 /= weight;
+        }
+    }
+    out
+}
+
+/// Finds the start in `from..=to` whose next `len` samples are the most similar to those from
+/// `template`, first on the decimated signal and then around the best coarse match.
+fn best_match(mono: &[f32], coarse: &[f32], template: usize, len: usize, from: usize, to: usize) -> usize {
+    fn correlation(signal: &[f32], a: usize, b: usize, len: usize, step: usize) -> f32 {
+        (0..len).step_by(step).map(|i| signal[a + i] * signal[b + i]).sum()
+    }
+    let best = |candidates: std::ops::RangeInclusive<usize>, score: &dyn Fn(usize) -> f32| {
+        candidates
+            .map(|it| (it, score(it)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(from, |it| it.0)
+    };
+    let coarse_len = len / COARSE;
+    let rough = best(from / COARSE..=to / COARSE, &|it| correlation(coarse, it, template / COARSE, coarse_len, 1)) * COARSE;
+    best(rough.saturating_sub(COARSE).max(from)..=(rough + COARSE).min(to), &|it| correlation(mono, it, template, len, 2))
+}
+
+/// Linearly resamples `frames` to `len` frames. When there are fewer frames to keep, what's above
+/// the new Nyquist frequency is filtered out first, or it would fold back as aliasing.
+fn resample(frames: &[Frame], len: usize) -> Vec<Frame> {
+    let ratio = frames.len() as f64 / len as f64;
+    let filtered;
+    let frames = if ratio > 1. {
+        filtered = low_pass(frames, 0.5 / ratio);
+        &filtered
+    } else {
+        frames
+    };
+    let end = frames.len() - 1;
+    (0..len)
+        .map(|i| {
+            let pos = i as f64 * ratio;
+            let index = (pos as usize).min(end);
+            let next = frames[(index + 1).min(end)];
+            let cur = frames[index];
+            let t = (pos - index as f64) as f32;
+            Frame(cur.0 + (next.0 - cur.0) * t, cur.1 + (next.1 - cur.1) * t)
+        })
+        .collect()
+}
+
+/// Filters out frequencies above `cutoff`, in cycles per frame, with a Hann-windowed sinc.
+fn low_pass(frames: &[Frame], cutoff: f64) -> Vec<Frame> {
+    let half = (LOW_PASS_ZEROS / (2. * cutoff)).ceil() as usize;
+    let mut kernel: Vec<f32> = (0..=half * 2)
+        .map(|i| {
+            let x = i as f64 - half as f64;
+            let sinc = if x == 0. { 2. * cutoff } else { (TAU * cutoff * x).sin() / (PI * x) };
+            let window = 0.5 + 0.5 * (PI * x / (half + 1) as f64).cos();
+            (sinc * window) as f32
+        })
+        .collect();
+    // no gain at low frequencies
+    let sum: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|it| *it /= sum);
+
+    let end = frames.len() - 1;
+    (0..frames.len())
+        .map(|i| {
+            kernel.iter().enumerate().fold(Frame::default(), |acc, (k, w)| {
+                // the edges are extended
+                let frame = frames[(i + k).saturating_sub(half).min(end)];
+                Frame(acc.0 + frame.0 * w, acc.1 + frame.1 * w)
+            })
+        })
+        .collect()
+}
@@ -2,6 +2,7 @@ use super::Ui;
 use crate::{
     core::{Matrix, Point, Vector},
     ext::get_viewport,
+    profile::{self, Subsystem},
 };
 use glyph_brush::{
     ab_glyph::{Font, FontArc, ScaleFont},
@@ -319,6 +320,7 @@ impl TextPainter {
     }
 
     fn submit(&mut self, tr: Matrix, alpha: f32) {
+        let _span = profile::span(Subsystem::Text);
         let mut flushed = false;
         loop {
             match self.brush.process_queued(
@@ -0,0 +1,179 @@
+//! Parses the sample charts in `tests/charts` and checks their shape and key timings, so that
+//! changes to the parsers can't silently shift a chart. Also checks where broken PEC charts are
+//! reported to go wrong.
+
+use prpr::{
+    core::{Chart, ChartExtra, NoteKind},
+    parse::{parse_pec, parse_phigros},
+};
+use std::path::Path;
+use walkdir::WalkDir;
+
+struct Expected {
+    file: &'static str,
+    offset: f32,
+    lines: usize,
+    notes: usize,
+    holds: usize,
+    fakes: usize,
+    /// Time of the first note, in seconds
+    first: f64,
+    /// Time the last note (or hold) ends, in seconds
+    last: f64,
+}
+
+const CORPUS: &[Expected] = &[
+    Expected {
+        file: "pgr_v1.json",
+        offset: 0.,
+        lines: 1,
+        notes: 3,
+        holds: 1,
+        fakes: 0,
+        first: 1.,
+        last: 4.,
+    },
+    Expected {
+        file: "pgr_v3.json",
+        offset: 0.1,
+        lines: 2,
+        notes: 4,
+        holds: 1,
+        fakes: 0,
+        first: 0.5,
+        last: 4.,
+    },
+    Expected {
+        file: "pgr_unversioned.json",
+        offset: -0.05,
+        lines: 1,
+        notes: 2,
+        holds: 0,
+        fakes: 0,
+        first: 1.,
+        last: 2.,
+    },
+    Expected {
+        file: "pgr_unversioned_v3.json",
+        offset: 0.,
+        lines: 1,
+        notes: 1,
+        holds: 0,
+        fakes: 0,
+        first: 1.,
+        last: 1.,
+    },
+    Expected {
+        file: "variants.pec",
+        offset: -0.15,
+        lines: 2,
+        notes: 4,
+        holds: 1,
+        fakes: 1,
+        first: 2.,
+        last: 5.,
+    },
+];
+
+fn corpus_dir() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/charts"))
+}
+
+fn parse(file: &str) -> Chart {
+    let source = std::fs::read_to_string(corpus_dir().join(file)).unwrap();
+    let chart = if file.ends_with(".pec") {
+        parse_pec(&source, ChartExtra::default())
+    } else {
+        parse_phigros(&source, ChartExtra::default())
+    };
+    chart.unwrap_or_else(|err| panic!("failed to parse {file}: {err:?}"))
+}
+
+fn assert_close(file: &str, what: &str, actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-4, "{file}: {what} is {actual}, expected {expected}");
+}
+
+#[test]
+fn corpus_is_covered() {
+    for entry in WalkDir::new(corpus_dir()).min_depth(1) {
+        let entry = entry.unwrap();
+        let name = entry.file_name().to_str().unwrap();
+        assert!(CORPUS.iter().any(|it| it.file == name), "{name} has no expectations");
+    }
+}
+
+#[test]
+fn corpus() {
+    for expected in CORPUS {
+        let file = expected.file;
+        let mut chart = parse(file);
+        assert_close(file, "offset", chart.offset as f64, expected.offset as f64);
+        assert_eq!(chart.lines.len(), expected.lines, "{file}: line count");
+
+        let notes: Vec<_> = chart.lines.iter().flat_map(|line| line.notes.iter()).collect();
+        assert_eq!(notes.len(), expected.notes, "{file}: note count");
+        let holds = notes.iter().filter(|it| matches!(it.kind, NoteKind::Hold { .. })).count();
+        assert_eq!(holds, expected.holds, "{file}: hold count");
+        assert_eq!(notes.iter().filter(|it| it.fake).count(), expected.fakes, "{file}: fake note count");
+
+        let first = notes.iter().map(|it| it.time).fold(f64::INFINITY, f64::min);
+        let last = notes
+            .iter()
+            .map(|it| match it.kind {
+                NoteKind::Hold { end_time, .. } => end_time,
+                _ => it.time,
+            })
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert_close(file, "first note", first, expected.first);
+        assert_close(file, "last note", last, expected.last);
+
+        // every sample starts with its lines on screen, which catches misread move events
+        for (id, line) in chart.lines.iter_mut().enumerate() {
+            line.object.translation.set_time(0.);
+            let pos = line.object.translation.now();
+            assert!(pos.x.abs() <= 1. && pos.y.abs() <= 1., "{file}: line {id} starts at {pos:?}");
+        }
+    }
+}
+
+#[test]
+fn unversioned_v3() {
+    // every line stays at y = 0, which must not be mistaken for packed version 1 positions
+    let file = "pgr_unversioned_v3.json";
+    let mut chart = parse(file);
+    let line = &mut chart.lines[0];
+    line.object.translation.set_time(0.);
+    let pos = line.object.translation.now();
+    assert_close(file, "line x", pos.x as f64, 0.5);
+    assert_close(file, "line y", pos.y as f64, -1.);
+}
+
+#[test]
+fn pec_errors() {
+    prpr_l10n::set_prefered_locale("en-US".parse().ok());
+    let source = "\
+0
+bp 0 120
+n1 0 1 abc 1 0
+zz 1
+n1 0 2 0 3 0
+cv 0 1 1 extra
+bp 4 x
+";
+    let err = parse_pec(source, ChartExtra::default()).unwrap_err();
+    assert_eq!(err.to_string(), "Found 5 errors in the chart.");
+    // the bpm events are read first, yet their errors are listed in order along with the others
+    let expected = [
+        "On line #3, column 8: Expected f32.",
+        "On line #4, column 1: Unknown command: zz.",
+        "On line #5, column 10: Expected 1 / 2 (above / below the line).",
+        "On line #6, column 10: Unexpected extra content: extra.",
+        "On line #7, column 6: Expected f32.",
+    ];
+    let report = err.root_cause().to_string();
+    let lines: Vec<_> = report.lines().collect();
+    assert_eq!(lines.len(), expected.len(), "{report}");
+    for (line, expected) in lines.iter().zip(expected) {
+        assert!(line.starts_with(expected), "{line:?} doesn't start with {expected:?}");
+    }
+}
@@ -0,0 +1,31 @@
+//! Checks the values the line inspector derives from animations.
+
+use prpr::core::{Anim, AnimFloat, Keyframe};
+
+/// Linear tween
+const LINEAR: u8 = 2;
+
+fn linear(points: &[(f64, f32)]) -> AnimFloat {
+    Anim::new(points.iter().map(|&(time, value)| Keyframe::new(time, value, LINEAR)).collect())
+}
+
+#[test]
+fn slope() {
+    let mut anim = linear(&[(0., 0.), (2., 4.), (4., 4.)]);
+    anim.set_time(1.);
+    assert!((anim.now_slope() - 2.).abs() < 1e-3, "slope is {}", anim.now_slope());
+    anim.set_time(3.);
+    assert!(anim.now_slope().abs() < 1e-3);
+    // nothing changes after the last keyframe
+    anim.set_time(10.);
+    assert_eq!(anim.now_slope(), 0.);
+}
+
+#[test]
+fn chained() {
+    let mut anim = Anim::chain(vec![linear(&[(0., 0.), (2., 4.)]), linear(&[(0., 0.), (4., -2.)])]);
+    anim.set_time(1.);
+    // the slopes of chained animations add up, as their values do
+    assert!((anim.now_slope() - 1.5).abs() < 1e-3, "slope is {}", anim.now_slope());
+    assert_eq!(anim.keyframe_times().collect::<Vec<_>>(), [0., 2., 0., 4.]);
+}
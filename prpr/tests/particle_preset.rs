@@ -0,0 +1,72 @@
+//! Checks that particle presets are read from YAML and JSON, survive a round trip and are rejected
+//! when the emitter couldn't handle them.
+
+use prpr::particle::{BlendMode, EmissionShape, ParticlePreset, ParticleShape};
+
+const SPARKS: &str = r#"
+lifetime: 0.8
+amount: 12
+oneShot: true
+emissionShape:
+  type: sphere
+  radius: 0.05
+shape:
+  type: circle
+  subdivisions: 8
+initialDirectionSpread: 6.28
+initialVelocity: 0.6
+gravity: [0, 0.5]
+size: 0.01
+sizeCurve:
+  points: [[0, 1], [1, 0]]
+blendMode: additive
+colorsCurve:
+  start: [1, 1, 1, 1]
+  mid: [1, 0.8, 0.4, 0.8]
+  end: [1, 0.5, 0, 0]
+texture: spark.png
+atlas:
+  columns: 4
+  rows: 2
+  start: 0
+  end: 8
+"#;
+
+#[test]
+fn parse_yaml() {
+    let preset = ParticlePreset::parse(SPARKS).unwrap();
+    let config = &preset.config;
+    assert_eq!(config.amount, 12);
+    assert!(config.one_shot);
+    assert_eq!(config.emission_shape, EmissionShape::Sphere { radius: 0.05 });
+    assert_eq!(config.shape, ParticleShape::Circle { subdivisions: 8 });
+    assert_eq!(config.blend_mode, BlendMode::Additive);
+    assert_eq!(config.gravity.y, 0.5);
+    assert_eq!(config.colors_curve.end.a, 0.);
+    assert_eq!(preset.texture.as_deref(), Some("spark.png"));
+    // unset fields keep their defaults
+    assert_eq!(config.initial_direction.y, -1.);
+}
+
+#[test]
+fn round_trip() {
+    let preset = ParticlePreset::parse(SPARKS).unwrap();
+    let json = serde_json::to_string(&preset).unwrap();
+    let again = ParticlePreset::parse(&json).unwrap();
+    assert_eq!(serde_json::to_string(&again).unwrap(), json);
+}
+
+#[test]
+fn reject_invalid() {
+    for source in [
+        "lifetime: 0",
+        "amount: 100000",
+        "sizeCurve: { points: [[0, 1]] }",
+        "sizeCurve: { points: [[0, 1], [1, 0]], interpolation: bezier }",
+        "shape: { type: circle, subdivisions: 1 }",
+        "shape: { type: customMesh, vertices: [0, 0, 0, 0, 0, 1, 1, 1, 1], indices: [0, 1, 2] }",
+        "atlas: { columns: 2, rows: 2, start: 0, end: 5 }",
+    ] {
+        assert!(ParticlePreset::parse(source).is_err(), "{source} was accepted");
+    }
+}
@@ -0,0 +1,52 @@
+//! Checks that spans recorded by the profiler are exported as a well-formed Chrome trace.
+
+use prpr::profile::{self, Subsystem};
+use serde_json::Value;
+
+fn trace() -> Vec<Value> {
+    let mut buffer = Vec::new();
+    profile::write_trace(&mut buffer).unwrap();
+    let trace: Value = serde_json::from_slice(&buffer).unwrap();
+    trace["traceEvents"].as_array().unwrap().clone()
+}
+
+fn spans<'a>(events: &'a [Value], name: &str) -> Vec<&'a Value> {
+    events.iter().filter(|it| it["ph"] == "X" && it["name"] == name).collect()
+}
+
+#[test]
+fn nested_spans() {
+    profile::set_enabled(true);
+    profile::next_frame();
+    {
+        let _render = profile::span(Subsystem::Render);
+        let _notes = profile::span(Subsystem::Notes);
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+    profile::next_frame();
+
+    let events = trace();
+    let (render, notes, frame) = (spans(&events, "render"), spans(&events, "notes"), spans(&events, "frame"));
+    assert_eq!((render.len(), notes.len(), frame.len()), (1, 1, 1));
+    let range = |it: &Value| {
+        let start = it["ts"].as_f64().unwrap();
+        (start, start + it["dur"].as_f64().unwrap())
+    };
+    let (render, notes, frame) = (range(render[0]), range(notes[0]), range(frame[0]));
+    assert!(notes.1 - notes.0 >= 2000., "the span lasted {}us", notes.1 - notes.0);
+    // spans nest within their parents and the frame, as the viewers expect
+    assert!(render.0 <= notes.0 && notes.1 <= render.1);
+    assert!(frame.0 <= render.0 && render.1 <= frame.1);
+
+    // exported spans are forgotten
+    assert!(spans(&trace(), "render").is_empty());
+}
+
+#[test]
+fn disabled() {
+    profile::set_enabled(false);
+    profile::next_frame();
+    drop(profile::span(Subsystem::Judge));
+    profile::next_frame();
+    assert!(trace().iter().all(|it| it["ph"] != "X"));
+}
@@ -0,0 +1,301 @@
+//! Renders charts at given times into an offscreen target and compares the frames with the golden
+//! images in `tests/render/golden`, so that changes to the renderer can't silently move a line or
+//! a note.
+//!
+//! The frames depend on the GL implementation, so they are recorded with Mesa's software renderer:
+//!
+//! ```sh
+//! LIBGL_ALWAYS_SOFTWARE=1 xvfb-run -s "-screen 0 1280x720x24" cargo test -p prpr --test render
+//! ```
+//!
+//! A missing golden image fails the run. Set `PRPR_UPDATE_GOLDEN=1` to record them, after adding a
+//! case or an intended change. When a frame doesn't match, the actual one is written to
+//! `prpr-render` in the temporary directory so that it can be looked at.
+//!
+//! Without a display the run fails as well. `PRPR_SKIP_RENDER=1` skips it on purpose, as CI does:
+//! its runners have neither a display nor an audio device, and no golden images are recorded yet.
+
+use anyhow::{Context, Result};
+use image::RgbaImage;
+use macroquad::prelude::*;
+use prpr::{
+    config::Config,
+    core::{init_assets, Chart, Resource, UIElement},
+    ext::SafeTexture,
+    fs::{fs_from_file, FileSystem, PatchedFileSystem},
+    info::ChartInfo,
+    scene::GameScene,
+    ui::{FontArc, TextPainter, Ui},
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+
+/// A channel differing by more than this counts the pixel as changed
+const CHANNEL_TOLERANCE: u8 = 16;
+/// The ratio of changed pixels a frame may have and still match
+const PIXEL_TOLERANCE: f64 = 0.005;
+
+struct Case {
+    name: &'static str,
+    /// Directory of the chart, relative to `tests`
+    dir: &'static str,
+    chart: &'static str,
+    /// `extra.json` to load along with the chart, relative to `tests`, which turns effects on
+    extra: Option<&'static str>,
+    /// Times to render at, in seconds
+    times: &'static [f64],
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "pgr",
+        dir: "charts",
+        chart: "pgr_v3.json",
+        extra: None,
+        times: &[0.3, 1.5, 3.],
+    },
+    Case {
+        name: "pgr-v1",
+        dir: "charts",
+        chart: "pgr_v1.json",
+        extra: None,
+        times: &[0.5, 2.5],
+    },
+    Case {
+        name: "pec",
+        dir: "charts",
+        chart: "variants.pec",
+        extra: None,
+        times: &[1., 2.5, 4.5],
+    },
+    // moving, rotating, fading and colored lines, a child line, every note kind (also below the
+    // line and fake ones) and a line with the pause button attached
+    Case {
+        name: "rpe",
+        dir: "render/rpe",
+        chart: "chart.json",
+        extra: None,
+        times: &[0., 0.75, 1.6, 2.25, 3.],
+    },
+    // a vignette over the whole chart and grayscale limited to the first line, from 1s to 3s
+    Case {
+        name: "effects",
+        dir: "render/rpe",
+        chart: "chart.json",
+        extra: Some("render/effects.json"),
+        times: &[0.5, 2.],
+    },
+];
+
+enum Outcome {
+    Matched,
+    Recorded,
+    Missing,
+    Mismatched { changed: f64, actual: PathBuf },
+}
+
+fn tests_dir() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests"))
+}
+
+fn golden_path(case: &Case, time: f64) -> PathBuf {
+    tests_dir()
+        .join("render/golden")
+        .join(format!("{}-{}.png", case.name, (time * 1000.).round() as u32))
+}
+
+fn solid_texture(color: [u8; 4]) -> SafeTexture {
+    SafeTexture::from(Texture2D::from_rgba8(1, 1, &color))
+}
+
+async fn load(case: &Case) -> Result<(Chart, Resource)> {
+    let info = ChartInfo {
+        chart: case.chart.to_owned(),
+        music: "music.ogg".to_owned(),
+        ..Default::default()
+    };
+    // the music is only needed for its length
+    let music = std::fs::read("assets/cali.ogg").context("failed to read music")?;
+    let mut patches = HashMap::from([("music.ogg".to_owned(), music)]);
+    if let Some(extra) = case.extra {
+        patches.insert("extra.json".to_owned(), std::fs::read(tests_dir().join(extra)).context("failed to read extra")?);
+    }
+    let mut fs: Box<dyn FileSystem> = Box::new(PatchedFileSystem(fs_from_file(&tests_dir().join(case.dir))?, patches));
+    let (chart, ..) = GameScene::load_chart(fs.as_mut(), &info).await?;
+    let config = Config {
+        sample_count: 1,
+        particle: false,
+        ..Default::default()
+    };
+    let no_effect = case.extra.is_none();
+    let res = Resource::new(config, info, fs, None, solid_texture([0, 0, 0, 255]), solid_texture([0, 0, 0, 255]), no_effect)
+        .await
+        .context("failed to create resources (is an audio device available?)")?;
+    Ok((chart, res))
+}
+
+/// Draws the chart as the game does, and reads the frame back. With effects on, the chart is drawn
+/// to the chart target first and copied onto `target` afterwards.
+fn render(chart: &mut Chart, res: &mut Resource, painter: &mut TextPainter, target: RenderTarget, time: f64) -> RgbaImage {
+    let vp = (0, 0, WIDTH as i32, HEIGHT as i32);
+    let asp = WIDTH as f32 / HEIGHT as f32;
+    res.time = time;
+    res.camera.render_target = Some(target);
+    res.update_size(vp);
+    chart.update(res);
+
+    let onto = res.chart_target.as_ref().map_or(target, |it| it.output());
+    let mut ui = Ui::new(painter, Some(vp));
+    set_camera(&Camera2D {
+        zoom: vec2(1., -asp),
+        render_target: Some(onto),
+        ..Default::default()
+    });
+    clear_background(BLACK);
+
+    let gl = unsafe { get_internal_gl() };
+    set_camera(&res.camera);
+    gl.quad_gl.render_pass(Some(onto.render_pass));
+    gl.quad_gl.viewport(res.camera.viewport);
+    let h = 1. / res.aspect_ratio;
+    draw_rectangle(-1., -h, 2., h * 2., Color::new(0., 0., 0., res.info.background_dim));
+    chart.render(&mut ui, res);
+
+    // a plain stand-in for the pause button, to follow the line it's attached to
+    let pause = Rect::new(-0.97, -h + 0.05, 0.045, 0.048);
+    chart.with_element(&mut ui, res, UIElement::Pause, None, (pause.x, pause.y), |ui, color| ui.fill_rect(pause, color));
+
+    if let Some(chart_target) = &res.chart_target {
+        gl.flush();
+        gl.quad_gl.viewport(None);
+        set_camera(&Camera2D {
+            zoom: vec2(1., asp),
+            render_target: Some(target),
+            ..Default::default()
+        });
+        draw_texture_ex(
+            chart_target.output().texture,
+            -1.,
+            -1. / asp,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(vec2(2., 2. / asp)),
+                ..Default::default()
+            },
+        );
+    }
+    gl.flush();
+
+    let data = target.texture.get_texture_data();
+    let mut image = RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes).unwrap();
+    // rows are read back from the bottom
+    image::imageops::flip_vertical_in_place(&mut image);
+    image
+}
+
+/// Returns the ratio of pixels that differ noticeably.
+fn compare(actual: &RgbaImage, expected: &RgbaImage) -> f64 {
+    if actual.dimensions() != expected.dimensions() {
+        return 1.;
+    }
+    let changed = actual
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, e)| a.0.iter().zip(e.0).any(|(a, e)| a.abs_diff(e) > CHANNEL_TOLERANCE))
+        .count();
+    changed as f64 / (actual.width() * actual.height()) as f64
+}
+
+fn check(case: &Case, time: f64, actual: &RgbaImage, update: bool) -> Result<Outcome> {
+    let path = golden_path(case, time);
+    if update {
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        actual.save(&path).with_context(|| format!("failed to save {}", path.display()))?;
+        return Ok(Outcome::Recorded);
+    }
+    if !path.exists() {
+        return Ok(Outcome::Missing);
+    }
+    let expected = image::open(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?
+        .into_rgba8();
+    let changed = compare(actual, &expected);
+    if changed <= PIXEL_TOLERANCE {
+        return Ok(Outcome::Matched);
+    }
+    let actual_path = std::env::temp_dir().join("prpr-render").join(path.file_name().unwrap());
+    std::fs::create_dir_all(actual_path.parent().unwrap())?;
+    actual.save(&actual_path)?;
+    Ok(Outcome::Mismatched {
+        changed,
+        actual: actual_path,
+    })
+}
+
+async fn run() -> Result<bool> {
+    init_assets();
+    let rt = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build()?;
+    let _guard = rt.enter();
+
+    let font = FontArc::try_from_vec(load_file("bold.ttf").await?)?;
+    let mut painter = TextPainter::new(font, None);
+    let target = render_target(WIDTH, HEIGHT);
+    target.texture.set_filter(FilterMode::Nearest);
+    let update = std::env::var("PRPR_UPDATE_GOLDEN").is_ok_and(|it| it == "1");
+
+    let mut passed = true;
+    for case in CASES {
+        let (mut chart, mut res) = load(case).await.with_context(|| format!("failed to load {}", case.name))?;
+        for &time in case.times {
+            let actual = render(&mut chart, &mut res, &mut painter, target, time);
+            let name = golden_path(case, time).file_name().unwrap().to_string_lossy().into_owned();
+            match check(case, time, &actual, update)? {
+                Outcome::Matched => println!("{name} ... ok"),
+                Outcome::Recorded => println!("{name} ... recorded"),
+                Outcome::Missing => {
+                    println!("{name} ... FAILED: no golden image, record it with PRPR_UPDATE_GOLDEN=1");
+                    passed = false;
+                }
+                Outcome::Mismatched { changed, actual } => {
+                    println!("{name} ... FAILED: {:.2}% of the pixels changed, see {}", changed * 100., actual.display());
+                    passed = false;
+                }
+            }
+            next_frame().await;
+        }
+    }
+    Ok(passed)
+}
+
+fn main() {
+    if std::env::var("PRPR_SKIP_RENDER").is_ok_and(|it| it == "1") {
+        println!("skipping render tests as PRPR_SKIP_RENDER is set");
+        return;
+    }
+    if cfg!(target_os = "linux") && std::env::var_os("DISPLAY").is_none() && std::env::var_os("WAYLAND_DISPLAY").is_none() {
+        eprintln!("no display to create a GL context on, run under xvfb-run or set PRPR_SKIP_RENDER=1 to skip");
+        std::process::exit(1);
+    }
+    let conf = Conf {
+        window_title: "prpr render tests".to_owned(),
+        window_width: WIDTH as i32,
+        window_height: HEIGHT as i32,
+        ..Default::default()
+    };
+    macroquad::Window::from_config(conf, async {
+        let code = match run().await {
+            Ok(true) => 0,
+            Ok(false) => 1,
+            Err(err) => {
+                eprintln!("{err:?}");
+                1
+            }
+        };
+        std::process::exit(code);
+    });
+}
@@ -0,0 +1,106 @@
+//! Checks the problems `validate_shader` finds in effect shaders, and that it ignores whatever is
+//! commented out.
+
+use miniquad::UniformType;
+use prpr::{core::validate_shader, fs::fs_from_file, parse::lint_effects};
+use std::path::Path;
+
+const VALID: &str = "#version 100
+precision mediump float;
+
+varying lowp vec2 uv;
+uniform sampler2D screenTexture;
+uniform float strength; // %0.5%
+
+void main() {
+  gl_FragColor = texture2D(screenTexture, uv) * strength;
+}
+";
+
+fn errors(shader: &str, vars: &[(String, UniformType)], textures: &[String]) -> String {
+    validate_shader(shader, vars, textures).unwrap_err().to_string()
+}
+
+#[test]
+fn valid() {
+    validate_shader(VALID, &[("strength".to_owned(), UniformType::Float1)], &[]).unwrap();
+}
+
+#[test]
+fn missing_main_and_precision() {
+    let err = errors("uniform sampler2D screenTexture;\nvoid render() {}\n", &[], &[]);
+    assert!(err.contains("no `void main()` function"), "{err}");
+    assert!(err.contains("no default float precision"), "{err}");
+}
+
+#[test]
+fn brackets() {
+    let err = errors(&VALID.replace("* strength;", "* (strength;"), &[], &[]);
+    assert!(err.contains("line 10: `}` does not match the `(` on line 9"), "{err}");
+
+    let err = errors(&VALID.replace("uv) * strength", "uv] * strength"), &[], &[]);
+    assert!(err.contains("line 9: `]` does not match the `(` on line 9"), "{err}");
+
+    let err = errors(&format!("{VALID}}}\n"), &[], &[]);
+    assert!(err.contains("line 11: `}` was never opened"), "{err}");
+}
+
+#[test]
+fn uniforms() {
+    let err = errors(&VALID.replace("uniform sampler2D screenTexture", "uniform vec2 screenTexture"), &[], &[]);
+    assert!(err.contains("line 5: uniform `screenTexture` is provided by the game as `sampler2D`, but declared as `vec2`"), "{err}");
+
+    let err = errors(VALID, &[("strength".to_owned(), UniformType::Float4)], &[]);
+    assert!(err.contains("line 6: uniform `strength` is given a `vec4` value, but declared as `float`"), "{err}");
+
+    let err = errors(VALID, &[("missing".to_owned(), UniformType::Float1)], &[]);
+    assert!(err.contains("`missing` is given a value, but the shader has no such uniform"), "{err}");
+
+    let err = errors(VALID, &[], &["strength".to_owned(), "mask".to_owned()]);
+    assert!(err.contains("line 6: uniform `strength` is given a texture, but declared as `float`"), "{err}");
+    assert!(err.contains("`mask` is given a texture, but the shader has no such uniform"), "{err}");
+}
+
+#[test]
+fn comments() {
+    // brackets and declarations in comments don't count
+    let shader = VALID.replace("void main() {", "/* uniform float hidden;\n ( [ */\nvoid main() { // )");
+    validate_shader(&shader, &[], &[]).unwrap();
+    let err = errors(&shader, &[("hidden".to_owned(), UniformType::Float1)], &[]);
+    assert!(err.contains("`hidden` is given a value, but the shader has no such uniform"), "{err}");
+
+    // a commented out `main` or precision isn't enough
+    let err = errors(
+        &VALID
+            .replace("precision", "// precision")
+            .replace("void main", "/* void main() */ void start"),
+        &[],
+        &[],
+    );
+    assert!(err.contains("no `void main()` function"), "{err}");
+    assert!(err.contains("no default float precision"), "{err}");
+
+    // line numbers stay the same after block comments spanning lines
+    let err = errors(&format!("/*\n\n*/\n{VALID}"), &[("strength".to_owned(), UniformType::Float2)], &[]);
+    assert!(err.contains("line 9: uniform `strength`"), "{err}");
+}
+
+#[test]
+fn chart_effects() {
+    // the second effect gives the float `factor` of the preset a vec2, and the shader of the third
+    // can't be found, which is left for loading the chart to report
+    let extra = r#"{
+        "bpm": 120,
+        "effects": [
+            { "start": [0, 0, 1], "end": [4, 0, 1], "shader": "grayscale", "vars": { "factor": 0.5 } },
+            { "start": [0, 0, 1], "end": [4, 0, 1], "shader": "grayscale", "vars": { "factor": [1, 1] } },
+            { "start": [0, 0, 1], "end": [4, 0, 1], "shader": "/missing.glsl" }
+        ]
+    }"#;
+    let mut fs = fs_from_file(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let problems = rt.block_on(lint_effects(extra, fs.as_mut())).unwrap();
+    assert_eq!(problems.len(), 1, "{problems:?}");
+    assert!(problems[0].contains("#1"), "{}", problems[0]);
+    assert!(problems[0].contains("uniform `factor` is given a"), "{}", problems[0]);
+}
@@ -0,0 +1,57 @@
+//! Checks that stretched music keeps its length, and sounds at its original pitch when played at
+//! the speed it was stretched for.
+
+use prpr::stretch::keep_pitch;
+use sasa::{AudioClip, Frame};
+
+const SAMPLE_RATE: u32 = 44100;
+
+fn sine(freq: f32, seconds: f32) -> AudioClip {
+    let frames = (0..(SAMPLE_RATE as f32 * seconds) as usize)
+        .map(|i| {
+            let value = (std::f32::consts::TAU * freq * i as f32 / SAMPLE_RATE as f32).sin() * 0.5;
+            Frame(value, value)
+        })
+        .collect();
+    AudioClip::from_raw(frames, SAMPLE_RATE)
+}
+
+/// Estimates the frequency from the rising zero crossings, leaving out the edges.
+fn frequency(clip: &AudioClip) -> f32 {
+    let frames = clip.frames();
+    let middle = &frames[frames.len() / 10..frames.len() * 9 / 10];
+    let crossings = middle.windows(2).filter(|it| it[0].0 < 0. && it[1].0 >= 0.).count();
+    crossings as f32 / (middle.len() as f32 / SAMPLE_RATE as f32)
+}
+
+fn rms(clip: &AudioClip) -> f32 {
+    let frames = clip.frames();
+    (frames.iter().map(|it| it.0 * it.0).sum::<f32>() / frames.len() as f32).sqrt()
+}
+
+#[test]
+fn length() {
+    let clip = sine(440., 1.);
+    for speed in [0.5, 0.75, 1., 1.5, 2.] {
+        assert_eq!(keep_pitch(&clip, speed).frames().len(), clip.frames().len(), "at {speed}x");
+    }
+}
+
+#[test]
+fn pitch() {
+    let clip = sine(440., 2.);
+    for speed in [0.5, 1., 2.] {
+        // the clip is shifted the other way, so that playing it at `speed` brings the pitch back
+        let freq = frequency(&keep_pitch(&clip, speed)) * speed;
+        assert!((freq / 440. - 1.).abs() < 0.02, "{freq}Hz at {speed}x");
+    }
+}
+
+#[test]
+fn no_aliasing() {
+    // shifted up to 36kHz at 0.5x, which doesn't fit under the Nyquist frequency and would fold
+    // back to 8.1kHz without filtering
+    let clip = sine(18000., 1.);
+    let level = rms(&keep_pitch(&clip, 0.5));
+    assert!(level < 0.05, "rms is {level}");
+}